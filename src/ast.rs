@@ -0,0 +1,991 @@
+//! The syntax tree produced by the [`Parser`](crate::parser::Parser).
+//!
+//! The tree mirrors the SQL text as closely as possible, it is lowered into a
+//! [`Query`](crate::query_parser::Query) before being executed.
+
+use std::fmt;
+
+use rust_decimal::Decimal;
+
+use crate::{types::DataType, value::Value};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement {
+    CreateDatabase(String),
+    DropDatabase(String),
+    UseDatabase(String),
+    ShowDatabases,
+    ShowCurrentDatabase,
+    ShowTables,
+    CreateTable {
+        name: String,
+        columns: Vec<ColumnDef>,
+    },
+    DropTable(String),
+    TruncateTable(String),
+    AlterTable {
+        name: String,
+        operation: AlterTableOperation,
+    },
+    Select(Query),
+    Insert {
+        table: String,
+        columns: Option<Vec<String>>,
+        rows: Vec<Vec<Expr>>,
+    },
+    Update {
+        table: String,
+        assignments: Vec<(String, Expr)>,
+        selection: Option<Expr>,
+    },
+    Delete {
+        table: String,
+        selection: Expr,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnDef {
+    pub name: String,
+    pub datatype: DataType,
+    pub not_null: bool,
+    /// Computed for every row inserted without a value for the column.
+    pub default: Option<Expr>,
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AlterTableOperation {
+    AddColumn(ColumnDef),
+    AlterColumn(ColumnDef),
+    DropColumn(String),
+}
+
+/// A `SELECT`, possibly combined with others, along with the clauses that
+/// apply to its final result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub with: Option<With>,
+    pub body: SetExpr,
+    pub order_by: Vec<OrderByExpr>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// `WITH [RECURSIVE] name AS (SELECT ...), ...`, each query can read the
+/// ones before it like a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct With {
+    pub recursive: bool,
+    pub ctes: Vec<Cte>,
+}
+
+/// A common table expression, `name [(col, ...)] AS (SELECT ...)`. The
+/// columns of the query are renamed when a column list is given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cte {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub query: Query,
+}
+
+impl Query {
+    /// Check if the query reads the table `name`, in any of its clauses or
+    /// subqueries. A `WITH` query of the same name isn't told apart.
+    pub fn references(&self, name: &str) -> bool {
+        let ctes = self.with.iter().flat_map(|with| &with.ctes);
+        ctes.map(|cte| &cte.query)
+            .any(|query| query.references(name))
+            || self.body.references(name)
+            || self.order_by.iter().any(|key| key.expr.references(name))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetExpr {
+    Select(Box<Select>),
+    /// `left UNION [ALL] right`, duplicates are removed unless `all` is set.
+    SetOperation {
+        op: SetOperator,
+        all: bool,
+        left: Box<SetExpr>,
+        right: Box<SetExpr>,
+    },
+}
+
+impl SetExpr {
+    /// See [`Query::references`].
+    pub fn references(&self, name: &str) -> bool {
+        match self {
+            SetExpr::Select(select) => select.references(name),
+            SetExpr::SetOperation { left, right, .. } => {
+                left.references(name) || right.references(name)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOperator {
+    Union,
+    Intersect,
+    Except,
+}
+
+impl SetOperator {
+    /// Binding power of the operator, `INTERSECT` binds tighter like in the
+    /// SQL standard.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            SetOperator::Union | SetOperator::Except => 1,
+            SetOperator::Intersect => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Select {
+    pub distinct: bool,
+    pub projection: Vec<SelectItem>,
+    /// Without `FROM` the query reads a single row without columns.
+    pub from: Option<TableWithJoins>,
+    pub selection: Option<Expr>,
+    pub group_by: Vec<Expr>,
+    pub having: Option<Expr>,
+}
+
+impl Select {
+    /// See [`Query::references`].
+    pub fn references(&self, name: &str) -> bool {
+        let joins = self.from.iter().flat_map(|from| &from.joins);
+        let mut relations = self
+            .from
+            .iter()
+            .map(|from| &from.relation)
+            .chain(joins.clone().map(|join| &join.relation));
+        let mut exprs = self
+            .projection
+            .iter()
+            .filter_map(|item| match item {
+                SelectItem::Expr(expr) | SelectItem::ExprWithAlias { expr, .. } => Some(expr),
+                SelectItem::Wildcard | SelectItem::QualifiedWildcard(_) => None,
+            })
+            .chain(joins.filter_map(|join| join.constraint.as_ref()))
+            .chain(&self.selection)
+            .chain(&self.group_by)
+            .chain(&self.having);
+
+        relations.any(|relation| relation.references(name))
+            || exprs.any(|expr| expr.references(name))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectItem {
+    Wildcard,
+    /// Every column of a single table, `t.*`.
+    QualifiedWildcard(String),
+    Expr(Expr),
+    /// `expr [AS] alias`, the alias names the column of the result.
+    ExprWithAlias {
+        expr: Expr,
+        alias: String,
+    },
+}
+
+/// The `FROM` clause, a table joined with any number of other tables from
+/// left to right.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableWithJoins {
+    pub relation: TableFactor,
+    pub joins: Vec<Join>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableFactor {
+    Table {
+        name: String,
+        alias: Option<String>,
+    },
+    /// `(SELECT ...) AS alias`, the alias is required.
+    Derived {
+        subquery: Box<Query>,
+        alias: String,
+    },
+    /// A function returning rows like `JSON_EACH(doc)`, its arguments can
+    /// read the columns of the tables before it.
+    Function {
+        name: String,
+        args: Vec<Expr>,
+        alias: Option<String>,
+    },
+}
+
+impl TableFactor {
+    /// The name columns of the table are qualified with, the alias if any.
+    pub fn qualifier(&self) -> &str {
+        match self {
+            TableFactor::Table { name, alias } => alias.as_ref().unwrap_or(name),
+            TableFactor::Derived { alias, .. } => alias,
+            TableFactor::Function { name, alias, .. } => alias.as_ref().unwrap_or(name),
+        }
+    }
+
+    /// See [`Query::references`].
+    pub fn references(&self, name: &str) -> bool {
+        match self {
+            TableFactor::Table { name: table, .. } => table == name,
+            TableFactor::Derived { subquery, .. } => subquery.references(name),
+            TableFactor::Function { args, .. } => args.iter().any(|arg| arg.references(name)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Join {
+    pub relation: TableFactor,
+    pub kind: JoinKind,
+    /// The `ON` condition, always missing for cross joins.
+    pub constraint: Option<Expr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    Full,
+    Cross,
+}
+
+/// A single `ORDER BY` key, `expr [ASC|DESC] [NULLS FIRST|LAST]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderByExpr {
+    pub expr: Expr,
+    pub desc: bool,
+    /// Nulls are larger than any value when not given, they come last in
+    /// ascending order and first in descending order.
+    pub nulls_first: Option<bool>,
+}
+
+impl OrderByExpr {
+    pub fn nulls_first(&self) -> bool {
+        self.nulls_first.unwrap_or(self.desc)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Identifier(String),
+    /// A column qualified with its table name or alias, `t.col`.
+    QualifiedIdentifier {
+        table: String,
+        column: String,
+    },
+    Literal(Literal),
+    BinaryOp {
+        left: Box<Expr>,
+        op: BinaryOperator,
+        right: Box<Expr>,
+    },
+    UnaryOp {
+        op: UnaryOperator,
+        expr: Box<Expr>,
+    },
+    IsNull {
+        expr: Box<Expr>,
+        negated: bool,
+    },
+    /// A function call like `UPPER(name)` or `COUNT(DISTINCT id)`, the name
+    /// is kept as written.
+    Function {
+        name: String,
+        args: Vec<Expr>,
+        distinct: bool,
+    },
+    /// A window function like `RANK() OVER (PARTITION BY a ORDER BY b)`,
+    /// computed over the rows of the window instead of a group.
+    Window {
+        name: String,
+        args: Vec<Expr>,
+        window: WindowSpec,
+    },
+    /// `CAST(expr AS type)`, converts the value to a column type.
+    Cast {
+        expr: Box<Expr>,
+        data_type: DataType,
+    },
+    /// The `*` of `COUNT(*)`, never valid anywhere else.
+    Wildcard,
+    Nested(Box<Expr>),
+    /// `CASE [operand] WHEN ... THEN ... [ELSE ...] END`, with an operand each
+    /// `WHEN` value is compared to it, otherwise each `WHEN` is a condition.
+    Case {
+        operand: Option<Box<Expr>>,
+        branches: Vec<(Expr, Expr)>,
+        else_result: Option<Box<Expr>>,
+    },
+    /// A subquery returning a single value, `(SELECT MAX(age) FROM user)`.
+    Subquery(Box<Query>),
+    /// `expr [NOT] IN (SELECT ...)`.
+    InSubquery {
+        expr: Box<Expr>,
+        subquery: Box<Query>,
+        negated: bool,
+    },
+    /// `EXISTS (SELECT ...)`, negated with `NOT` like any other condition.
+    Exists(Box<Query>),
+    /// `expr [NOT] IN (a, b, c)`.
+    InList {
+        expr: Box<Expr>,
+        list: Vec<Expr>,
+        negated: bool,
+    },
+    /// `expr [NOT] BETWEEN low AND high`, both bounds are included.
+    Between {
+        expr: Box<Expr>,
+        low: Box<Expr>,
+        high: Box<Expr>,
+        negated: bool,
+    },
+    /// `expr [NOT] LIKE pattern [ESCAPE char]`, `%` matches any text and `_`
+    /// any single char. `ILIKE` ignores case.
+    Like {
+        expr: Box<Expr>,
+        pattern: Box<Expr>,
+        escape: Option<Box<Expr>>,
+        negated: bool,
+        case_insensitive: bool,
+    },
+}
+
+impl Expr {
+    /// Binding power of the postfix `IS [NOT] NULL` and `[NOT] IN`, the same
+    /// as comparisons.
+    pub const POSTFIX_PRECEDENCE: u8 = 4;
+
+    /// Function names computed over a group of rows instead of a single row.
+    pub const AGGREGATES: &'static [&'static str] = &["COUNT", "SUM", "AVG", "MIN", "MAX"];
+
+    pub fn is_aggregate(&self) -> bool {
+        matches!(self, Expr::Function { name, .. }
+            if Expr::AGGREGATES.iter().any(|a| a.eq_ignore_ascii_case(name)))
+    }
+
+    /// The table qualifier and name of a column reference.
+    pub fn as_column(&self) -> Option<(Option<&str>, &str)> {
+        match self {
+            Expr::Identifier(name) => Some((None, name)),
+            Expr::QualifiedIdentifier { table, column } => Some((Some(table), column)),
+            _ => None,
+        }
+    }
+
+    /// Every expression of a `CASE`: the operand, each `WHEN` and `THEN` and
+    /// the `ELSE`, none for any other expression.
+    pub fn case_exprs(&self) -> Vec<&Expr> {
+        let Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } = self
+        else {
+            return vec![];
+        };
+
+        let branches = branches.iter().flat_map(|(when, then)| [when, then]);
+        operand
+            .as_deref()
+            .into_iter()
+            .chain(branches)
+            .chain(else_result.as_deref())
+            .collect()
+    }
+
+    /// The operands of `IN`, `BETWEEN` and `LIKE` starting with the tested
+    /// expression, none for any other expression.
+    pub fn operands(&self) -> Vec<&Expr> {
+        match self {
+            Expr::InList { expr, list, .. } => [expr.as_ref()].into_iter().chain(list).collect(),
+            Expr::Between {
+                expr, low, high, ..
+            } => vec![expr, low, high],
+            Expr::Like {
+                expr,
+                pattern,
+                escape,
+                ..
+            } => [expr.as_ref(), pattern]
+                .into_iter()
+                .chain(escape.as_deref())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Check if the expression or any of its sub expressions is an aggregate.
+    pub fn contains_aggregate(&self) -> bool {
+        self.is_aggregate()
+            || match self {
+                Expr::BinaryOp { left, right, .. } => {
+                    left.contains_aggregate() || right.contains_aggregate()
+                }
+                Expr::UnaryOp { expr, .. }
+                | Expr::IsNull { expr, .. }
+                | Expr::Nested(expr)
+                | Expr::Cast { expr, .. }
+                | Expr::InSubquery { expr, .. } => expr.contains_aggregate(),
+                Expr::Function { args, .. } => args.iter().any(Expr::contains_aggregate),
+                Expr::Case { .. } => self.case_exprs().into_iter().any(Expr::contains_aggregate),
+                Expr::InList { .. } | Expr::Between { .. } | Expr::Like { .. } => {
+                    self.operands().into_iter().any(Expr::contains_aggregate)
+                }
+                // The window function itself is not an aggregate, though its
+                // arguments and keys can be when the query is grouped.
+                Expr::Window { args, window, .. } => args
+                    .iter()
+                    .chain(window.exprs())
+                    .any(Expr::contains_aggregate),
+                // Aggregates of a subquery are computed over its own rows.
+                Expr::Identifier(_)
+                | Expr::QualifiedIdentifier { .. }
+                | Expr::Literal(_)
+                | Expr::Wildcard
+                | Expr::Subquery(_)
+                | Expr::Exists(_) => false,
+            }
+    }
+
+    /// Check if a subquery of the expression reads the table `name`, see
+    /// [`Query::references`].
+    pub fn references(&self, name: &str) -> bool {
+        match self {
+            Expr::Subquery(query) | Expr::Exists(query) => query.references(name),
+            Expr::InSubquery { expr, subquery, .. } => {
+                expr.references(name) || subquery.references(name)
+            }
+            Expr::BinaryOp { left, right, .. } => left.references(name) || right.references(name),
+            Expr::UnaryOp { expr, .. }
+            | Expr::IsNull { expr, .. }
+            | Expr::Nested(expr)
+            | Expr::Cast { expr, .. } => expr.references(name),
+            Expr::Function { args, .. } => args.iter().any(|arg| arg.references(name)),
+            Expr::Case { .. } => self.case_exprs().into_iter().any(|e| e.references(name)),
+            Expr::InList { .. } | Expr::Between { .. } | Expr::Like { .. } => {
+                self.operands().into_iter().any(|e| e.references(name))
+            }
+            Expr::Window { args, window, .. } => args
+                .iter()
+                .chain(window.exprs())
+                .any(|e| e.references(name)),
+            Expr::Identifier(_)
+            | Expr::QualifiedIdentifier { .. }
+            | Expr::Literal(_)
+            | Expr::Wildcard => false,
+        }
+    }
+}
+
+/// `OVER ([PARTITION BY ...] [ORDER BY ...] [ROWS ...])`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowSpec {
+    pub partition_by: Vec<Expr>,
+    pub order_by: Vec<OrderByExpr>,
+    /// Without a frame the window ends with the last row sorted the same as
+    /// the current one, or spans the whole partition without `ORDER BY`.
+    pub frame: Option<WindowFrame>,
+}
+
+impl WindowSpec {
+    /// The partition and sort keys, in this order.
+    pub fn exprs(&self) -> impl Iterator<Item = &Expr> {
+        self.partition_by
+            .iter()
+            .chain(self.order_by.iter().map(|key| &key.expr))
+    }
+}
+
+/// `ROWS BETWEEN start AND end`, counted in rows from the current one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowFrame {
+    pub start: FrameBound,
+    pub end: FrameBound,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameBound {
+    UnboundedPreceding,
+    Preceding(usize),
+    CurrentRow,
+    Following(usize),
+    UnboundedFollowing,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Literal {
+    Number(String),
+    String(String),
+    Boolean(bool),
+    Null,
+}
+
+impl Literal {
+    /// Numbers without a fraction are integers, numbers with an exponent are
+    /// floats and every other number is an exact decimal, like in standard SQL.
+    pub fn value(&self) -> Value {
+        match self {
+            Literal::Number(n) => match n.parse::<i64>() {
+                Ok(v) => Value::Int(v),
+                Err(_) if !n.contains(['e', 'E']) => match Decimal::from_str_exact(n) {
+                    Ok(v) => Value::Decimal(v),
+                    Err(_) => n.parse::<f64>().map(Value::Float).unwrap_or(Value::Null),
+                },
+                Err(_) => n.parse::<f64>().map(Value::Float).unwrap_or(Value::Null),
+            },
+            Literal::String(s) => Value::Text(s.clone()),
+            Literal::Boolean(b) => Value::Bool(*b),
+            Literal::Null => Value::Null,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Eq,
+    NotEq,
+    Gt,
+    Lt,
+    GtEq,
+    LtEq,
+    And,
+    Or,
+    Plus,
+    Minus,
+    Multiply,
+    Divide,
+    Modulo,
+    /// `||`, joins the text of both sides.
+    StringConcat,
+    /// `text REGEXP pattern`, true if the pattern matches any part of the text.
+    Regexp,
+    /// `json -> key`, a member of an object or an element of an array.
+    JsonGet,
+    /// `json ->> key`, like `->` but the member is returned as text.
+    JsonGetText,
+}
+
+impl BinaryOperator {
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::Eq
+                | BinaryOperator::NotEq
+                | BinaryOperator::Gt
+                | BinaryOperator::Lt
+                | BinaryOperator::GtEq
+                | BinaryOperator::LtEq
+        )
+    }
+
+    /// Operators computing a value instead of a condition.
+    pub fn is_arithmetic(&self) -> bool {
+        matches!(
+            self,
+            BinaryOperator::Plus
+                | BinaryOperator::Minus
+                | BinaryOperator::Multiply
+                | BinaryOperator::Divide
+                | BinaryOperator::Modulo
+                | BinaryOperator::StringConcat
+        )
+    }
+
+    /// Binding power of the operator, higher binds tighter.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Or => 1,
+            BinaryOperator::And => 2,
+            BinaryOperator::Eq
+            | BinaryOperator::NotEq
+            | BinaryOperator::Gt
+            | BinaryOperator::Lt
+            | BinaryOperator::GtEq
+            | BinaryOperator::LtEq
+            | BinaryOperator::Regexp => 4,
+            BinaryOperator::StringConcat => 5,
+            BinaryOperator::Plus | BinaryOperator::Minus => 6,
+            BinaryOperator::Multiply | BinaryOperator::Divide | BinaryOperator::Modulo => 7,
+            // Tighter than a unary minus, `-doc->'a'` is `-(doc->'a')`.
+            BinaryOperator::JsonGet | BinaryOperator::JsonGetText => 9,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Not,
+    Minus,
+}
+
+impl UnaryOperator {
+    /// Binding power of the operand, `NOT a = b` is `NOT (a = b)` while
+    /// `-a * b` is `(-a) * b`.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            UnaryOperator::Not => 3,
+            UnaryOperator::Minus => 8,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Identifier(ident) => write!(f, "{}", ident),
+            Expr::QualifiedIdentifier { table, column } => write!(f, "{}.{}", table, column),
+            Expr::Literal(literal) => write!(f, "{}", literal),
+            Expr::BinaryOp { left, op, right } => write!(f, "{} {} {}", left, op, right),
+            Expr::UnaryOp {
+                op: UnaryOperator::Minus,
+                expr,
+            } => write!(f, "-{}", expr),
+            Expr::UnaryOp { op, expr } => write!(f, "{} {}", op, expr),
+            Expr::IsNull { expr, negated } => {
+                write!(f, "{} IS {}NULL", expr, if *negated { "NOT " } else { "" })
+            }
+            Expr::Function {
+                name,
+                args,
+                distinct,
+            } => {
+                let args = args.iter().map(Expr::to_string).collect::<Vec<_>>();
+                let distinct = if *distinct { "DISTINCT " } else { "" };
+                write!(f, "{}({}{})", name, distinct, args.join(", "))
+            }
+            Expr::Window { name, args, window } => {
+                let args = args.iter().map(Expr::to_string).collect::<Vec<_>>();
+                write!(f, "{}({}) OVER ({})", name, args.join(", "), window)
+            }
+            Expr::Cast { expr, data_type } => write!(f, "CAST({} AS {:?})", expr, data_type),
+            Expr::Wildcard => write!(f, "*"),
+            Expr::Nested(expr) => write!(f, "({})", expr),
+            Expr::Case {
+                operand,
+                branches,
+                else_result,
+            } => {
+                write!(f, "CASE")?;
+                if let Some(operand) = operand {
+                    write!(f, " {}", operand)?;
+                }
+                for (when, then) in branches {
+                    write!(f, " WHEN {} THEN {}", when, then)?;
+                }
+                if let Some(else_result) = else_result {
+                    write!(f, " ELSE {}", else_result)?;
+                }
+                write!(f, " END")
+            }
+            Expr::Subquery(query) => write!(f, "({})", query),
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => write!(
+                f,
+                "{} {}IN ({})",
+                expr,
+                if *negated { "NOT " } else { "" },
+                subquery
+            ),
+            Expr::Exists(query) => write!(f, "EXISTS ({})", query),
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let list = list.iter().map(Expr::to_string).collect::<Vec<_>>();
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "{} {}IN ({})", expr, not, list.join(", "))
+            }
+            Expr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                write!(f, "{} {}BETWEEN {} AND {}", expr, not, low, high)
+            }
+            Expr::Like {
+                expr,
+                pattern,
+                escape,
+                negated,
+                case_insensitive,
+            } => {
+                let not = if *negated { "NOT " } else { "" };
+                let like = if *case_insensitive { "ILIKE" } else { "LIKE" };
+                write!(f, "{} {}{} {}", expr, not, like, pattern)?;
+                match escape {
+                    Some(escape) => write!(f, " ESCAPE {}", escape),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(with) = &self.with {
+            write!(f, "{} ", with)?;
+        }
+        write!(f, "{}", self.body)?;
+        if !self.order_by.is_empty() {
+            let keys = self.order_by.iter().map(OrderByExpr::to_string);
+            write!(f, " ORDER BY {}", keys.collect::<Vec<_>>().join(", "))?;
+        }
+        if let Some(limit) = self.limit {
+            write!(f, " LIMIT {}", limit)?;
+        }
+        if let Some(offset) = self.offset {
+            write!(f, " OFFSET {}", offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for With {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ctes = self.ctes.iter().map(Cte::to_string).collect::<Vec<_>>();
+        let recursive = if self.recursive { "RECURSIVE " } else { "" };
+        write!(f, "WITH {}{}", recursive, ctes.join(", "))
+    }
+}
+
+impl fmt::Display for Cte {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.columns.is_empty() {
+            write!(f, " ({})", self.columns.join(", "))?;
+        }
+        write!(f, " AS ({})", self.query)
+    }
+}
+
+impl fmt::Display for SetExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetExpr::Select(select) => write!(f, "{}", select),
+            SetExpr::SetOperation {
+                op,
+                all,
+                left,
+                right,
+            } => write!(
+                f,
+                "{} {}{} {}",
+                left,
+                op,
+                if *all { " ALL" } else { "" },
+                right
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Select {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let projection = self.projection.iter().map(SelectItem::to_string);
+        write!(
+            f,
+            "SELECT {}{}",
+            if self.distinct { "DISTINCT " } else { "" },
+            projection.collect::<Vec<_>>().join(", "),
+        )?;
+        if let Some(from) = &self.from {
+            write!(f, " FROM {}", from)?;
+        }
+        if let Some(selection) = &self.selection {
+            write!(f, " WHERE {}", selection)?;
+        }
+        if !self.group_by.is_empty() {
+            let group_by = self.group_by.iter().map(Expr::to_string);
+            write!(f, " GROUP BY {}", group_by.collect::<Vec<_>>().join(", "))?;
+        }
+        if let Some(having) = &self.having {
+            write!(f, " HAVING {}", having)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for SelectItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectItem::Wildcard => write!(f, "*"),
+            SelectItem::QualifiedWildcard(table) => write!(f, "{}.*", table),
+            SelectItem::Expr(expr) => write!(f, "{}", expr),
+            SelectItem::ExprWithAlias { expr, alias } => write!(f, "{} AS {}", expr, alias),
+        }
+    }
+}
+
+impl fmt::Display for OrderByExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expr)?;
+        if self.desc {
+            write!(f, " DESC")?;
+        }
+        match self.nulls_first {
+            Some(true) => write!(f, " NULLS FIRST"),
+            Some(false) => write!(f, " NULLS LAST"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for WindowSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut clauses = Vec::new();
+        if !self.partition_by.is_empty() {
+            let keys = self.partition_by.iter().map(Expr::to_string);
+            clauses.push(format!(
+                "PARTITION BY {}",
+                keys.collect::<Vec<_>>().join(", ")
+            ));
+        }
+        if !self.order_by.is_empty() {
+            let keys = self.order_by.iter().map(OrderByExpr::to_string);
+            clauses.push(format!("ORDER BY {}", keys.collect::<Vec<_>>().join(", ")));
+        }
+        if let Some(frame) = &self.frame {
+            clauses.push(format!("ROWS BETWEEN {} AND {}", frame.start, frame.end));
+        }
+
+        write!(f, "{}", clauses.join(" "))
+    }
+}
+
+impl fmt::Display for FrameBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameBound::UnboundedPreceding => write!(f, "UNBOUNDED PRECEDING"),
+            FrameBound::Preceding(n) => write!(f, "{} PRECEDING", n),
+            FrameBound::CurrentRow => write!(f, "CURRENT ROW"),
+            FrameBound::Following(n) => write!(f, "{} FOLLOWING", n),
+            FrameBound::UnboundedFollowing => write!(f, "UNBOUNDED FOLLOWING"),
+        }
+    }
+}
+
+impl fmt::Display for TableWithJoins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.relation)?;
+        for join in &self.joins {
+            write!(f, " {} {}", join.kind, join.relation)?;
+            if let Some(constraint) = &join.constraint {
+                write!(f, " ON {}", constraint)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for TableFactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableFactor::Table { name, alias: None } => write!(f, "{}", name),
+            TableFactor::Table {
+                name,
+                alias: Some(alias),
+            } => write!(f, "{} AS {}", name, alias),
+            TableFactor::Derived { subquery, alias } => write!(f, "({}) AS {}", subquery, alias),
+            TableFactor::Function { name, args, alias } => {
+                let args = args.iter().map(Expr::to_string).collect::<Vec<_>>();
+                write!(f, "{}({})", name, args.join(", "))?;
+                match alias {
+                    Some(alias) => write!(f, " AS {}", alias),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for SetOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            SetOperator::Union => "UNION",
+            SetOperator::Intersect => "INTERSECT",
+            SetOperator::Except => "EXCEPT",
+        };
+
+        write!(f, "{}", op)
+    }
+}
+
+impl fmt::Display for JoinKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            JoinKind::Inner => "JOIN",
+            JoinKind::Left => "LEFT JOIN",
+            JoinKind::Right => "RIGHT JOIN",
+            JoinKind::Full => "FULL JOIN",
+            JoinKind::Cross => "CROSS JOIN",
+        };
+
+        write!(f, "{}", kind)
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Number(n) => write!(f, "{}", n),
+            // Quoted back the way the lexer reads it.
+            Literal::String(s) => write!(f, "'{}'", s.replace('\\', "\\\\").replace('\'', "''")),
+            Literal::Boolean(b) => write!(f, "{}", b),
+            Literal::Null => write!(f, "NULL"),
+        }
+    }
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            BinaryOperator::Eq => "=",
+            BinaryOperator::NotEq => "!=",
+            BinaryOperator::Gt => ">",
+            BinaryOperator::Lt => "<",
+            BinaryOperator::GtEq => ">=",
+            BinaryOperator::LtEq => "<=",
+            BinaryOperator::And => "AND",
+            BinaryOperator::Or => "OR",
+            BinaryOperator::Plus => "+",
+            BinaryOperator::Minus => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Modulo => "%",
+            BinaryOperator::StringConcat => "||",
+            BinaryOperator::Regexp => "REGEXP",
+            BinaryOperator::JsonGet => "->",
+            BinaryOperator::JsonGetText => "->>",
+        };
+
+        write!(f, "{}", op)
+    }
+}
+
+impl fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnaryOperator::Not => write!(f, "NOT"),
+            UnaryOperator::Minus => write!(f, "-"),
+        }
+    }
+}
@@ -20,6 +20,7 @@ type DBResult<T> = Result<T, DatabaseError>;
 
 pub struct Database;
 impl Database {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new(name: &str) -> DBResult<()> {
         let base_dir = Path::new(DB_DIR);
         let db_dir = base_dir.join(name);
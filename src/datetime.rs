@@ -0,0 +1,817 @@
+//! Dates, times, timestamps and intervals: parsing ISO-8601 values, the
+//! arithmetic between them and the fields read by `EXTRACT` and `DATE_TRUNC`.
+//!
+//! Timestamps with a time zone are kept in UTC, offsets are applied when they
+//! are parsed.
+
+use std::{cmp::Ordering, fmt, fmt::Write};
+
+use chrono::{
+    DateTime, Datelike, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Timelike, Utc,
+};
+use rust_decimal::prelude::ToPrimitive;
+
+use crate::{
+    ast::BinaryOperator,
+    eval::{EvalError, EvalResult},
+    value::Value,
+};
+
+const MICROS_PER_SECOND: i64 = 1_000_000;
+const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SECOND;
+/// Months are 30 days long when intervals are compared, like in PostgreSQL.
+const DAYS_PER_MONTH: i64 = 30;
+
+/// Both the date and time of a timestamp can be separated with a `T` or a space.
+const TIMESTAMP_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// A span of time. Months and days are kept apart from the rest because their
+/// length depends on the date they are added to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Interval {
+    pub months: i32,
+    pub days: i32,
+    pub micros: i64,
+}
+
+impl Interval {
+    /// Parse `1 year 2 months 3 days 04:05:06`, units can be plural or
+    /// abbreviated and a trailing `ago` negates the interval. ISO-8601
+    /// durations like `P1Y2M3DT4H5M6S` are accepted too.
+    pub fn parse(raw: &str) -> Option<Interval> {
+        let raw = raw.trim();
+        if let Some(duration) = raw.strip_prefix(['P', 'p']) {
+            return Interval::parse_iso(duration);
+        }
+
+        let mut interval = Builder::default();
+        let mut words = raw.split_whitespace().peekable();
+        let mut parsed = false;
+        while let Some(word) = words.next() {
+            if word.contains(':') {
+                interval.add_micros(parse_clock(word)? as f64)?;
+            } else if word.eq_ignore_ascii_case("ago") && words.peek().is_none() {
+                return interval.build()?.checked_neg();
+            } else {
+                // The unit can follow the number without a space, like `10d`.
+                let split = word.find(|c: char| c.is_alphabetic()).unwrap_or(word.len());
+                let (number, unit) = match word.split_at(split) {
+                    (number, "") => (number, words.next()?),
+                    split => split,
+                };
+                interval.add(number.parse().ok()?, unit)?;
+            }
+            parsed = true;
+        }
+
+        match parsed {
+            true => interval.build(),
+            false => None,
+        }
+    }
+
+    /// The part of an ISO-8601 duration after the `P`.
+    fn parse_iso(duration: &str) -> Option<Interval> {
+        let mut interval = Builder::default();
+        let mut time = false;
+        let mut number = String::new();
+        for c in duration.chars() {
+            match c.to_ascii_uppercase() {
+                'T' if !time && number.is_empty() => time = true,
+                c if c.is_ascii_digit() || matches!(c, '.' | '-' | '+') => number.push(c),
+                unit => {
+                    let unit = match (unit, time) {
+                        ('Y', false) => "year",
+                        ('M', false) => "month",
+                        ('W', false) => "week",
+                        ('D', false) => "day",
+                        ('H', true) => "hour",
+                        ('M', true) => "minute",
+                        ('S', true) => "second",
+                        _ => return None,
+                    };
+                    interval.add(number.parse().ok()?, unit)?;
+                    number.clear();
+                }
+            }
+        }
+
+        match number.is_empty() && duration.len() > usize::from(time) {
+            true => interval.build(),
+            false => None,
+        }
+    }
+
+    pub fn checked_neg(&self) -> Option<Interval> {
+        Some(Interval {
+            months: self.months.checked_neg()?,
+            days: self.days.checked_neg()?,
+            micros: self.micros.checked_neg()?,
+        })
+    }
+
+    pub fn checked_add(&self, other: &Interval) -> Option<Interval> {
+        Some(Interval {
+            months: self.months.checked_add(other.months)?,
+            days: self.days.checked_add(other.days)?,
+            micros: self.micros.checked_add(other.micros)?,
+        })
+    }
+
+    /// Fractions of a month or a day spill into the smaller units.
+    pub fn checked_mul(&self, factor: f64) -> Option<Interval> {
+        let mut interval = Builder::default();
+        interval.add_months(self.months as f64 * factor)?;
+        interval.add_days(self.days as f64 * factor)?;
+        interval.add_micros(self.micros as f64 * factor)?;
+        interval.build()
+    }
+
+    /// The length of the interval in microseconds, months are 30 days long.
+    pub fn total_micros(&self) -> i128 {
+        let days = self.months as i128 * DAYS_PER_MONTH as i128 + self.days as i128;
+        days * MICROS_PER_DAY as i128 + self.micros as i128
+    }
+
+    /// The length of the interval in seconds, like `EXTRACT(EPOCH ...)`.
+    pub fn seconds(&self) -> f64 {
+        self.total_micros() as f64 / MICROS_PER_SECOND as f64
+    }
+}
+
+impl Ord for Interval {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Ties are broken by the fields so only equal intervals are equal.
+        self.total_micros()
+            .cmp(&other.total_micros())
+            .then_with(|| (self.months, self.days).cmp(&(other.months, other.days)))
+    }
+}
+
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |n: i32| if n == 1 { "" } else { "s" };
+        let mut parts = Vec::new();
+        let (years, months) = (self.months / 12, self.months % 12);
+        for (n, unit) in [(years, "year"), (months, "month"), (self.days, "day")] {
+            if n != 0 {
+                parts.push(format!("{} {}{}", n, unit, plural(n)));
+            }
+        }
+
+        if self.micros != 0 || parts.is_empty() {
+            let sign = if self.micros < 0 { "-" } else { "" };
+            let micros = self.micros.unsigned_abs();
+            let seconds = micros / MICROS_PER_SECOND as u64;
+            let mut clock = format!(
+                "{}{:02}:{:02}:{:02}",
+                sign,
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            );
+            let fraction = micros % MICROS_PER_SECOND as u64;
+            if fraction != 0 {
+                write!(clock, ".{:06}", fraction)?;
+                clock.truncate(clock.trim_end_matches('0').len());
+            }
+            parts.push(clock);
+        }
+
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Sums the parts of an interval as they are parsed, fractions of a unit are
+/// carried to the smaller ones.
+#[derive(Default)]
+struct Builder {
+    months: i64,
+    days: i64,
+    micros: f64,
+}
+
+impl Builder {
+    fn add(&mut self, n: f64, unit: &str) -> Option<()> {
+        match unit.to_lowercase().as_str() {
+            "y" | "yr" | "yrs" | "year" | "years" => self.add_months(n * 12.0),
+            "mon" | "mons" | "month" | "months" => self.add_months(n),
+            "w" | "week" | "weeks" => self.add_days(n * 7.0),
+            "d" | "day" | "days" => self.add_days(n),
+            "h" | "hr" | "hrs" | "hour" | "hours" => {
+                self.add_micros(n * 3600.0 * MICROS_PER_SECOND as f64)
+            }
+            "m" | "min" | "mins" | "minute" | "minutes" => {
+                self.add_micros(n * 60.0 * MICROS_PER_SECOND as f64)
+            }
+            "s" | "sec" | "secs" | "second" | "seconds" => {
+                self.add_micros(n * MICROS_PER_SECOND as f64)
+            }
+            "ms" | "millisecond" | "milliseconds" => self.add_micros(n * 1000.0),
+            "us" | "microsecond" | "microseconds" => self.add_micros(n),
+            _ => None,
+        }
+    }
+
+    fn add_months(&mut self, months: f64) -> Option<()> {
+        self.months += whole(months)?;
+        self.add_days(months.fract() * DAYS_PER_MONTH as f64)
+    }
+
+    fn add_days(&mut self, days: f64) -> Option<()> {
+        self.days += whole(days)?;
+        self.add_micros(days.fract() * MICROS_PER_DAY as f64)
+    }
+
+    fn add_micros(&mut self, micros: f64) -> Option<()> {
+        self.micros += micros;
+        Some(())
+    }
+
+    fn build(&self) -> Option<Interval> {
+        Some(Interval {
+            months: self.months.try_into().ok()?,
+            days: self.days.try_into().ok()?,
+            micros: whole(self.micros.round())?,
+        })
+    }
+}
+
+/// The integer part of a number, `None` if it doesn't fit.
+fn whole(n: f64) -> Option<i64> {
+    let n = n.trunc();
+    (n.is_finite() && n.abs() < i64::MAX as f64).then_some(n as i64)
+}
+
+/// `[-]HH:MM[:SS[.ffffff]]` as microseconds.
+fn parse_clock(clock: &str) -> Option<i64> {
+    let (sign, clock) = match clock.strip_prefix('-') {
+        Some(clock) => (-1.0, clock),
+        None => (1.0, clock),
+    };
+    let mut parts = clock.split(':');
+    let hours = parts.next()?.parse::<u32>().ok()? as f64;
+    let minutes = parts.next()?.parse::<u32>().ok()? as f64;
+    let seconds = match parts.next() {
+        Some(seconds) => seconds.parse::<f64>().ok().filter(|s| *s >= 0.0)?,
+        None => 0.0,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let seconds = hours * 3600.0 + minutes * 60.0 + seconds;
+    whole((sign * seconds * MICROS_PER_SECOND as f64).round())
+}
+
+pub fn parse_date(raw: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(raw.trim(), "%Y-%m-%d").ok()
+}
+
+pub fn parse_time(raw: &str) -> Option<NaiveTime> {
+    ["%H:%M:%S%.f", "%H:%M"]
+        .iter()
+        .find_map(|format| NaiveTime::parse_from_str(raw.trim(), format).ok())
+}
+
+/// A timestamp without a time zone, a date alone is at midnight.
+pub fn parse_timestamp(raw: &str) -> Option<NaiveDateTime> {
+    let raw = raw.trim();
+    TIMESTAMP_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())
+        .or_else(|| Some(parse_date(raw)?.and_time(NaiveTime::MIN)))
+}
+
+/// A timestamp with an optional UTC offset like `Z`, `+02` or `-05:30`,
+/// timestamps without one are in UTC.
+pub fn parse_timestamptz(raw: &str) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+    let (timestamp, offset) = match raw.strip_suffix(['Z', 'z']) {
+        Some(timestamp) => (timestamp, 0),
+        // The offset follows the time, dates have dashes too.
+        None => match raw
+            .rfind(['+', '-'])
+            .filter(|pos| raw[..*pos].contains(':'))
+        {
+            Some(pos) => (&raw[..pos], parse_offset(&raw[pos..])?),
+            None => (raw, 0),
+        },
+    };
+
+    let timestamp = parse_timestamp(timestamp)?;
+    let utc = timestamp.checked_sub_signed(TimeDelta::seconds(offset))?;
+    Some(utc.and_utc())
+}
+
+/// `+HH`, `+HHMM` or `+HH:MM` as seconds.
+fn parse_offset(offset: &str) -> Option<i64> {
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let digits = offset[1..].replace(':', "");
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i64>().ok()?, 0),
+        4 => (digits[..2].parse().ok()?, digits[2..].parse().ok()?),
+        _ => return None,
+    };
+
+    (hours < 24 && minutes < 60).then_some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Add an interval to a timestamp, months first then days then the rest.
+fn add_interval(timestamp: NaiveDateTime, interval: &Interval) -> Option<NaiveDateTime> {
+    let months = Months::new(interval.months.unsigned_abs());
+    let timestamp = match interval.months < 0 {
+        true => timestamp.checked_sub_months(months)?,
+        false => timestamp.checked_add_months(months)?,
+    };
+
+    timestamp
+        .checked_add_signed(TimeDelta::try_days(interval.days as i64)?)?
+        .checked_add_signed(TimeDelta::microseconds(interval.micros))
+}
+
+/// The difference between two timestamps in days and microseconds.
+fn difference(a: NaiveDateTime, b: NaiveDateTime) -> Option<Interval> {
+    let micros = a.signed_duration_since(b).num_microseconds()?;
+    Some(Interval {
+        months: 0,
+        days: (micros / MICROS_PER_DAY).try_into().ok()?,
+        micros: micros % MICROS_PER_DAY,
+    })
+}
+
+/// Apply an arithmetic operator to dates, times and intervals. Returns `None`
+/// when the operator doesn't apply to the values.
+pub fn arithmetic(op: BinaryOperator, left: &Value, right: &Value) -> Option<EvalResult<Value>> {
+    use BinaryOperator::{Divide, Minus, Multiply, Plus};
+
+    let midnight = |date: &NaiveDate| date.and_time(NaiveTime::MIN);
+    let shift = |timestamp: NaiveDateTime, interval: &Interval, op| match op {
+        Plus => add_interval(timestamp, interval),
+        _ => add_interval(timestamp, &interval.checked_neg()?),
+    };
+    let number = |value: &Value| match value {
+        Value::Int(v) => Some(*v as f64),
+        Value::Float(v) => Some(*v),
+        Value::Decimal(v) => v.to_f64(),
+        _ => None,
+    };
+
+    let value = match (op, left, right) {
+        (Plus, Value::Date(date), Value::Int(days))
+        | (Plus, Value::Int(days), Value::Date(date)) => TimeDelta::try_days(*days)
+            .and_then(|days| date.checked_add_signed(days))
+            .map(Value::Date),
+        (Minus, Value::Date(date), Value::Int(days)) => TimeDelta::try_days(*days)
+            .and_then(|days| date.checked_sub_signed(days))
+            .map(Value::Date),
+        (Minus, Value::Date(a), Value::Date(b)) => Some(Value::Int((*a - *b).num_days())),
+        (Plus, Value::Date(date), Value::Time(time))
+        | (Plus, Value::Time(time), Value::Date(date)) => {
+            Some(Value::Timestamp(date.and_time(*time)))
+        }
+        (Plus | Minus, Value::Date(date), Value::Interval(interval)) => {
+            shift(midnight(date), interval, op).map(Value::Timestamp)
+        }
+        (Plus, Value::Interval(interval), Value::Date(date)) => {
+            shift(midnight(date), interval, op).map(Value::Timestamp)
+        }
+        (Plus | Minus, Value::Timestamp(timestamp), Value::Interval(interval))
+        | (Plus, Value::Interval(interval), Value::Timestamp(timestamp)) => {
+            shift(*timestamp, interval, op).map(Value::Timestamp)
+        }
+        (Plus | Minus, Value::TimestampTz(timestamp), Value::Interval(interval))
+        | (Plus, Value::Interval(interval), Value::TimestampTz(timestamp)) => {
+            shift(timestamp.naive_utc(), interval, op).map(|t| Value::TimestampTz(t.and_utc()))
+        }
+        // Times wrap around midnight, days and months are ignored.
+        (Plus | Minus, Value::Time(time), Value::Interval(interval))
+        | (Plus, Value::Interval(interval), Value::Time(time)) => {
+            let micros = TimeDelta::microseconds(interval.micros);
+            let micros = if op == Minus { -micros } else { micros };
+            Some(Value::Time(time.overflowing_add_signed(micros).0))
+        }
+        // Dates are at midnight and timestamps without a time zone in UTC.
+        (Minus, a, b) if timestamp(a).is_some() && timestamp(b).is_some() => {
+            difference(timestamp(a)?, timestamp(b)?).map(Value::Interval)
+        }
+        (Minus, Value::Time(a), Value::Time(b)) => (*a - *b).num_microseconds().map(|micros| {
+            Value::Interval(Interval {
+                micros,
+                ..Interval::default()
+            })
+        }),
+        (Plus, Value::Interval(a), Value::Interval(b)) => a.checked_add(b).map(Value::Interval),
+        (Minus, Value::Interval(a), Value::Interval(b)) => b
+            .checked_neg()
+            .and_then(|b| a.checked_add(&b))
+            .map(Value::Interval),
+        (Multiply, Value::Interval(interval), n) | (Multiply, n, Value::Interval(interval)) => {
+            interval.checked_mul(number(n)?).map(Value::Interval)
+        }
+        (Divide, Value::Interval(interval), n) => {
+            interval.checked_mul(1.0 / number(n)?).map(Value::Interval)
+        }
+        _ => return None,
+    };
+
+    Some(value.ok_or_else(|| EvalError::Overflow(format!("{} {} {}", left, op, right))))
+}
+
+/// The timestamp of a date, a timestamp or a timestamp in UTC, dates are at midnight.
+fn timestamp(value: &Value) -> Option<NaiveDateTime> {
+    match value {
+        Value::Date(date) => Some(date.and_time(NaiveTime::MIN)),
+        Value::Timestamp(timestamp) => Some(*timestamp),
+        Value::TimestampTz(timestamp) => Some(timestamp.naive_utc()),
+        _ => None,
+    }
+}
+
+/// A field of a date, time, timestamp or interval, like `EXTRACT`.
+pub fn extract(field: &str, value: &Value) -> EvalResult<Value> {
+    let invalid = || EvalError::InvalidArgument(format!("`{}` is not a field of {}", field, value));
+    let field = field.to_lowercase();
+    let field = field.strip_suffix('s').unwrap_or(&field);
+
+    let seconds = |time: NaiveTime| time.second() as f64 + time.nanosecond() as f64 / 1e9;
+    let n = match (value, field) {
+        (Value::Interval(interval), field) => {
+            let micros = interval.micros;
+            match field {
+                "year" => (interval.months / 12) as f64,
+                "month" => (interval.months % 12) as f64,
+                "day" => interval.days as f64,
+                "hour" => (micros / 3600 / MICROS_PER_SECOND) as f64,
+                "minute" => (micros / 60 / MICROS_PER_SECOND % 60) as f64,
+                "second" => (micros % (60 * MICROS_PER_SECOND)) as f64 / MICROS_PER_SECOND as f64,
+                "epoch" => interval.seconds(),
+                _ => return Err(invalid()),
+            }
+        }
+        (Value::Time(time), field) => match field {
+            "hour" => time.hour() as f64,
+            "minute" => time.minute() as f64,
+            "second" => seconds(*time),
+            "epoch" => time.num_seconds_from_midnight() as f64 + time.nanosecond() as f64 / 1e9,
+            _ => return Err(invalid()),
+        },
+        (value, field) => {
+            let timestamp = timestamp(value).ok_or_else(invalid)?;
+            match field {
+                "year" => timestamp.year() as f64,
+                "quarter" => (timestamp.month0() / 3 + 1) as f64,
+                "month" => timestamp.month() as f64,
+                "week" => timestamp.iso_week().week() as f64,
+                "day" => timestamp.day() as f64,
+                "dow" => timestamp.weekday().num_days_from_sunday() as f64,
+                "isodow" => timestamp.weekday().number_from_monday() as f64,
+                "doy" => timestamp.ordinal() as f64,
+                "hour" => timestamp.hour() as f64,
+                "minute" => timestamp.minute() as f64,
+                "second" => seconds(timestamp.time()),
+                "epoch" => timestamp.and_utc().timestamp_micros() as f64 / 1e6,
+                _ => return Err(invalid()),
+            }
+        }
+    };
+
+    Ok(Value::Float(n))
+}
+
+/// Truncate a date or timestamp to the start of the given field, like
+/// `DATE_TRUNC`. The result has the same type as the value.
+pub fn trunc(field: &str, value: &Value) -> EvalResult<Value> {
+    let invalid =
+        || EvalError::InvalidArgument(format!("cannot truncate {} to `{}`", value, field));
+    let timestamp = timestamp(value).ok_or_else(invalid)?;
+    let date = timestamp.date();
+
+    let field = field.to_lowercase();
+    let truncated = match field.strip_suffix('s').unwrap_or(&field) {
+        "second" => timestamp.with_nanosecond(0),
+        "minute" => timestamp.with_nanosecond(0).and_then(|t| t.with_second(0)),
+        "hour" => date.and_hms_opt(timestamp.hour(), 0, 0),
+        "day" => Some(date.and_time(NaiveTime::MIN)),
+        "week" => {
+            let days = date.weekday().num_days_from_monday();
+            date.checked_sub_signed(TimeDelta::days(days as i64))
+                .map(|date| date.and_time(NaiveTime::MIN))
+        }
+        "month" => date.with_day(1).map(|date| date.and_time(NaiveTime::MIN)),
+        "quarter" => NaiveDate::from_ymd_opt(date.year(), date.month0() / 3 * 3 + 1, 1)
+            .map(|date| date.and_time(NaiveTime::MIN)),
+        "year" => {
+            NaiveDate::from_ymd_opt(date.year(), 1, 1).map(|date| date.and_time(NaiveTime::MIN))
+        }
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+
+    Ok(match value {
+        Value::Date(_) => Value::Date(truncated.date()),
+        Value::TimestampTz(_) => Value::TimestampTz(truncated.and_utc()),
+        _ => Value::Timestamp(truncated),
+    })
+}
+
+/// Translate a PostgreSQL style pattern like `YYYY-MM-DD HH24:MI` to the
+/// format understood by chrono, text in double quotes is copied as is.
+fn chrono_format(pattern: &str) -> String {
+    const PATTERNS: &[(&str, &str)] = &[
+        ("MONTH", "%B"),
+        ("YYYY", "%Y"),
+        ("HH24", "%H"),
+        ("HH12", "%I"),
+        ("DDD", "%j"),
+        ("MON", "%b"),
+        ("DAY", "%A"),
+        ("YY", "%y"),
+        ("MM", "%m"),
+        ("DD", "%d"),
+        ("DY", "%a"),
+        ("HH", "%I"),
+        ("MI", "%M"),
+        ("SS", "%S"),
+        ("MS", "%3f"),
+        ("US", "%6f"),
+        ("AM", "%p"),
+        ("PM", "%p"),
+        ("TZ", "%Z"),
+        ("OF", "%:z"),
+    ];
+
+    let mut format = String::new();
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        if c == '"' {
+            let end = rest[1..].find('"').map_or(rest.len(), |end| end + 2);
+            format.push_str(
+                &rest[1..end.min(rest.len())]
+                    .trim_end_matches('"')
+                    .replace('%', "%%"),
+            );
+            rest = &rest[end.min(rest.len())..];
+            continue;
+        }
+
+        let upper = rest.to_uppercase();
+        match PATTERNS
+            .iter()
+            .find(|(pattern, _)| upper.starts_with(pattern))
+        {
+            Some((pattern, item)) => {
+                format.push_str(item);
+                rest = &rest[pattern.len()..];
+            }
+            None => {
+                match c {
+                    '%' => format.push_str("%%"),
+                    c => format.push(c),
+                }
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    format
+}
+
+/// Format a date, time or timestamp with a PostgreSQL style pattern, like `TO_CHAR`.
+pub fn format(value: &Value, pattern: &str) -> EvalResult<Value> {
+    let format = chrono_format(pattern);
+    let mut text = String::new();
+    let written = match (value, timestamp(value)) {
+        (Value::Time(time), _) => write!(text, "{}", time.format(&format)),
+        (Value::TimestampTz(timestamp), _) => write!(text, "{}", timestamp.format(&format)),
+        (_, Some(timestamp)) => write!(text, "{}", timestamp.format(&format)),
+        _ => {
+            return Err(EvalError::InvalidArgument(format!(
+                "cannot format {}",
+                value
+            )))
+        }
+    };
+
+    match written {
+        Ok(()) => Ok(Value::Text(text)),
+        Err(_) => Err(EvalError::InvalidArgument(format!(
+            "pattern `{}` doesn't apply to {}",
+            pattern, value
+        ))),
+    }
+}
+
+/// Parse text with a PostgreSQL style pattern, like `TO_DATE` and
+/// `TO_TIMESTAMP`. Patterns without a time give a timestamp at midnight.
+pub fn parse_with(text: &str, pattern: &str, date: bool) -> EvalResult<Value> {
+    let format = chrono_format(pattern);
+    let timestamp = NaiveDateTime::parse_from_str(text, &format).or_else(|_| {
+        NaiveDate::parse_from_str(text, &format).map(|date| date.and_time(NaiveTime::MIN))
+    });
+
+    match timestamp {
+        Ok(timestamp) if date => Ok(Value::Date(timestamp.date())),
+        Ok(timestamp) => Ok(Value::TimestampTz(timestamp.and_utc())),
+        Err(err) => Err(EvalError::InvalidArgument(format!(
+            "`{}` doesn't match `{}`: {}",
+            text, pattern, err
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+
+    use super::{arithmetic, extract, format, parse_timestamp, parse_timestamptz, trunc, Interval};
+    use crate::{ast::BinaryOperator, value::Value};
+
+    fn interval(months: i32, days: i32, seconds: f64) -> Interval {
+        Interval {
+            months,
+            days,
+            micros: (seconds * 1e6) as i64,
+        }
+    }
+
+    fn timestamp(raw: &str) -> Value {
+        Value::Timestamp(parse_timestamp(raw).unwrap())
+    }
+
+    #[test]
+    fn parse_intervals() {
+        let parse = |raw| Interval::parse(raw);
+
+        assert_eq!(parse("1 year 2 months 3 days"), Some(interval(14, 3, 0.0)));
+        assert_eq!(parse("2 hours 30 min"), Some(interval(0, 0, 9000.0)));
+        assert_eq!(parse("1 day 04:05:06.5"), Some(interval(0, 1, 14706.5)));
+        assert_eq!(parse("1.5 days"), Some(interval(0, 1, 43200.0)));
+        assert_eq!(parse("10d"), Some(interval(0, 10, 0.0)));
+        assert_eq!(parse("3 days ago"), Some(interval(0, -3, 0.0)));
+        assert_eq!(parse("-01:00"), Some(interval(0, 0, -3600.0)));
+        assert_eq!(parse("P1Y2M3DT4H5M6S"), Some(interval(14, 3, 14706.0)));
+        assert_eq!(parse("PT0.5S"), Some(interval(0, 0, 0.5)));
+        assert_eq!(parse("P2W"), Some(interval(0, 14, 0.0)));
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("P"), None);
+        assert_eq!(parse("1 fortnight"), None);
+        assert_eq!(parse("1 day 2"), None);
+    }
+
+    #[test]
+    fn display_intervals() {
+        assert_eq!(interval(14, 3, 0.0).to_string(), "1 year 2 months 3 days");
+        assert_eq!(
+            interval(1, -1, 3600.5).to_string(),
+            "1 month -1 days 01:00:00.5"
+        );
+        assert_eq!(interval(0, 0, -90.0).to_string(), "-00:01:30");
+        assert_eq!(Interval::default().to_string(), "00:00:00");
+
+        let interval = interval(25, 1, 61.25);
+        assert_eq!(Interval::parse(&interval.to_string()), Some(interval));
+    }
+
+    #[test]
+    fn compare_intervals() {
+        assert!(interval(1, 0, 0.0) > interval(0, 29, 0.0));
+        assert!(interval(0, 1, 0.0) < interval(0, 0, 90000.0));
+        assert_ne!(interval(1, 0, 0.0), interval(0, 30, 0.0));
+    }
+
+    #[test]
+    fn parse_timestamps() {
+        let utc = |raw| parse_timestamptz(raw).map(|t| t.naive_utc().to_string());
+
+        assert_eq!(
+            parse_timestamp("2024-02-29T10:30:00.25").map(|t| t.to_string()),
+            Some("2024-02-29 10:30:00.250".into())
+        );
+        assert_eq!(
+            parse_timestamp("2024-02-29").map(|t| t.to_string()),
+            Some("2024-02-29 00:00:00".into())
+        );
+        assert_eq!(parse_timestamp("2023-02-29"), None);
+        assert_eq!(
+            utc("2024-01-01T10:00:00Z"),
+            Some("2024-01-01 10:00:00".into())
+        );
+        assert_eq!(
+            utc("2024-01-01 10:00:00+02"),
+            Some("2024-01-01 08:00:00".into())
+        );
+        assert_eq!(
+            utc("2024-01-01 01:00-05:30"),
+            Some("2024-01-01 06:30:00".into())
+        );
+        assert_eq!(utc("2024-01-01"), Some("2024-01-01 00:00:00".into()));
+        assert_eq!(utc("2024-01-01 10:00:00+2"), None);
+    }
+
+    #[test]
+    fn date_arithmetic() {
+        use BinaryOperator::{Minus, Multiply, Plus};
+        let date = |y, m, d| Value::Date(NaiveDate::from_ymd_opt(y, m, d).unwrap());
+        let eval = |op, left: &Value, right: &Value| arithmetic(op, left, right).unwrap().unwrap();
+
+        assert_eq!(
+            eval(Plus, &date(2024, 2, 28), &Value::Int(2)),
+            date(2024, 3, 1)
+        );
+        assert_eq!(
+            eval(Minus, &date(2024, 3, 1), &date(2024, 2, 1)),
+            Value::Int(29)
+        );
+        assert_eq!(
+            eval(
+                Plus,
+                &date(2024, 1, 31),
+                &Value::Interval(interval(1, 0, 0.0))
+            ),
+            timestamp("2024-02-29")
+        );
+        assert_eq!(
+            eval(
+                Minus,
+                &timestamp("2024-03-01 12:00"),
+                &timestamp("2024-02-28 06:30")
+            ),
+            Value::Interval(interval(0, 2, 19800.0))
+        );
+        assert_eq!(
+            eval(
+                Plus,
+                &Value::Time(NaiveTime::from_hms_opt(23, 0, 0).unwrap()),
+                &Value::Interval(interval(0, 0, 7200.0))
+            ),
+            Value::Time(NaiveTime::from_hms_opt(1, 0, 0).unwrap())
+        );
+        assert_eq!(
+            eval(
+                Multiply,
+                &Value::Interval(interval(1, 1, 0.0)),
+                &Value::Float(1.5)
+            ),
+            Value::Interval(interval(1, 16, 43200.0))
+        );
+        assert!(arithmetic(Plus, &date(2024, 1, 1), &date(2024, 1, 1)).is_none());
+        assert!(arithmetic(Plus, &date(2024, 1, 1), &Value::Int(i64::MAX))
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn extract_and_truncate() {
+        let ts = timestamp("2024-05-15 13:45:30.5");
+
+        assert_eq!(extract("year", &ts).unwrap(), Value::Float(2024.0));
+        assert_eq!(extract("QUARTER", &ts).unwrap(), Value::Float(2.0));
+        assert_eq!(extract("dow", &ts).unwrap(), Value::Float(3.0));
+        assert_eq!(extract("second", &ts).unwrap(), Value::Float(30.5));
+        assert_eq!(
+            extract("epoch", &timestamp("1970-01-02")).unwrap(),
+            Value::Float(86400.0)
+        );
+        assert_eq!(
+            extract("hours", &Value::Interval(interval(0, 1, 7260.0))).unwrap(),
+            Value::Float(2.0)
+        );
+        assert!(extract("century", &ts).is_err());
+
+        assert_eq!(trunc("month", &ts).unwrap(), timestamp("2024-05-01"));
+        assert_eq!(trunc("week", &ts).unwrap(), timestamp("2024-05-13"));
+        assert_eq!(trunc("quarter", &ts).unwrap(), timestamp("2024-04-01"));
+        assert_eq!(trunc("hour", &ts).unwrap(), timestamp("2024-05-15 13:00"));
+        assert!(trunc("hour", &Value::Int(1)).is_err());
+    }
+
+    #[test]
+    fn format_with_patterns() {
+        let ts = timestamp("2024-05-07 13:05:09");
+
+        assert_eq!(
+            format(&ts, "YYYY-MM-DD HH24:MI:SS").unwrap(),
+            Value::Text("2024-05-07 13:05:09".into())
+        );
+        assert_eq!(
+            format(&ts, "Day, DD Mon YY HH12 AM \"at\" %").unwrap(),
+            Value::Text("Tuesday, 07 May 24 01 PM at %".into())
+        );
+        assert!(format(&Value::Time(NaiveTime::MIN), "YYYY").is_err());
+    }
+}
@@ -0,0 +1,2052 @@
+//! Evaluates [expressions](Expr) against the rows of a query.
+//!
+//! Columns carry their [`DataType`] so values are compared the way the table
+//! declares them, literals are untyped and take the type of the other side of
+//! a comparison.
+
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use rust_decimal::{prelude::ToPrimitive, Decimal, RoundingStrategy};
+use thiserror::Error;
+
+use crate::{
+    ast::{BinaryOperator, Expr, Literal, Query, UnaryOperator},
+    datetime,
+    executor::{ExecutorError, ResultSet},
+    functions, json,
+    table::Row,
+    types::{DataType, DataTypesErr},
+    value::Value,
+};
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Error)]
+pub enum EvalError {
+    #[error("Column not found")]
+    ColNotFound(String),
+    #[error("Ambiguous column")]
+    AmbiguousCol(String),
+    #[error("Types error")]
+    TypeErr(#[from] DataTypesErr),
+    #[error("Unknown function")]
+    UnknownFunction(String),
+    #[error("Invalid argument")]
+    InvalidArgument(String),
+    #[error("Aggregate not allowed here")]
+    MisplacedAggregate(String),
+    #[error("Window function not allowed here")]
+    MisplacedWindow(String),
+    #[error("Integer overflow")]
+    Overflow(String),
+    #[error("Division by zero")]
+    DivisionByZero(String),
+    #[error("Subquery error")]
+    SubqueryErr(Box<ExecutorError>),
+}
+
+pub type EvalResult<T> = Result<T, EvalError>;
+
+/// A value along with the type of the column it comes from, if any.
+pub type Operand<'a> = (Value, Option<&'a DataType>);
+
+/// A column of a table or of an intermediate result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    /// The table the column belongs to.
+    pub table: Option<String>,
+    pub name: String,
+    /// Computed columns have no declared type.
+    pub dtype: Option<DataType>,
+}
+
+/// Runs the subqueries found in expressions.
+pub trait QueryRunner {
+    /// The context holds the row of the enclosing query, correlated
+    /// subqueries read its columns.
+    fn run(&self, query: &Query, ctx: Context) -> EvalResult<ResultSet>;
+}
+
+/// The current row of an enclosing query.
+pub struct Outer<'a> {
+    scope: &'a Scope<'a>,
+    row: &'a [Value],
+    /// Set once a column of the row is read, the subquery is correlated.
+    used: Cell<bool>,
+}
+
+/// The result of a `WITH` query, read by name like a table.
+pub struct Binding<'a> {
+    pub name: &'a str,
+    pub result: &'a ResultSet,
+    /// The bindings defined before this one.
+    pub parent: Option<&'a Binding<'a>>,
+}
+
+/// What an expression can see beyond the columns of its own row.
+#[derive(Clone, Copy)]
+pub struct Context<'a> {
+    /// Subqueries are rejected without a runner.
+    pub runner: Option<&'a dyn QueryRunner>,
+    pub outer: Option<&'a Outer<'a>>,
+    pub ctes: Option<&'a Binding<'a>>,
+    /// The time the statement started, read by `NOW()` and the other clock
+    /// functions so every row sees the same time. Subqueries keep it.
+    pub now: DateTime<Utc>,
+}
+
+impl Default for Context<'_> {
+    fn default() -> Self {
+        Self {
+            runner: None,
+            outer: None,
+            ctes: None,
+            now: Utc::now(),
+        }
+    }
+}
+
+impl<'a> Context<'a> {
+    /// The innermost `WITH` query named `name`, it shadows any table.
+    pub fn cte(&self, name: &str) -> Option<&'a ResultSet> {
+        let mut binding = self.ctes;
+        while let Some(Binding {
+            name: bound,
+            result,
+            parent,
+        }) = binding
+        {
+            if *bound == name {
+                return Some(result);
+            }
+            binding = *parent;
+        }
+
+        None
+    }
+}
+
+/// The columns visible to an expression, values of a row are in the same order.
+pub struct Scope<'a> {
+    cols: &'a [Column],
+    ctx: Context<'a>,
+    /// Results of subqueries that don't depend on the current row.
+    cache: RefCell<HashMap<*const Query, Rc<ResultSet>>>,
+    /// Compiled patterns of `LIKE` and `REGEXP`, most are the same literal
+    /// for every row.
+    regexes: RefCell<HashMap<String, Regex>>,
+}
+
+impl<'a> Scope<'a> {
+    pub fn new(cols: &'a [Column]) -> Self {
+        Self::with_context(cols, Context::default())
+    }
+
+    pub fn with_context(cols: &'a [Column], ctx: Context<'a>) -> Self {
+        Self {
+            cols,
+            ctx,
+            cache: RefCell::default(),
+            regexes: RefCell::default(),
+        }
+    }
+
+    pub fn cols(&self) -> &'a [Column] {
+        self.cols
+    }
+
+    /// Position of a column in the row, a qualified column only matches the
+    /// columns of its table.
+    pub fn resolve(&self, table: Option<&str>, name: &str) -> EvalResult<usize> {
+        let mut positions = self
+            .cols
+            .iter()
+            .enumerate()
+            .filter(|(_, col)| {
+                col.name == name && table.is_none_or(|table| col.table.as_deref() == Some(table))
+            })
+            .map(|(pos, _)| pos);
+
+        let ident = || match table {
+            Some(table) => format!("{}.{}", table, name),
+            None => name.to_string(),
+        };
+        match (positions.next(), positions.next()) {
+            (Some(pos), None) => Ok(pos),
+            (Some(_), Some(_)) => Err(EvalError::AmbiguousCol(ident())),
+            (None, _) => Err(EvalError::ColNotFound(ident())),
+        }
+    }
+
+    /// The column an expression refers to, if it's a column reference.
+    pub fn column(&self, expr: &Expr) -> EvalResult<Option<usize>> {
+        match expr.as_column() {
+            Some((table, name)) => self.resolve(table, name).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Resolve a column reference, columns missing from this scope are
+    /// looked up in the enclosing queries. `None` means the column is outer.
+    fn lookup(&self, expr: &Expr) -> EvalResult<Option<usize>> {
+        match (self.column(expr), self.ctx.outer) {
+            (Err(EvalError::ColNotFound(_)), Some(outer)) => {
+                outer.used.set(true);
+                outer.scope.lookup(expr).map(|_| None)
+            }
+            (result, _) => result,
+        }
+    }
+
+    /// Check the expression before reading any row: every column must exist
+    /// and literals compared to a column must be valid values of its type.
+    pub fn validate(&self, expr: &Expr, allow_aggregates: bool) -> EvalResult<()> {
+        match expr {
+            Expr::Identifier(_) | Expr::QualifiedIdentifier { .. } => self.lookup(expr).map(|_| ()),
+            Expr::Literal(_) => Ok(()),
+            // Subqueries are checked when they run.
+            Expr::Subquery(_) | Expr::Exists(_) if self.ctx.runner.is_none() => Err(
+                EvalError::InvalidArgument("subqueries are not supported here".into()),
+            ),
+            Expr::Subquery(_) | Expr::Exists(_) => Ok(()),
+            Expr::InSubquery { expr, subquery, .. } => {
+                self.validate(expr, allow_aggregates)?;
+                self.validate(&Expr::Subquery(subquery.clone()), allow_aggregates)
+            }
+            Expr::Wildcard => Err(EvalError::InvalidArgument(
+                "`*` is only valid in COUNT(*)".into(),
+            )),
+            // Window functions are computed by the executor once every row
+            // is known, anywhere else they have no rows to run over.
+            Expr::Window { .. } => Err(EvalError::MisplacedWindow(expr.to_string())),
+            Expr::BinaryOp { left, op, right } => {
+                self.validate(left, allow_aggregates)?;
+                self.validate(right, allow_aggregates)?;
+
+                match op {
+                    _ if op.is_comparison() => self.check_comparison(left, right),
+                    BinaryOperator::And | BinaryOperator::Or => {
+                        self.expect_bool(left)?;
+                        self.expect_bool(right)
+                    }
+                    BinaryOperator::Regexp => {
+                        self.expect_text("REGEXP", left)?;
+                        self.expect_text("REGEXP", right)?;
+                        if let Expr::Literal(literal) = right.as_ref() {
+                            self.regex(&literal.value().to_string())?;
+                        }
+                        Ok(())
+                    }
+                    BinaryOperator::JsonGet | BinaryOperator::JsonGetText => {
+                        match (self.expr_type(left), self.expr_type(right)) {
+                            (Some(dtype), _) if dtype != DataType::JSON && !dtype.is_text() => {
+                                Err(EvalError::InvalidArgument(format!(
+                                    "`{}` expects JSON, found {:?}",
+                                    op, dtype
+                                )))
+                            }
+                            (_, Some(dtype)) if !dtype.is_text() && !dtype.is_integer() => {
+                                Err(EvalError::InvalidArgument(format!(
+                                    "`{}` expects text or an integer key, found {:?}",
+                                    op, dtype
+                                )))
+                            }
+                            _ => Ok(()),
+                        }
+                    }
+                    _ if op.is_arithmetic() && *op != BinaryOperator::StringConcat => {
+                        match self.arithmetic_types(left, right)? {
+                            (Some(l), Some(r)) if arithmetic_type(*op, &l, &r).is_none() => {
+                                Err(EvalError::InvalidArgument(format!(
+                                    "`{}` cannot be applied to {:?} and {:?}",
+                                    op, l, r
+                                )))
+                            }
+                            (Some(_), Some(_)) => Ok(()),
+                            _ => {
+                                self.expect_numeric(&op.to_string(), left)?;
+                                self.expect_numeric(&op.to_string(), right)
+                            }
+                        }
+                    }
+                    _ => Ok(()),
+                }
+            }
+            Expr::UnaryOp {
+                op: UnaryOperator::Minus,
+                expr,
+            } => {
+                self.validate(expr, allow_aggregates)?;
+                self.expect_numeric("-", expr)
+            }
+            Expr::UnaryOp {
+                op: UnaryOperator::Not,
+                expr,
+            } => self.validate_condition(expr, allow_aggregates),
+            Expr::IsNull { expr, .. } | Expr::Nested(expr) => self.validate(expr, allow_aggregates),
+            Expr::Cast { expr, data_type } => {
+                self.validate(expr, allow_aggregates)?;
+                // Literals are converted right away, an invalid one would
+                // fail for every row.
+                if let Expr::Literal(literal) = expr.as_ref() {
+                    data_type.cast(&literal.value())?;
+                }
+                Ok(())
+            }
+            Expr::Case {
+                operand, branches, ..
+            } => {
+                for expr in expr.case_exprs() {
+                    self.validate(expr, allow_aggregates)?;
+                }
+                if operand.is_none() {
+                    for (when, _) in branches {
+                        self.expect_bool(when)?;
+                    }
+                }
+
+                self.case_type(expr).map(|_| ())
+            }
+            Expr::InList { .. } | Expr::Between { .. } | Expr::Like { .. } => {
+                for operand in expr.operands() {
+                    self.validate(operand, allow_aggregates)?;
+                }
+
+                match expr {
+                    Expr::Like {
+                        expr,
+                        pattern,
+                        escape,
+                        case_insensitive,
+                        ..
+                    } => {
+                        let op = if *case_insensitive { "ILIKE" } else { "LIKE" };
+                        self.expect_text(op, expr)?;
+                        self.expect_text(op, pattern)?;
+                        let escape = match escape.as_deref() {
+                            Some(Expr::Literal(literal)) => escape_char(&literal.value())?,
+                            Some(_) => return Ok(()),
+                            None => Some('\\'),
+                        };
+                        if let Expr::Literal(literal) = pattern.as_ref() {
+                            like_regex(&literal.value().to_string(), escape, *case_insensitive)?;
+                        }
+                        Ok(())
+                    }
+                    _ => {
+                        let operands = expr.operands();
+                        let (expr, bounds) = operands.split_first().unwrap();
+                        bounds
+                            .iter()
+                            .try_for_each(|bound| self.check_comparison(expr, bound))
+                    }
+                }
+            }
+            Expr::Function {
+                name,
+                args,
+                distinct,
+            } => {
+                if !expr.is_aggregate() {
+                    let function = functions::lookup(name)
+                        .ok_or_else(|| EvalError::UnknownFunction(name.clone()))?;
+                    if *distinct {
+                        return Err(EvalError::InvalidArgument(format!(
+                            "DISTINCT is only valid in aggregates, not in {}",
+                            name
+                        )));
+                    }
+                    for arg in args {
+                        self.validate(arg, allow_aggregates)?;
+                    }
+                    let types = args.iter().map(|arg| self.expr_type(arg));
+                    return function.check(&types.collect::<Vec<_>>());
+                } else if !allow_aggregates {
+                    return Err(EvalError::MisplacedAggregate(expr.to_string()));
+                }
+
+                match args.as_slice() {
+                    [Expr::Wildcard] if name.eq_ignore_ascii_case("COUNT") && !distinct => Ok(()),
+                    // Aggregates can't be nested.
+                    [arg] => {
+                        self.validate(arg, false)?;
+                        let is_sum = ["SUM", "AVG"].iter().any(|f| f.eq_ignore_ascii_case(name));
+                        match self.expr_type(arg) {
+                            Some(dtype)
+                                if is_sum && !dtype.is_numeric() && dtype != DataType::INTERVAL =>
+                            {
+                                Err(EvalError::InvalidArgument(format!(
+                                    "{} expects a number or an interval, found {:?}",
+                                    name, dtype
+                                )))
+                            }
+                            _ => Ok(()),
+                        }
+                    }
+                    _ => Err(EvalError::InvalidArgument(format!(
+                        "{} expects a single argument",
+                        name
+                    ))),
+                }
+            }
+        }
+    }
+
+    /// Check a condition like the one of `WHERE`, see [`Scope::validate`].
+    /// Its value must be a boolean, rows aren't matched by any other value.
+    pub fn validate_condition(&self, expr: &Expr, allow_aggregates: bool) -> EvalResult<()> {
+        self.validate(expr, allow_aggregates)?;
+        self.expect_bool(expr)
+    }
+
+    /// A literal compared to a column must be a valid value of its type.
+    fn check_comparison(&self, left: &Expr, right: &Expr) -> EvalResult<()> {
+        if let (col, Expr::Literal(literal)) | (Expr::Literal(literal), col) = (left, right) {
+            if let Ok(Some(pos)) = self.column(col) {
+                let value = literal.value();
+                match &self.cols[pos].dtype {
+                    // Numbers are compared in a type wide enough for both,
+                    // see `comparison_type`.
+                    Some(dtype) if !(dtype.is_numeric() && value.is_numeric()) => {
+                        dtype.coerce(&value)?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The types of the operands of arithmetic. A text literal mixed with a
+    /// date, time or interval is read as a value of the same type, like
+    /// `born - '2000-01-01'`, it must be a valid one.
+    fn arithmetic_types(
+        &self,
+        left: &Expr,
+        right: &Expr,
+    ) -> EvalResult<(Option<DataType>, Option<DataType>)> {
+        let (l, r) = (self.expr_type(left), self.expr_type(right));
+        let temporal = |dtype: &Option<DataType>| dtype.as_ref().is_some_and(DataType::is_temporal);
+
+        match (left, right) {
+            (_, Expr::Literal(literal @ Literal::String(_))) if temporal(&l) => {
+                l.as_ref().unwrap().coerce(&literal.value())?;
+                Ok((l.clone(), l))
+            }
+            (Expr::Literal(literal @ Literal::String(_)), _) if temporal(&r) => {
+                r.as_ref().unwrap().coerce(&literal.value())?;
+                Ok((r.clone(), r))
+            }
+            _ => Ok((l, r)),
+        }
+    }
+
+    /// Conditions must be booleans, checked when their type is known.
+    fn expect_bool(&self, expr: &Expr) -> EvalResult<()> {
+        match self.expr_type(expr) {
+            Some(DataType::BOOLEAN | DataType::BOOL) | None => Ok(()),
+            Some(dtype) => Err(EvalError::InvalidArgument(format!(
+                "`{}` is not a condition, found {:?}",
+                expr, dtype
+            ))),
+        }
+    }
+
+    /// Operands of pattern matching must be text, checked when their type is known.
+    fn expect_text(&self, op: &str, expr: &Expr) -> EvalResult<()> {
+        match self.expr_type(expr) {
+            Some(dtype) if !dtype.is_text() => Err(EvalError::InvalidArgument(format!(
+                "`{}` expects text, found {:?}",
+                op, dtype
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Operands of arithmetic must be numbers, dates or intervals, checked
+    /// when their type is known.
+    fn expect_numeric(&self, op: &str, expr: &Expr) -> EvalResult<()> {
+        match self.expr_type(expr) {
+            Some(dtype) if !dtype.is_numeric() && !dtype.is_temporal() => Err(
+                EvalError::InvalidArgument(format!("`{}` expects numbers, found {:?}", op, dtype)),
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /// The type every result of a `CASE` can be converted to, results of an
+    /// unknown type are skipped.
+    fn case_type(&self, expr: &Expr) -> EvalResult<Option<DataType>> {
+        let Expr::Case {
+            branches,
+            else_result,
+            ..
+        } = expr
+        else {
+            return Ok(None);
+        };
+
+        let results = branches.iter().map(|(_, then)| then);
+        let mut dtype: Option<DataType> = None;
+        for result in results.chain(else_result.as_deref()) {
+            dtype = match (dtype, self.expr_type(result)) {
+                (Some(a), Some(b)) => match a.common_type(&b) {
+                    Some(dtype) => Some(dtype),
+                    None => {
+                        return Err(EvalError::InvalidArgument(format!(
+                            "CASE types {:?} and {:?} cannot be matched",
+                            a, b
+                        )))
+                    }
+                },
+                (a, b) => a.or(b),
+            };
+        }
+
+        Ok(dtype)
+    }
+
+    /// The type of the values the expression evaluates to, if it's known
+    /// without reading any row.
+    pub fn expr_type(&self, expr: &Expr) -> Option<DataType> {
+        match expr {
+            Expr::Identifier(_) | Expr::QualifiedIdentifier { .. } => {
+                match (self.lookup(expr), self.ctx.outer) {
+                    (Ok(Some(pos)), _) => self.cols[pos].dtype.clone(),
+                    (Ok(None), Some(outer)) => outer.scope.expr_type(expr),
+                    _ => None,
+                }
+            }
+            Expr::Literal(literal) => value_type(&literal.value()).cloned(),
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::StringConcat,
+                right,
+            } => match (self.expr_type(left), self.expr_type(right)) {
+                (Some(DataType::BLOB), Some(DataType::BLOB)) => Some(DataType::BLOB),
+                _ => Some(DataType::TEXT),
+            },
+            Expr::BinaryOp {
+                op: BinaryOperator::JsonGetText,
+                ..
+            } => Some(DataType::TEXT),
+            Expr::BinaryOp {
+                op: BinaryOperator::JsonGet,
+                ..
+            } => Some(DataType::JSON),
+            Expr::BinaryOp { left, op, right } if op.is_arithmetic() => {
+                match self.arithmetic_types(left, right).ok()? {
+                    (Some(left), Some(right)) => arithmetic_type(*op, &left, &right),
+                    _ => None,
+                }
+            }
+            Expr::UnaryOp {
+                op: UnaryOperator::Minus,
+                expr,
+            } => self.expr_type(expr),
+            Expr::Case { .. } => self.case_type(expr).ok().flatten(),
+            Expr::Cast { data_type, .. } => Some(data_type.clone()),
+            Expr::Function { name, args, .. } if !expr.is_aggregate() => {
+                let types = args.iter().map(|arg| self.expr_type(arg));
+                let function = functions::lookup(name)?;
+                function
+                    .return_type(&types.collect::<Vec<_>>())
+                    .ok()
+                    .flatten()
+            }
+            Expr::BinaryOp { .. }
+            | Expr::UnaryOp { .. }
+            | Expr::IsNull { .. }
+            | Expr::InSubquery { .. }
+            | Expr::InList { .. }
+            | Expr::Between { .. }
+            | Expr::Like { .. }
+            | Expr::Exists(_) => Some(DataType::BOOL),
+            Expr::Function { name, args, .. } | Expr::Window { name, args, .. } => {
+                match name.to_uppercase().as_str() {
+                    "COUNT" | "ROW_NUMBER" | "RANK" | "DENSE_RANK" => Some(DataType::BIGINT),
+                    "AVG" | "SUM" => match args.first().and_then(|arg| self.expr_type(arg)) {
+                        Some(dtype) if dtype.is_decimal() => Some(DataType::DEC),
+                        Some(DataType::INTERVAL) => Some(DataType::INTERVAL),
+                        Some(dtype) if dtype.is_integer() && name.eq_ignore_ascii_case("SUM") => {
+                            Some(DataType::BIGINT)
+                        }
+                        _ if name.eq_ignore_ascii_case("AVG") => Some(DataType::FLOAT),
+                        dtype => dtype,
+                    },
+                    _ => args.first().and_then(|arg| self.expr_type(arg)),
+                }
+            }
+            Expr::Nested(expr) => self.expr_type(expr),
+            Expr::Wildcard | Expr::Subquery(_) => None,
+        }
+    }
+
+    /// Only rows the condition is true for match, unknown is treated as false.
+    pub fn matches(&self, condition: &Option<Expr>, row: &[Value]) -> EvalResult<bool> {
+        match condition {
+            None => Ok(true),
+            Some(expr) => Ok(self.eval_predicate(expr, row, None)? == Some(true)),
+        }
+    }
+
+    /// Evaluate a condition using three-valued logic, `None` is unknown.
+    /// Comparing against a null value is always unknown.
+    ///
+    /// Aggregates are computed over `group`, every other column is read from
+    /// `row`.
+    pub fn eval_predicate(
+        &self,
+        expr: &Expr,
+        row: &[Value],
+        group: Option<&[Row]>,
+    ) -> EvalResult<Option<bool>> {
+        let predicate = |expr| self.eval_predicate(expr, row, group);
+
+        match expr {
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::And,
+                right,
+            } => Ok(match predicate(left)? {
+                // The right side isn't evaluated, it can rely on the left one.
+                Some(false) => Some(false),
+                left => match (left, predicate(right)?) {
+                    (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                },
+            }),
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::Or,
+                right,
+            } => Ok(match predicate(left)? {
+                Some(true) => Some(true),
+                left => match (left, predicate(right)?) {
+                    (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                },
+            }),
+            Expr::UnaryOp {
+                op: UnaryOperator::Not,
+                expr,
+            } => Ok(predicate(expr)?.map(|v| !v)),
+            Expr::IsNull { expr, negated } => {
+                let (value, _) = self.eval(expr, row, group)?;
+                Ok(Some(value.is_null() != *negated))
+            }
+            Expr::Nested(expr) => predicate(expr),
+            Expr::Exists(query) => Ok(Some(!self.subquery(query, row)?.entries.is_empty())),
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => {
+                let left = self.eval(expr, row, group)?;
+                let result = self.subquery(subquery, row)?;
+                if result.cols.len() != 1 {
+                    return Err(EvalError::InvalidArgument(
+                        "subquery has too many columns".into(),
+                    ));
+                }
+
+                let dtype = subquery_type(&result.cols[0]);
+                let values = result
+                    .entries
+                    .iter()
+                    .map(|entry| Ok((entry[0].clone(), dtype)));
+                Ok(contains(&left, values)?.map(|found| found != *negated))
+            }
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let left = self.eval(expr, row, group)?;
+                let values = list.iter().map(|item| self.eval(item, row, group));
+                Ok(contains(&left, values)?.map(|found| found != *negated))
+            }
+            Expr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                let value = self.eval(expr, row, group)?;
+                let compare = |bound: &Expr| -> EvalResult<Option<Ordering>> {
+                    let bound = self.eval(bound, row, group)?;
+                    Ok(comparison_type(&value, &bound).compare(&value.0, &bound.0)?)
+                };
+                // Like `low <= expr AND expr <= high`.
+                let between = match (compare(low)?, compare(high)?) {
+                    (Some(low), Some(high)) => Some(low.is_ge() && high.is_le()),
+                    (Some(Ordering::Less), _) | (_, Some(Ordering::Greater)) => Some(false),
+                    _ => None,
+                };
+                Ok(between.map(|between| between != *negated))
+            }
+            Expr::Like {
+                expr,
+                pattern,
+                escape,
+                negated,
+                case_insensitive,
+            } => {
+                let value = self.eval(expr, row, group)?.0;
+                let pattern = self.eval(pattern, row, group)?.0;
+                let escape = match escape {
+                    Some(escape) => match self.eval(escape, row, group)?.0 {
+                        Value::Null => return Ok(None),
+                        escape => escape_char(&escape)?,
+                    },
+                    None => Some('\\'),
+                };
+                if value.is_null() || pattern.is_null() {
+                    return Ok(None);
+                }
+
+                let regex = like_regex(&pattern.to_string(), escape, *case_insensitive)?;
+                let matched = self.regex(&regex)?.is_match(&value.to_string());
+                Ok(Some(matched != *negated))
+            }
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::Regexp,
+                right,
+            } => {
+                let value = self.eval(left, row, group)?.0;
+                let pattern = self.eval(right, row, group)?.0;
+                if value.is_null() || pattern.is_null() {
+                    return Ok(None);
+                }
+
+                Ok(Some(
+                    self.regex(&pattern.to_string())?
+                        .is_match(&value.to_string()),
+                ))
+            }
+            Expr::BinaryOp { left, op, right } if op.is_comparison() => {
+                let left = self.eval(left, row, group)?;
+                let right = self.eval(right, row, group)?;
+
+                let dtype = comparison_type(&left, &right);
+                let ordering = match dtype.compare(&left.0, &right.0)? {
+                    Some(ordering) => ordering,
+                    None => return Ok(None),
+                };
+
+                Ok(Some(match op {
+                    BinaryOperator::Eq => ordering.is_eq(),
+                    BinaryOperator::NotEq => ordering.is_ne(),
+                    BinaryOperator::Gt => ordering.is_gt(),
+                    BinaryOperator::Lt => ordering.is_lt(),
+                    BinaryOperator::GtEq => ordering.is_ge(),
+                    BinaryOperator::LtEq => ordering.is_le(),
+                    _ => unreachable!(),
+                }))
+            }
+            expr => match self.eval(expr, row, group)?.0 {
+                Value::Bool(v) => Ok(Some(v)),
+                Value::Null => Ok(None),
+                value => Err(EvalError::InvalidArgument(format!(
+                    "`{}` is not a condition, found `{}`",
+                    expr, value
+                ))),
+            },
+        }
+    }
+
+    /// Evaluate an expression to its value along with its type.
+    pub fn eval(
+        &self,
+        expr: &Expr,
+        row: &[Value],
+        group: Option<&[Row]>,
+    ) -> EvalResult<Operand<'a>> {
+        Ok(match expr {
+            Expr::Identifier(_) | Expr::QualifiedIdentifier { .. } => {
+                match (self.lookup(expr)?, self.ctx.outer) {
+                    (Some(pos), _) => (row[pos].clone(), self.cols[pos].dtype.as_ref()),
+                    (None, Some(outer)) => {
+                        outer.used.set(true);
+                        outer.scope.eval(expr, outer.row, None)?
+                    }
+                    (None, None) => unreachable!(),
+                }
+            }
+            Expr::Subquery(query) => {
+                let result = self.subquery(query, row)?;
+                if result.cols.len() != 1 {
+                    return Err(EvalError::InvalidArgument(
+                        "subquery has too many columns".into(),
+                    ));
+                }
+                let dtype = subquery_type(&result.cols[0]);
+                match result.entries.as_slice() {
+                    [] => (Value::Null, dtype),
+                    [entry] => (entry[0].clone(), dtype),
+                    _ => {
+                        return Err(EvalError::InvalidArgument(
+                            "more than one row returned by a subquery used as an expression".into(),
+                        ))
+                    }
+                }
+            }
+            Expr::Literal(literal) => (literal.value(), None),
+            Expr::Nested(expr) => self.eval(expr, row, group)?,
+            Expr::BinaryOp { left, op, right } if op.is_arithmetic() => {
+                let left = self.eval(left, row, group)?;
+                let right = self.eval(right, row, group)?;
+                let left = read_as_temporal(left, right.1)?;
+                let right = read_as_temporal(right, left.1)?;
+                let value = arithmetic(*op, &left.0, &right.0)?;
+                match int_type(&left).zip(int_type(&right)) {
+                    Some((l, r)) if matches!(value, Value::Int(_)) => {
+                        let dtype = widest_int(l, r);
+                        let expr = || format!("{} {} {}", left.0, op, right.0);
+                        (fit_int(dtype, value, expr)?, Some(dtype))
+                    }
+                    _ => {
+                        let dtype = value_type(&value);
+                        (value, dtype)
+                    }
+                }
+            }
+            Expr::BinaryOp {
+                left,
+                op: op @ (BinaryOperator::JsonGet | BinaryOperator::JsonGetText),
+                right,
+            } => {
+                let document = json::document(&self.eval(left, row, group)?.0)?;
+                let key = self.eval(right, row, group)?.0;
+                let value = match document {
+                    Some(document) if !key.is_null() => json::get(&document, &key)?,
+                    _ => None,
+                };
+                match op {
+                    BinaryOperator::JsonGet => (
+                        value.map_or(Value::Null, Value::Json),
+                        Some(&DataType::JSON),
+                    ),
+                    _ => (
+                        value.as_ref().map_or(Value::Null, json::text),
+                        Some(&DataType::TEXT),
+                    ),
+                }
+            }
+            Expr::UnaryOp {
+                op: UnaryOperator::Minus,
+                expr,
+            } => match self.eval(expr, row, group)? {
+                (Value::Int(v), dtype) => match v.checked_neg() {
+                    Some(neg) => match dtype {
+                        Some(dtype) => (
+                            fit_int(dtype, Value::Int(neg), || format!("-{}", v))?,
+                            Some(dtype),
+                        ),
+                        None => (Value::Int(neg), dtype),
+                    },
+                    None => return Err(EvalError::Overflow(format!("-{}", v))),
+                },
+                (Value::Float(v), dtype) => (Value::Float(-v), dtype),
+                (Value::Decimal(v), dtype) => (Value::Decimal(-v), dtype),
+                (Value::Interval(v), dtype) => match v.checked_neg() {
+                    Some(v) => (Value::Interval(v), dtype),
+                    None => return Err(EvalError::Overflow(format!("-{}", v))),
+                },
+                (Value::Null, dtype) => (Value::Null, dtype),
+                (value, _) => {
+                    return Err(EvalError::InvalidArgument(format!(
+                        "`-` expects a number, found {}",
+                        value
+                    )))
+                }
+            },
+            Expr::Case {
+                operand,
+                branches,
+                else_result,
+            } => {
+                let operand = match operand {
+                    Some(operand) => Some(self.eval(operand, row, group)?),
+                    None => None,
+                };
+                for (when, then) in branches {
+                    // Comparing to the operand follows `=`, null never matches.
+                    let matched = match &operand {
+                        Some(operand) => {
+                            let when = self.eval(when, row, group)?;
+                            comparison_type(operand, &when).compare(&operand.0, &when.0)?
+                                == Some(Ordering::Equal)
+                        }
+                        None => self.eval_predicate(when, row, group)? == Some(true),
+                    };
+                    if matched {
+                        return self.eval(then, row, group);
+                    }
+                }
+
+                match else_result {
+                    Some(else_result) => self.eval(else_result, row, group)?,
+                    None => (Value::Null, None),
+                }
+            }
+            Expr::Function {
+                name,
+                args,
+                distinct,
+            } if expr.is_aggregate() => match group {
+                Some(group) => self.aggregate(name, args, *distinct, group)?,
+                None => return Err(EvalError::MisplacedAggregate(expr.to_string())),
+            },
+            Expr::Function { name, args, .. } => {
+                let function = functions::lookup(name)
+                    .ok_or_else(|| EvalError::UnknownFunction(name.clone()))?;
+                let args = args
+                    .iter()
+                    .map(|arg| Ok(self.eval(arg, row, group)?.0))
+                    .collect::<EvalResult<Vec<_>>>()?;
+                let value = function.call(&args, self.ctx.now)?;
+                let dtype = value_type(&value);
+                (value, dtype)
+            }
+            // The declared type isn't kept, the value is typed like a literal.
+            Expr::Cast { expr, data_type } => {
+                let value = data_type.cast(&self.eval(expr, row, group)?.0)?;
+                let dtype = value_type(&value);
+                (value, dtype)
+            }
+            Expr::Window { .. } => return Err(EvalError::MisplacedWindow(expr.to_string())),
+            Expr::Wildcard => {
+                return Err(EvalError::InvalidArgument(
+                    "`*` is only valid in COUNT(*)".into(),
+                ))
+            }
+            expr => (
+                self.eval_predicate(expr, row, group)?
+                    .map_or(Value::Null, Value::Bool),
+                Some(&DataType::BOOL),
+            ),
+        })
+    }
+
+    /// Compile a regular expression, each pattern is compiled once.
+    fn regex(&self, pattern: &str) -> EvalResult<Regex> {
+        if let Some(regex) = self.regexes.borrow().get(pattern) {
+            return Ok(regex.clone());
+        }
+
+        let regex = Regex::new(pattern).map_err(|err| {
+            EvalError::InvalidArgument(format!("invalid pattern `{}`: {}", pattern, err))
+        })?;
+        self.regexes
+            .borrow_mut()
+            .insert(pattern.to_string(), regex.clone());
+        Ok(regex)
+    }
+
+    /// Run a subquery for the current row. The result is reused for every
+    /// other row unless the subquery reads a column of the row.
+    fn subquery(&self, query: &Query, row: &[Value]) -> EvalResult<Rc<ResultSet>> {
+        let runner = self.ctx.runner.ok_or_else(|| {
+            EvalError::InvalidArgument("subqueries are not supported here".into())
+        })?;
+
+        let key = query as *const Query;
+        if let Some(result) = self.cache.borrow().get(&key) {
+            return Ok(Rc::clone(result));
+        }
+
+        let outer = Outer {
+            scope: self,
+            row,
+            used: Cell::new(false),
+        };
+        let ctx = Context {
+            outer: Some(&outer),
+            ..self.ctx
+        };
+        let result = Rc::new(runner.run(query, ctx)?);
+        if !outer.used.get() {
+            self.cache.borrow_mut().insert(key, Rc::clone(&result));
+        }
+
+        Ok(result)
+    }
+
+    /// Compute an aggregate over every row of the group, nulls are skipped.
+    fn aggregate(
+        &self,
+        name: &str,
+        args: &[Expr],
+        distinct: bool,
+        group: &[Row],
+    ) -> EvalResult<Operand<'a>> {
+        let arg = match args {
+            [Expr::Wildcard] => {
+                return Ok((Value::Int(group.len() as i64), Some(&DataType::BIGINT)))
+            }
+            [arg] => arg,
+            _ => {
+                return Err(EvalError::InvalidArgument(format!(
+                    "{} expects a single argument",
+                    name
+                )))
+            }
+        };
+
+        let mut dtype = None;
+        let mut values = Vec::new();
+        let mut seen = HashSet::new();
+        for row in group {
+            let (value, value_type) = self.eval(arg, row, None)?;
+            dtype = value_type;
+            if value.is_null() || (distinct && !seen.insert(value.clone())) {
+                continue;
+            }
+            values.push(value);
+        }
+
+        aggregate(name, values, dtype)
+    }
+}
+
+/// Whether `left` is equal to any of `values`, like a chain of `OR` it's
+/// unknown unless some value is equal.
+fn contains<'a>(
+    left: &Operand,
+    values: impl Iterator<Item = EvalResult<Operand<'a>>>,
+) -> EvalResult<Option<bool>> {
+    if left.0.is_null() {
+        return Ok(None);
+    }
+
+    let mut found = Some(false);
+    for right in values {
+        let right = right?;
+        match comparison_type(left, &right).compare(&left.0, &right.0)? {
+            Some(Ordering::Equal) => return Ok(Some(true)),
+            Some(_) => {}
+            None => found = None,
+        }
+    }
+
+    Ok(found)
+}
+
+/// The escape char of `LIKE`, an empty string disables escaping.
+fn escape_char(escape: &Value) -> EvalResult<Option<char>> {
+    let text = escape.to_string();
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => Ok(None),
+        (Some(c), None) => Ok(Some(c)),
+        _ => Err(EvalError::InvalidArgument(format!(
+            "ESCAPE expects a single char, found `{}`",
+            text
+        ))),
+    }
+}
+
+/// Translate a `LIKE` pattern to a regular expression matching the whole text.
+fn like_regex(pattern: &str, escape: Option<char>, case_insensitive: bool) -> EvalResult<String> {
+    let mut regex = String::from(if case_insensitive { "(?is)^" } else { "(?s)^" });
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if Some(c) == escape => match chars.next() {
+                Some(c) => regex.push_str(&regex::escape(&c.to_string())),
+                None => {
+                    return Err(EvalError::InvalidArgument(format!(
+                        "LIKE pattern `{}` ends with the escape char",
+                        pattern
+                    )))
+                }
+            },
+            '%' => regex.push_str(".*"),
+            '_' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+
+    Ok(regex)
+}
+
+/// The type of a value computed without a column, like the value of a literal.
+fn value_type(value: &Value) -> Option<&'static DataType> {
+    match value {
+        Value::Int(_) => Some(&DataType::INT),
+        Value::Float(_) => Some(&DataType::FLOAT),
+        Value::Decimal(_) => Some(&DataType::DEC),
+        Value::Text(_) => Some(&DataType::TEXT),
+        Value::Bool(_) => Some(&DataType::BOOL),
+        Value::Date(_) => Some(&DataType::DATE),
+        Value::Time(_) => Some(&DataType::TIME),
+        Value::Timestamp(_) => Some(&DataType::TIMESTAMP),
+        Value::TimestampTz(_) => Some(&DataType::TIMESTAMPTZ),
+        Value::Interval(_) => Some(&DataType::INTERVAL),
+        Value::Blob(_) => Some(&DataType::BLOB),
+        Value::Json(_) => Some(&DataType::JSON),
+        Value::Uuid(_) => Some(&DataType::UUID),
+        Value::Null => None,
+    }
+}
+
+/// Untyped text mixed with a date, time or interval in arithmetic is read as
+/// a value of the same type, see [`Scope::arithmetic_types`].
+fn read_as_temporal<'a>(
+    operand: Operand<'a>,
+    other: Option<&'a DataType>,
+) -> EvalResult<Operand<'a>> {
+    match (operand, other) {
+        ((value @ Value::Text(_), None), Some(dtype)) if dtype.is_temporal() => {
+            Ok((dtype.coerce(&value)?, Some(dtype)))
+        }
+        (operand, _) => Ok(operand),
+    }
+}
+
+/// The type of the values of a subquery column. The result doesn't outlive
+/// the row, only the kind of the type is kept: integers are `BIGINT`,
+/// decimals `DEC` and text `TEXT`. Computed columns stay untyped like literals.
+fn subquery_type(col: &Column) -> Option<&'static DataType> {
+    match col.dtype.as_ref()? {
+        dtype if dtype.is_integer() => Some(&DataType::BIGINT),
+        dtype => value_type(&dtype.default()),
+    }
+}
+
+/// The integer type of an operand, integer literals are `INT` unless they
+/// need a `BIGINT`.
+fn int_type<'a>((value, dtype): &Operand<'a>) -> Option<&'a DataType> {
+    match (value, dtype) {
+        (_, Some(dtype)) => Some(*dtype).filter(|dtype| dtype.is_integer()),
+        (Value::Int(v), None) if i32::try_from(*v).is_ok() => Some(&DataType::INT),
+        (Value::Int(_), None) => Some(&DataType::BIGINT),
+        _ => None,
+    }
+}
+
+/// The integer type wide enough for both types, see [`DataType::common_type`].
+fn widest_int<'a>(left: &'a DataType, right: &'a DataType) -> &'a DataType {
+    match left.common_type(right) {
+        Some(dtype) if dtype == *left => left,
+        Some(dtype) if dtype == *right => right,
+        _ => &DataType::BIGINT,
+    }
+}
+
+/// Integers computed from columns overflow at the range of their type.
+fn fit_int(dtype: &DataType, value: Value, expr: impl Fn() -> String) -> EvalResult<Value> {
+    match (dtype.int_range(), &value) {
+        (Some((min, max)), Value::Int(v)) if v < &min || v > &max => Err(EvalError::Overflow(
+            format!("{} is out of range for {:?}", expr(), dtype),
+        )),
+        _ => Ok(value),
+    }
+}
+
+/// The type of the result of an arithmetic operator, `None` if it doesn't
+/// apply to the types. Dates and times can be shifted by intervals and
+/// subtracted from each other, see [`datetime::arithmetic`].
+pub fn arithmetic_type(op: BinaryOperator, left: &DataType, right: &DataType) -> Option<DataType> {
+    use BinaryOperator::{Divide, Minus, Multiply, Plus, StringConcat};
+    use DataType::{DATE, INTERVAL, TIME, TIMESTAMP, TIMESTAMPTZ};
+
+    let is_int = DataType::is_integer;
+    let is_time = |dtype: &DataType| matches!(dtype, TIME | TIMESTAMP | TIMESTAMPTZ);
+    let is_timestamp = |dtype: &DataType| matches!(dtype, DATE | TIMESTAMP | TIMESTAMPTZ);
+
+    match (op, left, right) {
+        (StringConcat, DataType::BLOB, DataType::BLOB) => Some(DataType::BLOB),
+        (StringConcat, _, _) => Some(DataType::TEXT),
+        // The result of decimals can have any precision.
+        (_, l, r) if l.is_numeric() && r.is_numeric() => match l.common_type(r)? {
+            DataType::DECIMAL(..) => Some(DataType::DEC),
+            dtype => Some(dtype),
+        },
+        (Plus, DATE, n) | (Plus, n, DATE) | (Minus, DATE, n) if is_int(n) => Some(DATE),
+        (Minus, DATE, DATE) => Some(DataType::INT),
+        (Plus, DATE, TIME) | (Plus, TIME, DATE) => Some(TIMESTAMP),
+        (Plus | Minus, DATE, INTERVAL) | (Plus, INTERVAL, DATE) => Some(TIMESTAMP),
+        (Plus | Minus, t, INTERVAL) | (Plus, INTERVAL, t) if is_time(t) => Some(t.clone()),
+        (Minus, a, b) if a == b && is_time(a) => Some(INTERVAL),
+        // Dates are at midnight and timestamps without a time zone in UTC.
+        (Minus, a, b) if is_timestamp(a) && is_timestamp(b) => Some(INTERVAL),
+        (Plus | Minus, INTERVAL, INTERVAL) => Some(INTERVAL),
+        (Multiply, INTERVAL, n) | (Multiply, n, INTERVAL) | (Divide, INTERVAL, n)
+            if n.is_numeric() =>
+        {
+            Some(INTERVAL)
+        }
+        _ => None,
+    }
+}
+
+/// Apply an arithmetic operator, any null operand gives null. Integers stay
+/// integers, `/` truncates like in most databases. Decimals stay exact unless
+/// mixed with floats. `||` joins the bytes of two blobs and the text of any
+/// other values.
+pub fn arithmetic(op: BinaryOperator, left: &Value, right: &Value) -> EvalResult<Value> {
+    let expr = || format!("{} {} {}", left, op, right);
+    let float = |value: &Value| match value {
+        Value::Int(v) => *v as f64,
+        Value::Float(v) => *v,
+        Value::Decimal(v) => v.to_f64().unwrap_or(f64::NAN),
+        _ => unreachable!(),
+    };
+    let decimal = |value: &Value| match value {
+        Value::Int(v) => Some(Decimal::from(*v)),
+        Value::Decimal(v) => Some(*v),
+        _ => None,
+    };
+
+    let value = match (op, left, right) {
+        (_, Value::Null, _) | (_, _, Value::Null) => Value::Null,
+        (BinaryOperator::StringConcat, Value::Blob(a), Value::Blob(b)) => {
+            Value::Blob([&a[..], b].concat())
+        }
+        (BinaryOperator::StringConcat, left, right) => Value::Text(format!("{}{}", left, right)),
+        (BinaryOperator::Divide | BinaryOperator::Modulo, _, right)
+            if right.is_numeric() && float(right) == 0.0 =>
+        {
+            return Err(EvalError::DivisionByZero(expr()))
+        }
+        (op, Value::Int(a), Value::Int(b)) => {
+            let value = match op {
+                BinaryOperator::Plus => a.checked_add(*b),
+                BinaryOperator::Minus => a.checked_sub(*b),
+                BinaryOperator::Multiply => a.checked_mul(*b),
+                BinaryOperator::Divide => a.checked_div(*b),
+                BinaryOperator::Modulo => a.checked_rem(*b),
+                _ => unreachable!(),
+            };
+            Value::Int(value.ok_or_else(|| EvalError::Overflow(expr()))?)
+        }
+        (op, Value::Decimal(_), _) | (op, _, Value::Decimal(_))
+            if decimal(left).is_some() && decimal(right).is_some() =>
+        {
+            let (a, b) = (decimal(left).unwrap(), decimal(right).unwrap());
+            let value = match op {
+                BinaryOperator::Plus => a.checked_add(b),
+                BinaryOperator::Minus => a.checked_sub(b),
+                BinaryOperator::Multiply => a.checked_mul(b),
+                BinaryOperator::Divide => a.checked_div(b),
+                BinaryOperator::Modulo => a.checked_rem(b),
+                _ => unreachable!(),
+            };
+            Value::Decimal(value.ok_or_else(|| EvalError::Overflow(expr()))?)
+        }
+        (op, left, right) if left.is_numeric() && right.is_numeric() => {
+            let (a, b) = (float(left), float(right));
+            let value = match op {
+                BinaryOperator::Plus => a + b,
+                BinaryOperator::Minus => a - b,
+                BinaryOperator::Multiply => a * b,
+                BinaryOperator::Divide => a / b,
+                BinaryOperator::Modulo => a % b,
+                _ => unreachable!(),
+            };
+            // Floats are kept finite, see `DataType::parse_value`.
+            if !value.is_finite() {
+                return Err(EvalError::Overflow(expr()));
+            }
+            Value::Float(value)
+        }
+        (op, left, right) => match datetime::arithmetic(op, left, right) {
+            Some(value) => value?,
+            None => {
+                return Err(EvalError::InvalidArgument(format!(
+                    "`{}` expects numbers, found {}",
+                    op,
+                    expr()
+                )))
+            }
+        },
+    };
+
+    Ok(value)
+}
+
+/// Compute an aggregate over values that are not null, all of type `dtype`.
+pub fn aggregate<'a>(
+    name: &str,
+    values: Vec<Value>,
+    dtype: Option<&'a DataType>,
+) -> EvalResult<Operand<'a>> {
+    match name.to_uppercase().as_str() {
+        "COUNT" => Ok((Value::Int(values.len() as i64), Some(&DataType::BIGINT))),
+        // The sum can exceed the range or the precision of the column.
+        "SUM" => match sum(name, &values)? {
+            sum @ Value::Int(_) => Ok((sum, Some(&DataType::BIGINT))),
+            sum @ Value::Decimal(_) => Ok((sum, Some(&DataType::DEC))),
+            sum @ Value::Float(_) => Ok((sum, Some(&DataType::FLOAT))),
+            sum @ Value::Interval(_) => Ok((sum, Some(&DataType::INTERVAL))),
+            sum => Ok((sum, dtype)),
+        },
+        "AVG" => match sum(name, &values)? {
+            Value::Int(sum) => Ok((
+                Value::Float(sum as f64 / values.len() as f64),
+                Some(&DataType::FLOAT),
+            )),
+            Value::Float(sum) => Ok((
+                Value::Float(sum / values.len() as f64),
+                Some(&DataType::FLOAT),
+            )),
+            // Like MySQL the average has 4 more decimals than the values.
+            Value::Decimal(sum) => {
+                let scale = (sum.scale() + 4).min(Decimal::MAX_SCALE);
+                let avg = sum
+                    .checked_div(Decimal::from(values.len()))
+                    .ok_or_else(|| EvalError::Overflow(format!("{}({})", name, sum)))?
+                    .round_dp_with_strategy(scale, RoundingStrategy::MidpointAwayFromZero);
+                Ok((Value::Decimal(avg), Some(&DataType::DEC)))
+            }
+            sum @ Value::Interval(_) => {
+                let count = Value::Int(values.len() as i64);
+                let avg = arithmetic(BinaryOperator::Divide, &sum, &count)
+                    .map_err(|_| EvalError::Overflow(format!("{}({})", name, sum)))?;
+                Ok((avg, Some(&DataType::INTERVAL)))
+            }
+            _ => Ok((Value::Null, Some(&DataType::FLOAT))),
+        },
+        name => {
+            let mut values = values.into_iter();
+            let mut result = values.next().unwrap_or(Value::Null);
+            for value in values {
+                let operand = (value, dtype);
+                let ordering = comparison_type(&operand, &(result.clone(), dtype))
+                    .compare(&operand.0, &result)?
+                    .unwrap_or(Ordering::Equal);
+
+                if (name == "MIN" && ordering.is_lt()) || (name == "MAX" && ordering.is_gt()) {
+                    result = operand.0;
+                }
+            }
+            Ok((result, dtype))
+        }
+    }
+}
+
+/// Sum of numbers or of intervals, mixed numbers are added like `+` does, see
+/// [`arithmetic`].
+fn sum(name: &str, values: &[Value]) -> EvalResult<Value> {
+    let mut values = values.iter();
+    let mut sum = match values.next() {
+        Some(value) => value.clone(),
+        None => return Ok(Value::Null),
+    };
+
+    for value in values {
+        sum = match (&sum, value) {
+            (a, b) if a.is_numeric() && b.is_numeric() => arithmetic(BinaryOperator::Plus, a, b)
+                .map_err(|_| EvalError::Overflow(format!("{}({} + {})", name, a, b)))?,
+            (Value::Interval(a), Value::Interval(b)) => a
+                .checked_add(b)
+                .map(Value::Interval)
+                .ok_or_else(|| EvalError::Overflow(format!("{}({} + {})", name, a, b)))?,
+            _ => {
+                return Err(EvalError::InvalidArgument(format!(
+                    "{} expects numbers or intervals, found `{}`",
+                    name, value
+                )))
+            }
+        };
+    }
+
+    match sum {
+        Value::Int(_) | Value::Float(_) | Value::Decimal(_) | Value::Interval(_) => Ok(sum),
+        value => Err(EvalError::InvalidArgument(format!(
+            "{} expects numbers or intervals, found `{}`",
+            name, value
+        ))),
+    }
+}
+
+/// The type both sides of a comparison are coerced to. Mixing integer and
+/// float columns compares as floats, decimals with integers as decimals and
+/// two literals are compared as they are, integers as integers so large ones
+/// stay distinct. A number compared to a numeric column is mixed the same
+/// way, `salary > 150.5` doesn't truncate the literal to an integer.
+pub fn comparison_type((left, left_type): &Operand, (right, right_type): &Operand) -> DataType {
+    let numeric = |l: &DataType, r: &DataType| match l.common_type(r) {
+        Some(DataType::DECIMAL(..)) | None => DataType::DEC,
+        Some(dtype) => dtype,
+    };
+
+    match (left_type, right_type) {
+        (Some(l), Some(r)) if l.is_numeric() && r.is_numeric() && l != r => numeric(l, r),
+        (Some(dtype), None) if dtype.is_numeric() && right.is_numeric() => {
+            value_type(right).map_or(DataType::DEC, |r| numeric(dtype, r))
+        }
+        (None, Some(dtype)) if dtype.is_numeric() && left.is_numeric() => {
+            value_type(left).map_or(DataType::DEC, |l| numeric(l, dtype))
+        }
+        (Some(l), Some(r)) if l.is_temporal() && r.is_temporal() && l != r => {
+            l.common_type(r).unwrap_or_else(|| (*l).clone())
+        }
+        (Some(dtype), _) | (None, Some(dtype)) => (*dtype).clone(),
+        (None, None) if left.is_numeric() && right.is_numeric() => match (left, right) {
+            (Value::Int(_), Value::Int(_)) => DataType::BIGINT,
+            (Value::Float(_), _) | (_, Value::Float(_)) => DataType::FLOAT,
+            _ => DataType::DEC,
+        },
+        (None, None) => match (left, right) {
+            (Value::Bool(_), Value::Bool(_)) => DataType::BOOL,
+            _ => DataType::TEXT,
+        },
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use chrono::{DateTime, Utc};
+
+    use super::{aggregate, Column, Context, EvalError, Scope};
+    use crate::{
+        parser::Parser,
+        types::{DataType, DataTypesErr},
+        value::Value,
+    };
+
+    pub fn cols() -> Vec<Column> {
+        [
+            ("first_name", DataType::TEXT),
+            ("last_name", DataType::TEXT),
+            ("age", DataType::INT),
+            ("height", DataType::FLOAT),
+            ("country", DataType::VARCHAR(2)),
+            ("is_married", DataType::BOOL),
+            (
+                "level",
+                DataType::ENUM(vec!["LOW".into(), "MEDIUM".into(), "HIGH".into()]),
+            ),
+            ("born", DataType::DATE),
+            ("joined", DataType::TIMESTAMPTZ),
+            ("profile", DataType::JSON),
+        ]
+        .into_iter()
+        .map(|(name, dtype)| Column {
+            table: Some("user".into()),
+            name: name.into(),
+            dtype: Some(dtype),
+        })
+        .collect()
+    }
+
+    /// A row with the given raw values, every other column is null.
+    pub fn row(cols: &[Column], values: &[(&str, &str)]) -> Vec<Value> {
+        cols.iter()
+            .map(|col| match values.iter().find(|(c, _)| *c == col.name) {
+                Some((_, raw)) => col.dtype.as_ref().unwrap().parse_value(raw).unwrap(),
+                None => Value::Null,
+            })
+            .collect()
+    }
+
+    fn try_matches(condition: &str, values: &[(&str, &str)]) -> Result<bool, EvalError> {
+        let condition = Parser::new(condition).unwrap().parse_expr().unwrap();
+        let cols = cols();
+        let scope = Scope::new(&cols);
+        scope.validate_condition(&condition, false)?;
+
+        scope.matches(&Some(condition), &row(&cols, values))
+    }
+
+    fn matches(condition: &str, values: &[(&str, &str)]) -> bool {
+        try_matches(condition, values).unwrap()
+    }
+
+    #[test]
+    fn match_and_or_not() {
+        let entry = [("age", "21"), ("country", "EG"), ("is_married", "false")];
+
+        assert!(matches("age = 21 AND country = 'EG'", &entry));
+        assert!(!matches("age = 21 AND country = 'US'", &entry));
+        assert!(matches("country = 'US' OR age = 21", &entry));
+        assert!(matches("NOT is_married", &entry));
+        assert!(!matches("NOT (age = 21 OR country = 'US')", &entry));
+    }
+
+    #[test]
+    fn match_column_to_column() {
+        let entry = [("first_name", "jone"), ("last_name", "jone"), ("age", "21")];
+
+        assert!(matches("first_name = last_name", &entry));
+        assert!(matches("'jone' = first_name", &entry));
+        assert!(!matches("first_name != last_name", &entry));
+    }
+
+    #[test]
+    fn missing_value_never_matches() {
+        let entry = [("age", "21")];
+
+        assert!(!matches("first_name = 'jone'", &entry));
+        assert!(!matches("first_name != 'jone'", &entry));
+    }
+
+    #[test]
+    fn three_valued_logic() {
+        let entry = [("age", "21")];
+
+        assert!(matches("first_name IS NULL", &entry));
+        assert!(matches("age IS NOT NULL", &entry));
+        assert!(!matches("NOT first_name = 'jone'", &entry));
+        assert!(!matches("first_name = 'jone' OR age > 30", &entry));
+        assert!(matches("first_name = 'jone' OR age = 21", &entry));
+        assert!(!matches("NOT (first_name = 'jone' AND age = 21)", &entry));
+        assert!(matches("NOT (first_name = 'jone' AND age = 30)", &entry));
+        assert!(!matches("age = NULL", &entry));
+    }
+
+    #[test]
+    fn short_circuit_and_or() {
+        let entry = [("age", "3")];
+
+        assert!(!matches("age != 3 AND 100 / (3 - age) > 10", &entry));
+        assert!(matches("age = 3 OR 100 / (3 - age) > 10", &entry));
+        assert!(matches!(
+            try_matches("age = 3 AND 100 / (3 - age) > 10", &entry),
+            Err(EvalError::DivisionByZero(_))
+        ));
+    }
+
+    #[test]
+    fn reject_conditions_that_are_not_booleans() {
+        let entry = [("age", "3"), ("is_married", "true")];
+
+        for condition in [
+            "age",
+            "'abc'",
+            "NOT age",
+            "is_married AND first_name",
+            "CASE WHEN age THEN 1 END = 1",
+        ] {
+            assert!(
+                matches!(
+                    try_matches(condition, &entry),
+                    Err(EvalError::InvalidArgument(_))
+                ),
+                "{}",
+                condition
+            );
+        }
+        assert!(matches("is_married", &entry));
+        assert!(!matches("NULL", &entry));
+    }
+
+    #[test]
+    fn compare_using_column_types() {
+        let entry = [("age", "10"), ("height", "1.8"), ("level", "MEDIUM")];
+
+        assert!(matches("age > 9", &entry));
+        assert!(matches("height < 10", &entry));
+        assert!(matches("height < age", &entry));
+        assert!(matches("level > 'LOW' AND level < 'HIGH'", &entry));
+        assert!(matches("2 < 10", &entry));
+        assert!(matches("age > 9.5 AND age < 10.5", &entry));
+        assert!(!matches("age = 10.5", &entry));
+        assert!(matches(
+            "age IN (9.5, 10) AND age BETWEEN 9.9 AND 10.1",
+            &entry
+        ));
+        assert!(matches("height < 1.85", &entry));
+    }
+
+    #[test]
+    fn compare_with_uncoercible_literal() {
+        let entry = [("age", "10")];
+
+        assert!(matches!(
+            try_matches("age > 'ten'", &entry),
+            Err(EvalError::TypeErr(DataTypesErr::InvalidInt(_)))
+        ));
+    }
+
+    #[test]
+    fn aggregates_over_a_group() {
+        let cols = cols();
+        let scope = Scope::new(&cols);
+        let group = [
+            row(
+                &cols,
+                &[("age", "10"), ("height", "1.5"), ("level", "HIGH")],
+            ),
+            row(&cols, &[("age", "20"), ("level", "LOW")]),
+            row(
+                &cols,
+                &[("age", "20"), ("height", "2"), ("level", "MEDIUM")],
+            ),
+            row(&cols, &[]),
+        ];
+
+        let aggregate = |expr: &str| {
+            let expr = Parser::new(expr).unwrap().parse_expr().unwrap();
+            scope.validate(&expr, true).unwrap();
+            scope.eval(&expr, &group[0], Some(&group)).unwrap().0
+        };
+
+        assert_eq!(aggregate("COUNT(*)"), Value::Int(4));
+        assert_eq!(aggregate("COUNT(age)"), Value::Int(3));
+        assert_eq!(aggregate("COUNT(DISTINCT age)"), Value::Int(2));
+        assert_eq!(aggregate("COUNT(*) * 2147483647"), Value::Int(8589934588));
+        assert_eq!(aggregate("COUNT(age) * 2147483647"), Value::Int(6442450941));
+        assert_eq!(aggregate("SUM(age)"), Value::Int(50));
+        assert_eq!(aggregate("SUM(DISTINCT age)"), Value::Int(30));
+        assert_eq!(aggregate("AVG(height)"), Value::Float(1.75));
+        assert_eq!(aggregate("MIN(age)"), Value::Int(10));
+        assert_eq!(aggregate("MAX(level)"), Value::Text("HIGH".into()));
+        assert_eq!(aggregate("MIN(level)"), Value::Text("LOW".into()));
+        assert_eq!(aggregate("MAX(first_name)"), Value::Null);
+        assert_eq!(
+            aggregate("COUNT(*) > 3 AND MAX(age) = 20"),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            aggregate("SUM(age * INTERVAL '1 hour')").to_string(),
+            "50:00:00"
+        );
+        assert_eq!(
+            aggregate("AVG(age * INTERVAL '1 hour')").to_string(),
+            "16:40:00"
+        );
+    }
+
+    fn try_eval(expr: &str, values: &[(&str, &str)]) -> Result<Value, EvalError> {
+        let expr = Parser::new(expr).unwrap().parse_expr().unwrap();
+        let cols = cols();
+        let scope = Scope::new(&cols);
+        scope.validate(&expr, false)?;
+
+        Ok(scope.eval(&expr, &row(&cols, values), None)?.0)
+    }
+
+    #[test]
+    fn arithmetic_and_concatenation() {
+        let entry = [("first_name", "jone"), ("age", "21"), ("height", "1.5")];
+        let eval = |expr| try_eval(expr, &entry).unwrap();
+
+        assert_eq!(eval("age + 1 * 2"), Value::Int(23));
+        assert_eq!(eval("(age + 1) * 2"), Value::Int(44));
+        assert_eq!(eval("age / 2 - age % 2"), Value::Int(9));
+        assert_eq!(eval("-age * height"), Value::Float(-31.5));
+        assert_eq!(eval("height * 2 > age - 20"), Value::Bool(true));
+        assert_eq!(
+            eval("first_name || ' ' || age"),
+            Value::Text("jone 21".into())
+        );
+        assert_eq!(eval("last_name || 'x'"), Value::Null);
+        // Blobs are joined byte by byte.
+        assert_eq!(
+            eval("X'0A1B' || B64'AA=='"),
+            Value::Blob(vec![0x0a, 0x1b, 0])
+        );
+        assert_eq!(eval("age + NULL"), Value::Null);
+        assert!(matches!(
+            try_eval("age / (age - 21)", &entry),
+            Err(EvalError::DivisionByZero(_))
+        ));
+        assert!(matches!(
+            try_eval("age * 9223372036854775807", &entry),
+            Err(EvalError::Overflow(_))
+        ));
+        assert!(matches!(
+            try_eval("first_name + 1", &entry),
+            Err(EvalError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn case_expressions() {
+        let eval = |expr, values: &[(&str, &str)]| try_eval(expr, values).unwrap();
+        let grade = "CASE WHEN age < 13 THEN 'child' WHEN age < 20 THEN 'teen' ELSE 'adult' END";
+
+        assert_eq!(eval(grade, &[("age", "9")]), Value::Text("child".into()));
+        assert_eq!(eval(grade, &[("age", "15")]), Value::Text("teen".into()));
+        // Unknown conditions don't match, like in `WHERE`.
+        assert_eq!(eval(grade, &[]), Value::Text("adult".into()));
+
+        let level = "CASE level WHEN 'LOW' THEN 1 WHEN 'HIGH' THEN 3 END";
+        assert_eq!(eval(level, &[("level", "HIGH")]), Value::Int(3));
+        assert_eq!(eval(level, &[("level", "MEDIUM")]), Value::Null);
+        assert_eq!(eval(level, &[]), Value::Null);
+
+        assert!(matches!(
+            try_eval("CASE WHEN age > 1 THEN age ELSE 'none' END", &[]),
+            Err(EvalError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn scalar_functions_and_casts() {
+        let entry = [("first_name", "jone"), ("age", "21"), ("height", "1.5")];
+        let eval = |expr| try_eval(expr, &entry).unwrap();
+
+        assert_eq!(
+            eval("UPPER(SUBSTR(first_name, 1, 2)) || LENGTH(first_name)"),
+            Value::Text("JO4".into())
+        );
+        assert_eq!(eval("MOD(age, 4) + POWER(2, 3)"), Value::Float(9.0));
+        assert_eq!(
+            eval("IFNULL(last_name, 'none')"),
+            Value::Text("none".into())
+        );
+        assert_eq!(eval("NULLIF(age, 21)"), Value::Null);
+        assert_eq!(eval("CAST(height AS INT)"), Value::Int(2));
+        assert_eq!(eval("CAST('12' AS INT) + age"), Value::Int(33));
+        assert_eq!(eval("cast(age AS VARCHAR(2))"), Value::Text("21".into()));
+        assert!(matches!(
+            try_eval("CAST('abc' AS INT)", &entry),
+            Err(EvalError::TypeErr(DataTypesErr::InvalidInt(_)))
+        ));
+        assert!(matches!(
+            try_eval("CAST(first_name AS VARCHAR(2))", &entry),
+            Err(EvalError::TypeErr(DataTypesErr::InvalidStr(_)))
+        ));
+        assert!(matches!(
+            try_eval("ROUND(height, 1.5)", &entry),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            try_eval("UPPER(DISTINCT first_name)", &entry),
+            Err(EvalError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn sized_integer_overflow() {
+        let cols = [("tiny", DataType::TINYINT), ("small", DataType::SMALLINT)]
+            .into_iter()
+            .map(|(name, dtype)| Column {
+                table: None,
+                name: name.into(),
+                dtype: Some(dtype),
+            })
+            .collect::<Vec<_>>();
+        let row = [Value::Int(127), Value::Int(-32768)];
+        let eval = |expr: &str| {
+            let expr = Parser::new(expr).unwrap().parse_expr().unwrap();
+            Scope::new(&cols)
+                .eval(&expr, &row, None)
+                .map(|(value, _)| value)
+        };
+
+        assert_eq!(eval("tiny + 1").unwrap(), Value::Int(128));
+        assert_eq!(eval("small + tiny").unwrap(), Value::Int(-32641));
+        assert!(matches!(eval("small - tiny"), Err(EvalError::Overflow(_))));
+        assert!(matches!(eval("tiny - small"), Err(EvalError::Overflow(_))));
+        assert_eq!(eval("small * 10").unwrap(), Value::Int(-327680));
+        assert!(matches!(eval("tiny + tiny"), Err(EvalError::Overflow(_))));
+        assert_eq!(eval("small - 1 + tiny").unwrap(), Value::Int(-32642));
+        assert!(matches!(eval("small + small"), Err(EvalError::Overflow(_))));
+        assert!(matches!(
+            eval("small * 100000"),
+            Err(EvalError::Overflow(_))
+        ));
+        assert!(matches!(eval("-small"), Err(EvalError::Overflow(_))));
+    }
+
+    #[test]
+    fn exact_decimal_arithmetic() {
+        let eval = |expr| try_eval(expr, &[("age", "21")]).unwrap().to_string();
+
+        assert_eq!(eval("0.1 + 0.2"), "0.3");
+        assert_eq!(eval("1.10 * 3"), "3.30");
+        assert_eq!(eval("age * 0.5"), "10.5");
+        assert_eq!(eval("-(1.50 - 2)"), "0.50");
+        assert_eq!(eval("ROUND(2.675, 2)"), "2.68");
+        assert!(matches!(
+            try_eval("1.5 / 0.0", &[]),
+            Err(EvalError::DivisionByZero(_))
+        ));
+        assert!(matches!(
+            try_eval("79228162514264337593543950335 + 1", &[]),
+            Err(EvalError::Overflow(_))
+        ));
+        assert!(matches("0.1 + 0.2 = 0.3", &[]));
+        assert!(matches("age = 21.0 AND age > 20", &[("age", "21")]));
+
+        let values = ["0.10", "0.20", "0.25"].map(|v| Value::Decimal(v.parse().unwrap()));
+        let aggregate = |name| aggregate(name, values.to_vec(), Some(&DataType::DECIMAL(4, 2)));
+        assert_eq!(
+            aggregate("SUM").unwrap(),
+            (
+                Value::Decimal("0.55".parse().unwrap()),
+                Some(&DataType::DEC)
+            )
+        );
+        assert_eq!(aggregate("AVG").unwrap().0.to_string(), "0.183333");
+    }
+
+    #[test]
+    fn compare_large_integers() {
+        assert!(!matches("9007199254740993 = 9007199254740992", &[]));
+        assert!(matches("9007199254740993 > 9007199254740992", &[]));
+        assert!(matches("1 = CAST(1 AS FLOAT) AND 2 > 1.5", &[]));
+
+        let values = [9007199254740992, 9007199254740993].map(Value::Int);
+        assert_eq!(
+            aggregate("MAX", values.to_vec(), None).unwrap().0,
+            Value::Int(9007199254740993)
+        );
+    }
+
+    #[test]
+    fn sum_mixed_numbers() {
+        let cols = [Column {
+            table: None,
+            name: "price".into(),
+            dtype: Some(DataType::DECIMAL(10, 2)),
+        }];
+        let scope = Scope::new(&cols);
+        let group = ["0.10", "0.25", "0.50"]
+            .map(|v| vec![DataType::DECIMAL(10, 2).parse_value(v).unwrap()]);
+        let aggregate = |expr: &str| {
+            let expr = Parser::new(expr).unwrap().parse_expr().unwrap();
+            scope.validate(&expr, true).unwrap();
+            scope.eval(&expr, &group[0], Some(&group)).unwrap().0
+        };
+
+        assert_eq!(
+            aggregate("SUM(CASE WHEN price > 0.1 THEN price ELSE 0 END)").to_string(),
+            "0.75"
+        );
+        assert_eq!(
+            aggregate("SUM(CASE WHEN price > 0.1 THEN price ELSE CAST(0.5 AS FLOAT) END)"),
+            Value::Float(1.25)
+        );
+
+        let values = vec![Value::Int(1), Value::Decimal("0.5".parse().unwrap())];
+        assert_eq!(
+            super::aggregate("SUM", values, None).unwrap(),
+            (Value::Decimal("1.5".parse().unwrap()), Some(&DataType::DEC))
+        );
+    }
+
+    #[test]
+    fn json_operators() {
+        let entry = [(
+            "profile",
+            r#"{"name": "Jone", "tags": ["a", "b"], "age": 30, "none": null}"#,
+        )];
+        let eval = |expr| try_eval(expr, &entry).unwrap();
+
+        assert_eq!(eval("profile->>'name'"), Value::Text("Jone".into()));
+        assert_eq!(eval("profile->'name'"), Value::Json("Jone".into()));
+        assert_eq!(eval("profile->'tags'->>-1"), Value::Text("b".into()));
+        assert_eq!(eval("profile->'age' > 21"), Value::Bool(true));
+        assert_eq!(eval("profile->>'age' || '!'"), Value::Text("30!".into()));
+        assert_eq!(
+            eval("profile->'none'"),
+            Value::Json(serde_json::Value::Null)
+        );
+        assert_eq!(eval("profile->>'none'"), Value::Null);
+        assert_eq!(eval("profile->'missing'->'a'"), Value::Null);
+        assert_eq!(eval("'[1, 2]'->1"), Value::Json(2.into()));
+        assert_eq!(
+            eval("profile->'tags' = '[\"a\", \"b\"]'"),
+            Value::Bool(true)
+        );
+
+        assert!(matches!(
+            try_eval("age->'a'", &entry),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            try_eval("profile->born", &entry),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            try_eval("first_name->'a'", &[("first_name", "{")]),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            try_eval("profile = '{'", &entry),
+            Err(EvalError::TypeErr(DataTypesErr::InvalidJson(_)))
+        ));
+    }
+
+    #[test]
+    fn clock_reads_the_statement_time() {
+        let now = "2024-01-31T10:20:30Z".parse::<DateTime<Utc>>().unwrap();
+        let ctx = Context {
+            now,
+            ..Context::default()
+        };
+        let scope = Scope::with_context(&[], ctx);
+        let eval = |expr: &str| {
+            let expr = Parser::new(expr).unwrap().parse_expr().unwrap();
+            scope.eval(&expr, &[], None).unwrap().0
+        };
+
+        assert_eq!(eval("NOW()"), Value::TimestampTz(now));
+        assert_eq!(eval("CURRENT_TIMESTAMP()"), Value::TimestampTz(now));
+        assert_eq!(eval("CURRENT_DATE()"), Value::Date(now.date_naive()));
+        assert_eq!(eval("CURRENT_TIME()"), Value::Time(now.time()));
+    }
+
+    #[test]
+    fn date_and_time_expressions() {
+        let entry = [
+            ("age", "21"),
+            ("born", "2000-02-28"),
+            ("joined", "2024-05-01T12:00:00+02:00"),
+        ];
+        let eval = |expr| try_eval(expr, &entry).unwrap().to_string();
+
+        assert_eq!(eval("born + 2"), "2000-03-01");
+        assert_eq!(eval("born - DATE '2000-01-01'"), "58");
+        assert_eq!(eval("born + INTERVAL '1 year'"), "2001-02-28 00:00:00");
+        assert_eq!(
+            eval("joined + INTERVAL 90 MINUTES"),
+            "2024-05-01 11:30:00+00:00"
+        );
+        assert_eq!(
+            eval("joined - CAST(born AS TIMESTAMPTZ)"),
+            "8829 days 10:00:00"
+        );
+        // Text is read as the type of the other side.
+        assert_eq!(eval("born - '2000-01-01'"), "58");
+        assert_eq!(eval("joined - '2024-05-01 00:00:00'"), "10:00:00");
+        assert_eq!(
+            eval("joined - CAST('2024-04-30' AS TIMESTAMP)"),
+            "1 day 10:00:00"
+        );
+        assert_eq!(eval("EXTRACT(YEAR FROM born) + age"), "2021");
+        assert_eq!(
+            eval("DATE_TRUNC('day', joined)"),
+            "2024-05-01 00:00:00+00:00"
+        );
+        assert_eq!(eval("TO_CHAR(born, 'DD/MM/YYYY')"), "28/02/2000");
+
+        assert!(matches("born < '2000-03-01'", &entry));
+        assert!(matches("joined > born", &entry));
+        assert!(matches("joined = '2024-05-01 10:00Z'", &entry));
+        assert!(matches(
+            "born BETWEEN DATE '2000-01-01' AND CURRENT_DATE",
+            &entry
+        ));
+        assert!(matches(
+            "INTERVAL '1 day' < INTERVAL '25 hours' AND -INTERVAL '1 day' < INTERVAL '0 days'",
+            &entry
+        ));
+        assert!(matches(
+            "INTERVAL '1 day' = INTERVAL '24 hours' AND INTERVAL '1 month' = INTERVAL '30 days'",
+            &entry
+        ));
+
+        assert!(matches!(
+            try_eval("born + born", &entry),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            try_eval("born * 2", &entry),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            try_eval("EXTRACT(YEAR FROM first_name)", &entry),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            try_eval("born = '2000-02-30'", &entry),
+            Err(EvalError::TypeErr(DataTypesErr::InvalidDateTime(_)))
+        ));
+        assert!(matches!(
+            try_eval("born - 'yesterday'", &entry),
+            Err(EvalError::TypeErr(DataTypesErr::InvalidDateTime(_)))
+        ));
+    }
+
+    #[test]
+    fn pattern_and_range_predicates() {
+        let entry = [("first_name", "Jone_1"), ("age", "21"), ("level", "MEDIUM")];
+
+        assert!(matches("first_name LIKE 'J%'", &entry));
+        assert!(matches("first_name LIKE 'J__e%1'", &entry));
+        assert!(!matches("first_name LIKE 'j%'", &entry));
+        assert!(matches("first_name ILIKE 'j%'", &entry));
+        assert!(matches("first_name NOT LIKE '%x%'", &entry));
+        assert!(matches("first_name LIKE '%!_1' ESCAPE '!'", &entry));
+        assert!(!matches("first_name LIKE '%!_2' ESCAPE '!'", &entry));
+        assert!(!matches("last_name LIKE '%'", &entry));
+        assert!(!matches("last_name NOT LIKE '%'", &entry));
+
+        assert!(matches("age IN (1, 21, 3)", &entry));
+        assert!(matches("age NOT IN (1, 2)", &entry));
+        assert!(matches("level IN ('LOW', 'MEDIUM')", &entry));
+        assert!(!matches("age IN (1, NULL)", &entry));
+        assert!(!matches("age NOT IN (1, NULL)", &entry));
+
+        assert!(matches("age BETWEEN 20 AND 21", &entry));
+        assert!(matches("age NOT BETWEEN 1 AND 20", &entry));
+        assert!(matches("level BETWEEN 'LOW' AND 'MEDIUM'", &entry));
+        assert!(!matches("age BETWEEN 1 AND NULL", &entry));
+        assert!(matches("NOT age BETWEEN 30 AND NULL", &entry));
+        assert!(matches(
+            "age BETWEEN 20 AND 30 AND first_name IS NOT NULL",
+            &entry
+        ));
+
+        assert!(matches("first_name REGEXP '^J[a-z]+_[0-9]$'", &entry));
+        assert!(matches("first_name REGEXP 'one'", &entry));
+        assert!(matches("first_name NOT REGEXP '^one'", &entry));
+
+        assert!(matches!(
+            try_matches("age LIKE '2%'", &entry),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            try_matches("first_name LIKE 'a' ESCAPE 'ab'", &entry),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            try_matches("first_name LIKE 'a!' ESCAPE '!'", &entry),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            try_matches("first_name REGEXP '('", &entry),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            try_matches("age IN (1, 'one')", &entry),
+            Err(EvalError::TypeErr(DataTypesErr::InvalidInt(_)))
+        ));
+    }
+
+    #[test]
+    fn reject_misplaced_aggregates() {
+        let cols = cols();
+        let scope = Scope::new(&cols);
+        let validate = |expr: &str, allow_aggregates| {
+            let expr = Parser::new(expr).unwrap().parse_expr().unwrap();
+            scope.validate(&expr, allow_aggregates)
+        };
+
+        assert!(matches!(
+            validate("COUNT(*) > 1", false),
+            Err(EvalError::MisplacedAggregate(_))
+        ));
+        assert!(matches!(
+            validate("SUM(MAX(age))", true),
+            Err(EvalError::MisplacedAggregate(_))
+        ));
+        assert!(matches!(
+            validate("SUM(first_name)", true),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            validate("MEDIAN(age)", true),
+            Err(EvalError::UnknownFunction(_))
+        ));
+    }
+}
@@ -0,0 +1,1792 @@
+//! Runs `SELECT` queries: the tables of the `FROM` clause are joined, then
+//! their rows are filtered, grouped, sorted and projected, in this order.
+
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    iter,
+};
+
+use thiserror::Error;
+
+use crate::{
+    ast::{
+        BinaryOperator, Cte, Expr, Join, JoinKind, OrderByExpr, Query, Select, SelectItem, SetExpr,
+        SetOperator, TableFactor,
+    },
+    eval::{
+        comparison_type, Binding, Column, Context, EvalError, EvalResult, Operand, QueryRunner,
+        Scope,
+    },
+    json,
+    table::{Row, Table, TableEntries, TableError, TableResult},
+    types::DataType,
+    value::Value,
+    window::{self, Source},
+};
+
+#[derive(Debug, Error)]
+pub enum ExecutorError {
+    #[error("Table Error")]
+    TableErr(#[from] TableError),
+    #[error("Evaluation error")]
+    EvalErr(#[from] EvalError),
+    #[error("Column must appear in GROUP BY or be used in an aggregate")]
+    NotGrouped(String),
+    #[error("Table name specified more than once")]
+    DuplicateTable(String),
+    #[error("For SELECT DISTINCT, ORDER BY expressions must appear in the select list")]
+    NotProjected(String),
+    #[error("Each query must have the same number of columns")]
+    ColumnCountMismatch(String),
+    #[error("Column types of the queries don't match")]
+    TypeMismatch(String),
+    #[error("Recursive query exceeded the maximum depth")]
+    RecursionLimit(String),
+}
+
+type ExecutorResult<T> = Result<T, ExecutorError>;
+
+/// Rows read one at a time, reading a table file can fail.
+type Rows = Box<dyn Iterator<Item = TableResult<Row>>>;
+
+/// The result of a query, every entry has a value for each column.
+#[derive(Debug)]
+pub struct ResultSet {
+    pub cols: Vec<Column>,
+    pub entries: TableEntries,
+}
+
+pub struct Executor<'a> {
+    db: &'a str,
+}
+
+impl<'a> Executor<'a> {
+    pub fn new(db: &'a str) -> Self {
+        Self { db }
+    }
+
+    pub fn query(&self, query: &Query) -> ExecutorResult<ResultSet> {
+        self.execute(query, self.context())
+    }
+
+    /// Lets expressions outside of a query, like the condition of a
+    /// `DELETE`, run subqueries.
+    pub fn context(&self) -> Context<'_> {
+        Context {
+            runner: Some(self),
+            ..Context::default()
+        }
+    }
+
+    fn execute(&self, query: &Query, ctx: Context) -> ExecutorResult<ResultSet> {
+        execute_query(query, ctx, &|select, order_by, fetch, ctx| {
+            self.select(select, order_by, fetch, ctx)
+        })
+    }
+
+    fn select(
+        &self,
+        select: &Select,
+        order_by: &[OrderByExpr],
+        fetch: Option<usize>,
+        ctx: Context,
+    ) -> ExecutorResult<ResultSet> {
+        let Some(from) = &select.from else {
+            let rows = iter::once(Ok(Row::new()));
+            return execute_select(select, order_by, fetch, ctx, &[], rows);
+        };
+        let mut qualifiers = vec![from.relation.qualifier()];
+        for join in &from.joins {
+            let qualifier = join.relation.qualifier();
+            if qualifiers.contains(&qualifier) {
+                return Err(ExecutorError::DuplicateTable(qualifier.to_string()));
+            }
+            qualifiers.push(qualifier);
+        }
+
+        let (cols, rows) = self.scan(&from.relation, ctx)?;
+        if from.joins.is_empty() {
+            return execute_select(select, order_by, fetch, ctx, &cols, rows);
+        }
+
+        let mut relation = ResultSet {
+            cols,
+            entries: rows.collect::<TableResult<_>>()?,
+        };
+        for join in &from.joins {
+            if let TableFactor::Function { .. } = join.relation {
+                relation = execute_lateral_join(relation, join, ctx)?;
+                continue;
+            }
+
+            let (cols, rows) = self.scan(&join.relation, ctx)?;
+            let right = ResultSet {
+                cols,
+                entries: rows.collect::<TableResult<_>>()?,
+            };
+            relation = execute_join(relation, right, join.kind, &join.constraint, ctx)?;
+        }
+
+        execute_select(
+            select,
+            order_by,
+            fetch,
+            ctx,
+            &relation.cols,
+            relation.entries.into_iter().map(Ok),
+        )
+    }
+
+    /// The columns of the table or subquery, qualified with its alias.
+    fn scan(&self, relation: &TableFactor, ctx: Context) -> ExecutorResult<(Vec<Column>, Rows)> {
+        let (mut cols, rows): (_, Rows) = match relation {
+            TableFactor::Table { name, .. } => match ctx.cte(name) {
+                Some(result) => (
+                    result.cols.clone(),
+                    Box::new(result.entries.clone().into_iter().map(Ok)),
+                ),
+                None => {
+                    let (cols, rows) = Table::new(self.db, name)?.scan()?;
+                    (cols, Box::new(rows))
+                }
+            },
+            TableFactor::Derived { subquery, .. } => {
+                let result = self.execute(subquery, ctx)?;
+                (result.cols, Box::new(result.entries.into_iter().map(Ok)))
+            }
+            TableFactor::Function { name, args, .. } => {
+                let cols = table_function_cols(name)?;
+                let scope = Scope::with_context(&[], ctx);
+                for arg in args {
+                    scope.validate(arg, false)?;
+                }
+                let entries = table_function_rows(args, &scope, &[])?;
+                (cols, Box::new(entries.into_iter().map(Ok)))
+            }
+        };
+        for col in &mut cols {
+            col.table = Some(relation.qualifier().to_string());
+        }
+
+        Ok((cols, rows))
+    }
+}
+
+impl QueryRunner for Executor<'_> {
+    fn run(&self, query: &Query, ctx: Context) -> EvalResult<ResultSet> {
+        self.execute(query, ctx)
+            .map_err(|e| EvalError::SubqueryErr(Box::new(e)))
+    }
+}
+
+/// Runs a single `SELECT` sorted by the given keys, returning at least
+/// `fetch` of its first rows if any.
+type SelectFn<'f> =
+    dyn Fn(&Select, &[OrderByExpr], Option<usize>, Context) -> ExecutorResult<ResultSet> + 'f;
+
+/// Recursive queries give up after this many rounds, they are most likely
+/// missing a condition to stop.
+const MAX_RECURSION_DEPTH: usize = 1000;
+
+fn execute_query(query: &Query, ctx: Context, select: &SelectFn) -> ExecutorResult<ResultSet> {
+    match &query.with {
+        Some(with) => execute_with(query, &with.ctes, with.recursive, ctx, select),
+        None => execute_body(query, ctx, select),
+    }
+}
+
+/// Bind the result of each `WITH` query in order, then run the query body.
+fn execute_with(
+    query: &Query,
+    ctes: &[Cte],
+    recursive: bool,
+    ctx: Context,
+    select: &SelectFn,
+) -> ExecutorResult<ResultSet> {
+    let (cte, rest) = match ctes.split_first() {
+        Some(ctes) => ctes,
+        None => return execute_body(query, ctx, select),
+    };
+
+    let mut result = match &cte.query.body {
+        SetExpr::SetOperation {
+            op: SetOperator::Union,
+            all,
+            left,
+            right,
+        } if recursive && right.references(&cte.name) => {
+            let result = execute_recursive(cte, *all, left, right, ctx, select)?;
+            paginate(sort_result(result, &cte.query.order_by, ctx)?, &cte.query)
+        }
+        _ => execute_query(&cte.query, ctx, select)?,
+    };
+    rename_columns(&mut result, cte)?;
+
+    let binding = Binding {
+        name: &cte.name,
+        result: &result,
+        parent: ctx.ctes,
+    };
+    let ctx = Context {
+        ctes: Some(&binding),
+        ..ctx
+    };
+    execute_with(query, rest, recursive, ctx, select)
+}
+
+/// Fixpoint of `WITH RECURSIVE`: the left side of the union seeds the result,
+/// the right side runs again over the rows added by the previous round until
+/// it adds none.
+fn execute_recursive(
+    cte: &Cte,
+    all: bool,
+    left: &SetExpr,
+    right: &SetExpr,
+    ctx: Context,
+    select: &SelectFn,
+) -> ExecutorResult<ResultSet> {
+    let mut result = execute_set_expr(left, ctx, select)?;
+    rename_columns(&mut result, cte)?;
+    if !all {
+        result.entries = distinct(result.entries);
+    }
+
+    let mut seen = result.entries.iter().cloned().collect::<HashSet<_>>();
+    let mut added = result.entries.clone();
+    for depth in 1.. {
+        if added.is_empty() {
+            break;
+        } else if depth > MAX_RECURSION_DEPTH {
+            return Err(ExecutorError::RecursionLimit(cte.name.clone()));
+        }
+
+        let working = ResultSet {
+            cols: result.cols.clone(),
+            entries: added,
+        };
+        let binding = Binding {
+            name: &cte.name,
+            result: &working,
+            parent: ctx.ctes,
+        };
+        let ctx = Context {
+            ctes: Some(&binding),
+            ..ctx
+        };
+
+        let next = execute_set_expr(right, ctx, select)?;
+        if next.cols.len() != result.cols.len() {
+            return Err(ExecutorError::ColumnCountMismatch(format!(
+                "recursive {} of {} and {} columns",
+                cte.name,
+                result.cols.len(),
+                next.cols.len()
+            )));
+        }
+
+        added = coerce_rows(next.entries, &result.cols)?
+            .into_iter()
+            .filter(|row| all || seen.insert(row.clone()))
+            .collect();
+        result.entries.extend(added.iter().cloned());
+    }
+
+    Ok(result)
+}
+
+/// Name the columns of a `WITH` query after its column list, if any.
+fn rename_columns(result: &mut ResultSet, cte: &Cte) -> ExecutorResult<()> {
+    if cte.columns.is_empty() {
+        return Ok(());
+    } else if cte.columns.len() != result.cols.len() {
+        return Err(ExecutorError::ColumnCountMismatch(format!(
+            "{} has {} columns available but {} columns specified",
+            cte.name,
+            result.cols.len(),
+            cte.columns.len()
+        )));
+    }
+
+    for (col, name) in result.cols.iter_mut().zip(&cte.columns) {
+        col.name = name.clone();
+    }
+    Ok(())
+}
+
+fn execute_body(query: &Query, ctx: Context, select: &SelectFn) -> ExecutorResult<ResultSet> {
+    let result = match &query.body {
+        SetExpr::Select(body) => {
+            // Without ordering the first matching rows are the result, so
+            // there is no need to look past them.
+            let fetch = match query.limit {
+                Some(limit) if query.order_by.is_empty() => {
+                    Some(query.offset.unwrap_or(0).saturating_add(limit))
+                }
+                _ => None,
+            };
+            select(body, &query.order_by, fetch, ctx)?
+        }
+        body => sort_result(execute_set_expr(body, ctx, select)?, &query.order_by, ctx)?,
+    };
+
+    Ok(paginate(result, query))
+}
+
+/// Apply `OFFSET` and `LIMIT` of the query.
+fn paginate(mut result: ResultSet, query: &Query) -> ResultSet {
+    result.entries = result
+        .entries
+        .into_iter()
+        .skip(query.offset.unwrap_or(0))
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect();
+    result
+}
+
+fn execute_set_expr(expr: &SetExpr, ctx: Context, select: &SelectFn) -> ExecutorResult<ResultSet> {
+    match expr {
+        SetExpr::Select(body) => select(body, &[], None, ctx),
+        SetExpr::SetOperation {
+            op,
+            all,
+            left,
+            right,
+        } => {
+            let left = execute_set_expr(left, ctx, select)?;
+            let right = execute_set_expr(right, ctx, select)?;
+            set_operation(*op, *all, left, right)
+        }
+    }
+}
+
+/// Combine the rows of two queries, the columns of the left query name the
+/// result. Values of both sides are coerced to a type common to each column
+/// so equal values are equal rows.
+fn set_operation(
+    op: SetOperator,
+    all: bool,
+    left: ResultSet,
+    right: ResultSet,
+) -> ExecutorResult<ResultSet> {
+    if left.cols.len() != right.cols.len() {
+        return Err(ExecutorError::ColumnCountMismatch(format!(
+            "{} of {} and {} columns",
+            op,
+            left.cols.len(),
+            right.cols.len()
+        )));
+    }
+
+    let mut cols = left.cols;
+    for (col, other) in cols.iter_mut().zip(&right.cols) {
+        col.dtype = match (&col.dtype, &other.dtype) {
+            (Some(a), Some(b)) => Some(a.common_type(b).ok_or_else(|| {
+                ExecutorError::TypeMismatch(format!("{} of {:?} and {:?}", op, a, b))
+            })?),
+            (dtype, None) | (None, dtype) => dtype.clone(),
+        };
+    }
+
+    let left = coerce_rows(left.entries, &cols)?;
+    let right = coerce_rows(right.entries, &cols)?;
+
+    let entries = match op {
+        SetOperator::Union if all => left.into_iter().chain(right).collect(),
+        SetOperator::Union => distinct(left.into_iter().chain(right)),
+        SetOperator::Intersect | SetOperator::Except => {
+            let mut counts: HashMap<Row, usize> = HashMap::new();
+            for row in right {
+                *counts.entry(row).or_default() += 1;
+            }
+
+            let left = if all { left } else { distinct(left) };
+            let mut entries = Vec::new();
+            for row in left {
+                // With ALL each right row cancels a single left row.
+                let found = match counts.get_mut(&row) {
+                    Some(count) if *count > 0 => {
+                        if all {
+                            *count -= 1;
+                        }
+                        true
+                    }
+                    _ => false,
+                };
+
+                if found == (op == SetOperator::Intersect) {
+                    entries.push(row);
+                }
+            }
+            entries
+        }
+    };
+
+    Ok(ResultSet { cols, entries })
+}
+
+/// Coerce every value to the type of its column.
+fn coerce_rows(entries: TableEntries, cols: &[Column]) -> ExecutorResult<TableEntries> {
+    entries
+        .into_iter()
+        .map(|row| {
+            row.iter()
+                .zip(cols)
+                .map(|(value, col)| match &col.dtype {
+                    Some(dtype) => dtype.coerce(value),
+                    None => Ok(value.clone()),
+                })
+                .collect::<Result<Row, _>>()
+        })
+        .collect::<Result<TableEntries, _>>()
+        .map_err(|e| EvalError::from(e).into())
+}
+
+/// The rows in order without duplicates, only the first one is kept.
+fn distinct(rows: impl IntoIterator<Item = Row>) -> TableEntries {
+    let mut seen = HashSet::new();
+    rows.into_iter()
+        .filter(|row| seen.insert(row.clone()))
+        .collect()
+}
+
+/// Sort the result of a combined query, keys can only refer to its columns.
+fn sort_result(
+    result: ResultSet,
+    order_by: &[OrderByExpr],
+    ctx: Context,
+) -> ExecutorResult<ResultSet> {
+    let scope = Scope::with_context(&result.cols, ctx);
+    for key in order_by {
+        scope.validate(&key.expr, false)?;
+    }
+
+    let mut results = result
+        .entries
+        .into_iter()
+        .map(|row| {
+            let keys = order_by
+                .iter()
+                .map(|key| scope.eval(&key.expr, &row, None))
+                .collect::<EvalResult<Vec<_>>>()?;
+            Ok((keys, row))
+        })
+        .collect::<EvalResult<Vec<_>>>()?;
+    sort(&mut results, order_by);
+
+    Ok(ResultSet {
+        entries: results.into_iter().map(|(_, row)| row).collect(),
+        cols: result.cols.clone(),
+    })
+}
+
+/// Join the rows of two relations. Equalities between a column of each side
+/// are matched through a hash table built from the right rows, without any
+/// every pair of rows is checked against the condition.
+fn execute_join(
+    left: ResultSet,
+    right: ResultSet,
+    kind: JoinKind,
+    constraint: &Option<Expr>,
+    ctx: Context,
+) -> ExecutorResult<ResultSet> {
+    let cols = left
+        .cols
+        .iter()
+        .chain(&right.cols)
+        .cloned()
+        .collect::<Vec<_>>();
+    let scope = Scope::with_context(&cols, ctx);
+    if let Some(constraint) = constraint {
+        scope.validate_condition(constraint, false)?;
+    }
+
+    let left_scope = Scope::new(&left.cols);
+    let right_scope = Scope::new(&right.cols);
+    let (keys, condition) = match constraint {
+        Some(constraint) => equi_keys(constraint, &left_scope, &right_scope),
+        None => (vec![], None),
+    };
+
+    let index = if keys.is_empty() {
+        None
+    } else {
+        let mut index: HashMap<Vec<Value>, Vec<usize>> = HashMap::new();
+        for (pos, row) in right.entries.iter().enumerate() {
+            let right_keys = keys.iter().map(|(_, expr, dtype)| (expr, dtype));
+            if let Some(key) = join_key(&right_scope, right_keys, row)? {
+                index.entry(key).or_default().push(pos);
+            }
+        }
+        Some(index)
+    };
+    let condition = if index.is_some() {
+        condition
+    } else {
+        constraint.clone()
+    };
+
+    let all = (0..right.entries.len()).collect::<Vec<_>>();
+    let mut right_matched = vec![false; right.entries.len()];
+    let mut entries = Vec::new();
+    for left_row in &left.entries {
+        let candidates = match &index {
+            Some(index) => {
+                let left_keys = keys.iter().map(|(expr, _, dtype)| (expr, dtype));
+                join_key(&left_scope, left_keys, left_row)?
+                    .and_then(|key| index.get(&key))
+                    .map_or(&[][..], Vec::as_slice)
+            }
+            None => &all,
+        };
+
+        let mut matched = false;
+        for &pos in candidates {
+            let row = left_row
+                .iter()
+                .chain(&right.entries[pos])
+                .cloned()
+                .collect::<Row>();
+            if scope.matches(&condition, &row)? {
+                matched = true;
+                right_matched[pos] = true;
+                entries.push(row);
+            }
+        }
+
+        if !matched && matches!(kind, JoinKind::Left | JoinKind::Full) {
+            let nulls = vec![Value::Null; right.cols.len()];
+            entries.push(left_row.iter().cloned().chain(nulls).collect());
+        }
+    }
+
+    if matches!(kind, JoinKind::Right | JoinKind::Full) {
+        for (right_row, _) in right
+            .entries
+            .iter()
+            .zip(right_matched)
+            .filter(|(_, matched)| !matched)
+        {
+            let nulls = vec![Value::Null; left.cols.len()];
+            entries.push(nulls.into_iter().chain(right_row.iter().cloned()).collect());
+        }
+    }
+
+    Ok(ResultSet { cols, entries })
+}
+
+/// Join each row of `left` with the rows a table function returns for it,
+/// like `FROM file, JSON_EACH(file.doc)`.
+fn execute_lateral_join(left: ResultSet, join: &Join, ctx: Context) -> ExecutorResult<ResultSet> {
+    let TableFactor::Function { name, args, .. } = &join.relation else {
+        unreachable!()
+    };
+    // The rows of the function only exist for a row of the left side.
+    if matches!(join.kind, JoinKind::Right | JoinKind::Full) {
+        return Err(EvalError::InvalidArgument(format!(
+            "{} can only be joined with an inner, left or cross join",
+            name
+        ))
+        .into());
+    }
+
+    let mut cols = table_function_cols(name)?;
+    for col in &mut cols {
+        col.table = Some(join.relation.qualifier().to_string());
+    }
+    let scope = Scope::with_context(&left.cols, ctx);
+    for arg in args {
+        scope.validate(arg, false)?;
+    }
+
+    let mut entries = vec![];
+    for row in &left.entries {
+        let right = ResultSet {
+            cols: cols.clone(),
+            entries: table_function_rows(args, &scope, row)?,
+        };
+        let left = ResultSet {
+            cols: left.cols.clone(),
+            entries: vec![row.clone()],
+        };
+        entries.extend(execute_join(left, right, join.kind, &join.constraint, ctx)?.entries);
+    }
+
+    let cols = left.cols.into_iter().chain(cols).collect();
+    Ok(ResultSet { cols, entries })
+}
+
+/// The columns of a table function, `JSON_EACH` is the only one.
+fn table_function_cols(name: &str) -> ExecutorResult<Vec<Column>> {
+    if !name.eq_ignore_ascii_case("JSON_EACH") {
+        return Err(EvalError::UnknownFunction(name.to_string()).into());
+    }
+
+    let col = |name: &str, dtype| Column {
+        table: None,
+        name: name.into(),
+        dtype: Some(dtype),
+    };
+    Ok(vec![
+        col("key", DataType::TEXT),
+        col("value", DataType::JSON),
+    ])
+}
+
+/// The rows of `JSON_EACH(doc)` for the given row, see [`json::each`].
+fn table_function_rows(
+    args: &[Expr],
+    scope: &Scope,
+    row: &[Value],
+) -> ExecutorResult<TableEntries> {
+    let [arg] = args else {
+        return Err(EvalError::InvalidArgument(format!(
+            "JSON_EACH expects 1 arguments, found {}",
+            args.len()
+        ))
+        .into());
+    };
+
+    let document = json::document(&scope.eval(arg, row, None)?.0)?;
+    let entries = document
+        .into_iter()
+        .flat_map(json::each)
+        .map(|(key, value)| vec![key.map_or(Value::Null, Value::Text), Value::Json(value)]);
+    Ok(entries.collect())
+}
+
+/// Split a join condition into the equalities between a column of the left
+/// side and a column of the right side, along with the type the values are
+/// compared as, and whatever is left of the condition.
+fn equi_keys(
+    constraint: &Expr,
+    left: &Scope,
+    right: &Scope,
+) -> (Vec<(Expr, Expr, DataType)>, Option<Expr>) {
+    fn conjuncts<'e>(expr: &'e Expr, parts: &mut Vec<&'e Expr>) {
+        match expr {
+            Expr::BinaryOp {
+                left,
+                op: BinaryOperator::And,
+                right,
+            } => {
+                conjuncts(left, parts);
+                conjuncts(right, parts);
+            }
+            Expr::Nested(expr) => conjuncts(expr, parts),
+            expr => parts.push(expr),
+        }
+    }
+
+    // The side a column belongs to, `None` if it isn't a column of exactly one side.
+    let side = |expr: &Expr| match (left.column(expr), right.column(expr)) {
+        (Ok(Some(pos)), Err(_)) => Some((true, left.cols()[pos].dtype.as_ref())),
+        (Err(_), Ok(Some(pos))) => Some((false, right.cols()[pos].dtype.as_ref())),
+        _ => None,
+    };
+
+    let mut parts = vec![];
+    conjuncts(constraint, &mut parts);
+
+    let mut keys = vec![];
+    let mut rest = vec![];
+    for part in parts {
+        if let Expr::BinaryOp {
+            left: a,
+            op: BinaryOperator::Eq,
+            right: b,
+        } = part
+        {
+            match (side(a), side(b)) {
+                (Some((true, a_type)), Some((false, b_type))) => {
+                    let dtype = comparison_type(&(Value::Null, a_type), &(Value::Null, b_type));
+                    keys.push((a.as_ref().clone(), b.as_ref().clone(), dtype));
+                    continue;
+                }
+                (Some((false, a_type)), Some((true, b_type))) => {
+                    let dtype = comparison_type(&(Value::Null, a_type), &(Value::Null, b_type));
+                    keys.push((b.as_ref().clone(), a.as_ref().clone(), dtype));
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        rest.push(part.clone());
+    }
+
+    let rest = rest.into_iter().reduce(|left, right| Expr::BinaryOp {
+        left: Box::new(left),
+        op: BinaryOperator::And,
+        right: Box::new(right),
+    });
+    (keys, rest)
+}
+
+/// The hash key of a row, values are coerced to the type they are compared
+/// as. Rows with a null key never match.
+fn join_key<'k>(
+    scope: &Scope,
+    keys: impl Iterator<Item = (&'k Expr, &'k DataType)>,
+    row: &[Value],
+) -> EvalResult<Option<Vec<Value>>> {
+    let mut key = vec![];
+    for (expr, dtype) in keys {
+        let (value, _) = scope.eval(expr, row, None)?;
+        if value.is_null() {
+            return Ok(None);
+        }
+        key.push(dtype.coerce(&value)?);
+    }
+
+    Ok(Some(key))
+}
+
+/// Run a single `SELECT`, the rows are sorted but `OFFSET` and `LIMIT` are
+/// left to the caller. Without `fetch` every row is read.
+fn execute_select(
+    select: &Select,
+    order_by: &[OrderByExpr],
+    fetch: Option<usize>,
+    ctx: Context,
+    cols: &[Column],
+    rows: impl Iterator<Item = TableResult<Row>>,
+) -> ExecutorResult<ResultSet> {
+    let scope = Scope::with_context(cols, ctx);
+
+    let mut projection = Vec::new();
+    let mut result_cols = Vec::new();
+    let mut aliases = HashMap::new();
+    for item in &select.projection {
+        match item {
+            SelectItem::Wildcard | SelectItem::QualifiedWildcard(_) => {
+                let table = match item {
+                    SelectItem::QualifiedWildcard(table) => Some(table),
+                    _ => None,
+                };
+                let cols = scope
+                    .cols()
+                    .iter()
+                    .filter(|col| table.is_none_or(|table| col.table.as_ref() == Some(table)))
+                    .collect::<Vec<_>>();
+                if let (Some(table), true) = (table, cols.is_empty()) {
+                    return Err(EvalError::ColNotFound(format!("{}.*", table)).into());
+                }
+
+                for col in cols {
+                    projection.push(match &col.table {
+                        Some(table) => Expr::QualifiedIdentifier {
+                            table: table.clone(),
+                            column: col.name.clone(),
+                        },
+                        None => Expr::Identifier(col.name.clone()),
+                    });
+                    result_cols.push(col.clone());
+                }
+            }
+            SelectItem::Expr(expr) => {
+                projection.push(expr.clone());
+                result_cols.push(match scope.column(expr) {
+                    Ok(Some(pos)) => scope.cols()[pos].clone(),
+                    _ => Column {
+                        table: None,
+                        name: expr.to_string(),
+                        dtype: scope.expr_type(expr),
+                    },
+                });
+            }
+            SelectItem::ExprWithAlias { expr, alias } => {
+                projection.push(expr.clone());
+                result_cols.push(Column {
+                    table: None,
+                    name: alias.clone(),
+                    dtype: scope.expr_type(expr),
+                });
+                aliases.entry(alias.as_str()).or_insert(expr);
+            }
+        }
+    }
+
+    // A bare name in `ORDER BY` is an alias of the select list before being
+    // a column of the table.
+    let sort_keys = order_by
+        .iter()
+        .map(|key| match &key.expr {
+            Expr::Identifier(name) if aliases.contains_key(name.as_str()) => {
+                aliases[name.as_str()].clone()
+            }
+            expr => expr.clone(),
+        })
+        .collect::<Vec<_>>();
+    let aggregate = !select.group_by.is_empty()
+        || select.having.is_some()
+        || projection
+            .iter()
+            .chain(&sort_keys)
+            .any(Expr::contains_aggregate);
+
+    // Window functions are replaced with columns appended to every row, their
+    // values are computed once every row is known.
+    let mut windows = Vec::new();
+    let mut extract = |exprs: &[Expr]| {
+        exprs
+            .iter()
+            .map(|expr| window::extract(expr, &mut windows))
+            .collect::<Vec<_>>()
+    };
+    let (window_projection, window_sort_keys) = (extract(&projection), extract(&sort_keys));
+    let mut window_cols = cols.to_vec();
+    for expr in &windows {
+        window::validate(&scope, expr, aggregate)?;
+        window_cols.push(Column {
+            table: None,
+            name: expr.to_string(),
+            dtype: scope.expr_type(expr),
+        });
+    }
+    let window_scope = Scope::with_context(&window_cols, ctx);
+
+    if let Some(selection) = &select.selection {
+        scope.validate_condition(selection, false)?;
+    }
+    for expr in &select.group_by {
+        scope.validate(expr, false)?;
+    }
+    let extracted = window_projection.iter().chain(&window_sort_keys);
+    for (expr, original) in extracted.zip(projection.iter().chain(&sort_keys)) {
+        window_scope.validate(expr, aggregate)?;
+        if aggregate {
+            check_grouped(original, &select.group_by)?;
+        }
+    }
+    if let Some(having) = &select.having {
+        scope.validate_condition(having, aggregate)?;
+        check_grouped(having, &select.group_by)?;
+    }
+    let (projection, sort_keys) = (window_projection, window_sort_keys);
+    if select.distinct {
+        // Rows that only differ by a key would be merged, so there would be
+        // no single key to sort them by.
+        for expr in &sort_keys {
+            let projected = projection.iter().any(|col| {
+                col == expr
+                    || matches!((window_scope.column(col), window_scope.column(expr)),
+                        (Ok(Some(a)), Ok(Some(b))) if a == b)
+            });
+            if !projected {
+                return Err(ExecutorError::NotProjected(expr.to_string()));
+            }
+        }
+    }
+
+    let mut results = Vec::new();
+    let mut seen = HashSet::new();
+    // Rows or groups waiting for the values of the window functions.
+    let mut sources: Vec<Source> = Vec::new();
+    let mut groups: Vec<Vec<Row>> = Vec::new();
+    if !aggregate {
+        // Window functions need every row, even past the ones fetched.
+        let enough = match windows.is_empty() {
+            true => fetch.unwrap_or(usize::MAX),
+            false => usize::MAX,
+        };
+        for row in rows {
+            if results.len() >= enough {
+                break;
+            }
+
+            let row = row?;
+            if !scope.matches(&select.selection, &row)? {
+                continue;
+            }
+            if !windows.is_empty() {
+                sources.push((row, None));
+                continue;
+            }
+
+            let (keys, row) = project(&window_scope, &projection, &sort_keys, &row, None)?;
+            if !select.distinct || seen.insert(row.clone()) {
+                results.push((keys, row));
+            }
+        }
+    } else {
+        let mut group_pos: HashMap<Vec<Value>, usize> = HashMap::new();
+        for row in rows {
+            let row = row?;
+            if !scope.matches(&select.selection, &row)? {
+                continue;
+            }
+
+            let key = select
+                .group_by
+                .iter()
+                .map(|expr| Ok(scope.eval(expr, &row, None)?.0))
+                .collect::<Result<Vec<_>, EvalError>>()?;
+            match group_pos.entry(key) {
+                Entry::Occupied(pos) => groups[*pos.get()].push(row),
+                Entry::Vacant(pos) => {
+                    pos.insert(groups.len());
+                    groups.push(vec![row]);
+                }
+            }
+        }
+
+        // Without GROUP BY every row belongs to a single group, even when there are none.
+        if select.group_by.is_empty() && groups.is_empty() {
+            groups.push(vec![]);
+        }
+
+        let empty_row = vec![Value::Null; cols.len()];
+        for group in &groups {
+            let row = group.first().unwrap_or(&empty_row);
+            if let Some(having) = &select.having {
+                if scope.eval_predicate(having, row, Some(group))? != Some(true) {
+                    continue;
+                }
+            }
+            if !windows.is_empty() {
+                sources.push((row.clone(), Some(group)));
+                continue;
+            }
+
+            let (keys, row) = project(&window_scope, &projection, &sort_keys, row, Some(group))?;
+            if !select.distinct || seen.insert(row.clone()) {
+                results.push((keys, row));
+            }
+        }
+    }
+
+    if !windows.is_empty() {
+        let values = windows
+            .iter()
+            .map(|expr| window::evaluate(&scope, expr, &sources))
+            .collect::<Result<Vec<_>, _>>()?;
+        for (i, (row, group)) in sources.iter().enumerate() {
+            let mut row = row.clone();
+            row.extend(values.iter().map(|values| values[i].clone()));
+
+            let (keys, row) = project(&window_scope, &projection, &sort_keys, &row, *group)?;
+            if !select.distinct || seen.insert(row.clone()) {
+                results.push((keys, row));
+            }
+        }
+    }
+
+    sort(&mut results, order_by);
+    let entries = results.into_iter().map(|(_, row)| row).collect();
+
+    Ok(ResultSet {
+        cols: result_cols,
+        entries,
+    })
+}
+
+/// Columns used outside of an aggregate must be grouped, otherwise there is
+/// no single value for the group.
+fn check_grouped(expr: &Expr, group_by: &[Expr]) -> ExecutorResult<()> {
+    if expr.is_aggregate() || group_by.contains(expr) {
+        return Ok(());
+    }
+
+    match expr {
+        Expr::Identifier(_) | Expr::QualifiedIdentifier { .. } => {
+            Err(ExecutorError::NotGrouped(expr.to_string()))
+        }
+        Expr::Literal(_) | Expr::Wildcard => Ok(()),
+        Expr::BinaryOp { left, right, .. } => {
+            check_grouped(left, group_by)?;
+            check_grouped(right, group_by)
+        }
+        Expr::UnaryOp { expr, .. }
+        | Expr::IsNull { expr, .. }
+        | Expr::Nested(expr)
+        | Expr::Cast { expr, .. }
+        | Expr::InSubquery { expr, .. } => check_grouped(expr, group_by),
+        // Columns of a correlated subquery are read from the first row of the group.
+        Expr::Subquery(_) | Expr::Exists(_) => Ok(()),
+        Expr::Case { .. } => expr
+            .case_exprs()
+            .into_iter()
+            .try_for_each(|expr| check_grouped(expr, group_by)),
+        Expr::InList { .. } | Expr::Between { .. } | Expr::Like { .. } => expr
+            .operands()
+            .into_iter()
+            .try_for_each(|expr| check_grouped(expr, group_by)),
+        Expr::Function { args, .. } => args.iter().try_for_each(|arg| check_grouped(arg, group_by)),
+        Expr::Window { args, window, .. } => args
+            .iter()
+            .chain(window.exprs())
+            .try_for_each(|expr| check_grouped(expr, group_by)),
+    }
+}
+
+/// The projected row along with its sort keys, both are computed from the
+/// same row or group.
+fn project<'s>(
+    scope: &Scope<'s>,
+    projection: &[Expr],
+    sort_keys: &[Expr],
+    row: &[Value],
+    group: Option<&[Row]>,
+) -> Result<(Vec<Operand<'s>>, Row), EvalError> {
+    let keys = sort_keys
+        .iter()
+        .map(|expr| scope.eval(expr, row, group))
+        .collect::<Result<_, _>>()?;
+    let row = projection
+        .iter()
+        .map(|expr| Ok(scope.eval(expr, row, group)?.0))
+        .collect::<Result<_, EvalError>>()?;
+
+    Ok((keys, row))
+}
+
+/// Stable sort by every key in order.
+fn sort<T>(results: &mut [(Vec<Operand>, T)], order_by: &[OrderByExpr]) {
+    if order_by.is_empty() {
+        return;
+    }
+
+    results.sort_by(|(a, _), (b, _)| compare_keys(a, b, order_by));
+}
+
+/// Compare the sort keys of two rows, each key is compared using the type of
+/// its column.
+pub fn compare_keys(a: &[Operand], b: &[Operand], order_by: &[OrderByExpr]) -> Ordering {
+    order_by
+        .iter()
+        .zip(a.iter().zip(b))
+        .map(|(key, (a, b))| {
+            let ordering = match (a.0.is_null(), b.0.is_null()) {
+                (true, true) => return Ordering::Equal,
+                (true, false) if key.nulls_first() => return Ordering::Less,
+                (true, false) => return Ordering::Greater,
+                (false, true) if key.nulls_first() => return Ordering::Greater,
+                (false, true) => return Ordering::Less,
+                // Values of a key share the same type, coercion can't fail.
+                (false, false) => comparison_type(a, b)
+                    .compare(&a.0, &b.0)
+                    .ok()
+                    .flatten()
+                    .unwrap_or(Ordering::Equal),
+            };
+
+            if key.desc {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter;
+
+    use super::{
+        execute_join, execute_lateral_join, execute_query, execute_select, ExecutorError, ResultSet,
+    };
+    use crate::{
+        ast::{JoinKind, Query, SetExpr, Statement, TableFactor},
+        eval::{
+            tests::{cols, row},
+            Column, Context, EvalError, QueryRunner,
+        },
+        parser::Parser,
+        table::Row,
+        types::DataType,
+        value::Value,
+    };
+
+    /// Every table of `FROM` is made of the same rows, qualified with its
+    /// name or alias.
+    struct Memory<'m> {
+        cols: &'m [Column],
+        rows: &'m [Row],
+    }
+
+    impl Memory<'_> {
+        fn query(&self, query: &Query, ctx: Context) -> Result<ResultSet, ExecutorError> {
+            execute_query(query, ctx, &|select, order_by, fetch, ctx| {
+                let Some(from) = &select.from else {
+                    let rows = iter::once(Ok(Row::new()));
+                    return execute_select(select, order_by, fetch, ctx, &[], rows);
+                };
+                let relation = &from.relation;
+                let (cols, rows) = match relation {
+                    TableFactor::Table { name, .. } if ctx.cte(name).is_some() => {
+                        let result = ctx.cte(name).unwrap();
+                        (result.cols.as_slice(), result.entries.as_slice())
+                    }
+                    _ => (self.cols, self.rows),
+                };
+
+                let cols = cols
+                    .iter()
+                    .map(|col| Column {
+                        table: Some(relation.qualifier().into()),
+                        ..col.clone()
+                    })
+                    .collect::<Vec<_>>();
+                let rows = rows.iter().cloned().map(Ok);
+                execute_select(select, order_by, fetch, ctx, &cols, rows)
+            })
+        }
+    }
+
+    impl QueryRunner for Memory<'_> {
+        fn run(&self, query: &Query, ctx: Context) -> Result<ResultSet, EvalError> {
+            self.query(query, ctx)
+                .map_err(|e| EvalError::SubqueryErr(Box::new(e)))
+        }
+    }
+
+    fn run(query: &str, cols: &[Column], rows: &[Row]) -> Result<ResultSet, ExecutorError> {
+        let query = match Parser::new(query).unwrap().parse_statement().unwrap() {
+            Statement::Select(query) => query,
+            _ => unreachable!(),
+        };
+
+        let memory = Memory { cols, rows };
+        let ctx = Context {
+            runner: Some(&memory),
+            ..Context::default()
+        };
+        memory.query(&query, ctx)
+    }
+
+    fn select(query: &str) -> Result<ResultSet, ExecutorError> {
+        let cols = cols();
+        let rows = [
+            row(
+                &cols,
+                &[("first_name", "a"), ("age", "10"), ("level", "HIGH")],
+            ),
+            row(&cols, &[("first_name", "b"), ("level", "LOW")]),
+            row(
+                &cols,
+                &[("first_name", "c"), ("age", "9"), ("level", "LOW")],
+            ),
+            row(
+                &cols,
+                &[("first_name", "d"), ("age", "10"), ("level", "MEDIUM")],
+            ),
+        ];
+
+        run(query, &cols, &rows)
+    }
+
+    fn values(query: &str) -> Vec<Vec<String>> {
+        select(query)
+            .unwrap()
+            .entries
+            .iter()
+            .map(|row| row.iter().map(Value::to_string).collect())
+            .collect()
+    }
+
+    fn names(query: &str) -> Vec<String> {
+        values(query).into_iter().map(|row| row.join(" ")).collect()
+    }
+
+    #[test]
+    fn sort_by_many_keys() {
+        let query =
+            |order_by: &str| names(&format!("SELECT first_name FROM t ORDER BY {}", order_by));
+
+        assert_eq!(query("age"), vec!["c", "a", "d", "b"]);
+        assert_eq!(query("age DESC, level"), vec!["b", "d", "a", "c"]);
+        assert_eq!(query("age DESC NULLS LAST"), vec!["a", "d", "c", "b"]);
+        assert_eq!(query("level DESC, first_name"), vec!["a", "d", "b", "c"]);
+    }
+
+    #[test]
+    fn limit_and_offset() {
+        assert_eq!(
+            names("SELECT first_name FROM t LIMIT 2 OFFSET 1"),
+            vec!["b", "c"]
+        );
+        assert_eq!(
+            names("SELECT first_name FROM t ORDER BY first_name DESC FETCH FIRST 1 ROW ONLY"),
+            vec!["d"]
+        );
+    }
+
+    #[test]
+    fn aggregate_without_group_by() {
+        assert_eq!(
+            names("SELECT COUNT(*), COUNT(age), SUM(age), MAX(level) FROM t"),
+            vec!["4 3 29 HIGH"]
+        );
+        assert_eq!(
+            names("SELECT COUNT(*), SUM(age) FROM t WHERE age > 100"),
+            vec!["0 NULL"]
+        );
+    }
+
+    #[test]
+    fn group_by_with_having() {
+        assert_eq!(
+            names("SELECT level, COUNT(*), AVG(age) FROM t GROUP BY level ORDER BY level"),
+            vec!["LOW 2 9", "MEDIUM 1 10", "HIGH 1 10"]
+        );
+        assert_eq!(
+            names("SELECT age, COUNT(*) FROM t GROUP BY age HAVING COUNT(*) > 1"),
+            vec!["10 2"]
+        );
+        assert_eq!(
+            names("SELECT level FROM t GROUP BY level ORDER BY COUNT(*) DESC, level LIMIT 1"),
+            vec!["LOW"]
+        );
+    }
+
+    #[test]
+    fn result_columns() {
+        let result = select("SELECT level, COUNT(*) FROM t GROUP BY level").unwrap();
+        let cols = result
+            .cols
+            .iter()
+            .map(|col| col.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(cols, vec!["level", "COUNT(*)"]);
+    }
+
+    #[test]
+    fn reject_ungrouped_columns() {
+        assert!(matches!(
+            select("SELECT first_name, COUNT(*) FROM t GROUP BY level"),
+            Err(ExecutorError::NotGrouped(col)) if col == "first_name"
+        ));
+        assert!(matches!(
+            select("SELECT level FROM t WHERE COUNT(*) > 1 GROUP BY level"),
+            Err(ExecutorError::EvalErr(EvalError::MisplacedAggregate(_)))
+        ));
+    }
+
+    #[test]
+    fn select_distinct() {
+        assert_eq!(
+            names("SELECT DISTINCT age FROM t ORDER BY age"),
+            vec!["9", "10", "NULL"]
+        );
+        assert_eq!(
+            names("SELECT DISTINCT age, level FROM t WHERE age = 10"),
+            vec!["10 HIGH", "10 MEDIUM"]
+        );
+        assert!(matches!(
+            select("SELECT DISTINCT level FROM t ORDER BY age"),
+            Err(ExecutorError::NotProjected(col)) if col == "age"
+        ));
+    }
+
+    #[test]
+    fn set_operations() {
+        assert_eq!(
+            names("SELECT level FROM t WHERE age = 10 UNION SELECT level FROM t ORDER BY level"),
+            vec!["LOW", "MEDIUM", "HIGH"]
+        );
+        assert_eq!(
+            names("SELECT age FROM t UNION ALL SELECT age FROM t").len(),
+            8
+        );
+        assert_eq!(
+            names("SELECT age FROM t WHERE age > 9 INTERSECT SELECT age FROM t"),
+            vec!["10"]
+        );
+        assert_eq!(
+            names("SELECT age FROM t WHERE age > 9 INTERSECT ALL SELECT age FROM t"),
+            vec!["10", "10"]
+        );
+        assert_eq!(
+            names("SELECT age FROM t EXCEPT SELECT age FROM t WHERE age = 10"),
+            vec!["NULL", "9"]
+        );
+        assert_eq!(
+            names("SELECT age FROM t EXCEPT ALL SELECT age FROM t WHERE first_name = 'a'"),
+            vec!["NULL", "9", "10"]
+        );
+        assert_eq!(
+            names("SELECT age FROM t UNION SELECT height FROM t LIMIT 1 OFFSET 1"),
+            vec!["NULL"]
+        );
+    }
+
+    #[test]
+    fn reject_incompatible_queries() {
+        assert!(matches!(
+            select("SELECT age, level FROM t UNION SELECT age FROM t"),
+            Err(ExecutorError::ColumnCountMismatch(_))
+        ));
+        assert!(matches!(
+            select("SELECT age FROM t INTERSECT SELECT level FROM t"),
+            Err(ExecutorError::TypeMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn scalar_subqueries() {
+        assert_eq!(
+            names("SELECT first_name FROM t WHERE age = (SELECT MAX(age) FROM t)"),
+            vec!["a", "d"]
+        );
+        assert_eq!(
+            names("SELECT first_name, (SELECT COUNT(*) FROM t) FROM t LIMIT 1"),
+            vec!["a 4"]
+        );
+        // The average is a float, the integer column isn't coerced to it.
+        assert_eq!(
+            names("SELECT first_name FROM t WHERE age > (SELECT AVG(age) FROM t)"),
+            vec!["a", "d"]
+        );
+        assert_eq!(
+            names("SELECT first_name FROM t WHERE age > (SELECT age FROM t WHERE age > 100)"),
+            Vec::<String>::new()
+        );
+        assert!(matches!(
+            select("SELECT first_name FROM t WHERE age = (SELECT age FROM t)"),
+            Err(ExecutorError::EvalErr(EvalError::InvalidArgument(_)))
+        ));
+    }
+
+    #[test]
+    fn in_and_exists_subqueries() {
+        assert_eq!(
+            names("SELECT first_name FROM t WHERE level IN (SELECT level FROM t WHERE age = 10)"),
+            vec!["a", "d"]
+        );
+        assert_eq!(
+            names("SELECT first_name FROM t WHERE age NOT IN (SELECT AVG(age) FROM t)"),
+            vec!["a", "c", "d"]
+        );
+        // The null of `b` makes every other age unknown.
+        assert_eq!(
+            names(
+                "SELECT first_name FROM t WHERE age NOT IN (SELECT age FROM t WHERE level = 'LOW')"
+            ),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            names(
+                "SELECT first_name FROM t AS o WHERE EXISTS \
+                 (SELECT * FROM t WHERE t.level = o.level AND t.first_name != o.first_name)"
+            ),
+            vec!["b", "c"]
+        );
+        assert_eq!(
+            names(
+                "SELECT first_name FROM t AS o WHERE NOT EXISTS \
+                 (SELECT * FROM t WHERE age > o.age)"
+            ),
+            vec!["a", "b", "d"]
+        );
+    }
+
+    #[test]
+    fn common_table_expressions() {
+        assert_eq!(
+            names("WITH young (name) AS (SELECT first_name FROM t WHERE age < 10) SELECT name FROM young"),
+            vec!["c"]
+        );
+        assert_eq!(
+            names(
+                "WITH ten AS (SELECT age FROM t WHERE age = 10), \
+                 low AS (SELECT DISTINCT age FROM ten) \
+                 SELECT first_name FROM t WHERE age IN (SELECT age FROM low)"
+            ),
+            vec!["a", "d"]
+        );
+        assert!(matches!(
+            select("WITH x (a, b) AS (SELECT age FROM t) SELECT * FROM x"),
+            Err(ExecutorError::ColumnCountMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn recursive_common_table_expressions() {
+        // Each round adds the rows younger than the youngest of the last round.
+        assert_eq!(
+            names(
+                "WITH RECURSIVE r AS (SELECT first_name, age FROM t WHERE first_name = 'a' \
+                 UNION SELECT first_name, age FROM t WHERE age < (SELECT MIN(age) FROM r)) \
+                 SELECT first_name FROM r"
+            ),
+            vec!["a", "c"]
+        );
+        assert_eq!(
+            names(
+                "WITH RECURSIVE r AS (SELECT level FROM t UNION SELECT level FROM r) \
+                 SELECT level FROM r ORDER BY level"
+            ),
+            vec!["LOW", "MEDIUM", "HIGH"]
+        );
+        assert!(matches!(
+            select(
+                "WITH RECURSIVE r AS (SELECT level FROM t UNION ALL SELECT level FROM r) \
+                 SELECT level FROM r"
+            ),
+            Err(ExecutorError::RecursionLimit(name)) if name == "r"
+        ));
+        assert_eq!(
+            names(
+                "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x+1 FROM n WHERE x<5) \
+                 SELECT x FROM n"
+            ),
+            vec!["1", "2", "3", "4", "5"]
+        );
+        // A union that doesn't read itself runs once.
+        assert_eq!(
+            names(
+                "WITH RECURSIVE a AS (SELECT age FROM t UNION ALL SELECT age FROM t) \
+                 SELECT COUNT(*) FROM a"
+            ),
+            vec!["8"]
+        );
+    }
+
+    #[test]
+    fn select_without_from() {
+        assert_eq!(names("SELECT 1 + 1, 'a' AS b"), vec!["2 a"]);
+        assert_eq!(names("SELECT 1 WHERE 1 = 2"), Vec::<String>::new());
+        assert_eq!(
+            names("SELECT COUNT(*), (SELECT COUNT(*) FROM t)"),
+            vec!["1 4"]
+        );
+    }
+
+    #[test]
+    fn computed_columns() {
+        let result = select(
+            "SELECT first_name || '!' AS shout, age * 2 doubled, \
+             CASE WHEN age > 9 THEN 'old' ELSE 'young' END, 1 FROM t ORDER BY doubled DESC, shout",
+        )
+        .unwrap();
+
+        assert_eq!(
+            result
+                .cols
+                .iter()
+                .map(|col| (col.name.as_str(), col.dtype.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("shout", Some(DataType::TEXT)),
+                ("doubled", Some(DataType::INT)),
+                (
+                    "CASE WHEN age > 9 THEN 'old' ELSE 'young' END",
+                    Some(DataType::TEXT)
+                ),
+                ("1", Some(DataType::INT)),
+            ]
+        );
+        assert_eq!(
+            names(
+                "SELECT first_name || '!' AS shout, age * 2 doubled, \
+                 CASE WHEN age > 9 THEN 'old' ELSE 'young' END, 1 FROM t ORDER BY doubled DESC, shout"
+            ),
+            vec!["b! NULL young 1", "a! 20 old 1", "d! 20 old 1", "c! 18 young 1"]
+        );
+        assert_eq!(
+            names("SELECT level, SUM(age) * 10 + COUNT(*) AS score FROM t GROUP BY level ORDER BY score"),
+            vec!["LOW 92", "HIGH 101", "MEDIUM 101"]
+        );
+        assert!(matches!(
+            select("SELECT age / 0 FROM t"),
+            Err(ExecutorError::EvalErr(EvalError::DivisionByZero(_)))
+        ));
+        assert!(matches!(
+            select("SELECT age + level FROM t"),
+            Err(ExecutorError::EvalErr(EvalError::InvalidArgument(_)))
+        ));
+    }
+
+    #[test]
+    fn scalar_functions() {
+        assert_eq!(
+            names(
+                "SELECT UPPER(first_name), COALESCE(age, -1), LENGTH(level) FROM t \
+                 WHERE LOWER(level) != 'medium' ORDER BY ABS(COALESCE(age, 0) - 9), first_name"
+            ),
+            vec!["C 9 3", "A 10 4", "B -1 3"]
+        );
+        assert_eq!(
+            names(
+                "SELECT CAST(age AS TEXT) || '!', ROUND(AVG(age)) FROM t GROUP BY age ORDER BY age"
+            ),
+            vec!["9! 9", "10! 10", "NULL NULL"]
+        );
+        assert!(matches!(
+            select("SELECT UPPER(age) FROM t"),
+            Err(ExecutorError::EvalErr(EvalError::InvalidArgument(_)))
+        ));
+        assert!(matches!(
+            select("SELECT first_name FROM t WHERE SUBSTR(first_name) = 'a'"),
+            Err(ExecutorError::EvalErr(EvalError::InvalidArgument(_)))
+        ));
+        assert!(matches!(
+            select("SELECT INITCAP(first_name) FROM t"),
+            Err(ExecutorError::EvalErr(EvalError::UnknownFunction(_)))
+        ));
+    }
+
+    #[test]
+    fn ranking_window_functions() {
+        assert_eq!(
+            names(
+                "SELECT first_name, ROW_NUMBER() OVER (ORDER BY age), RANK() OVER (ORDER BY age), \
+                 DENSE_RANK() OVER (ORDER BY age) FROM t ORDER BY first_name"
+            ),
+            vec!["a 2 2 2", "b 4 4 3", "c 1 1 1", "d 3 2 2"]
+        );
+        assert_eq!(
+            names(
+                "SELECT first_name, ROW_NUMBER() OVER (PARTITION BY level ORDER BY first_name DESC) \
+                 FROM t ORDER BY first_name"
+            ),
+            vec!["a 1", "b 2", "c 1", "d 1"]
+        );
+        assert_eq!(
+            names("SELECT first_name FROM t ORDER BY ROW_NUMBER() OVER (ORDER BY first_name DESC) LIMIT 2"),
+            vec!["d", "c"]
+        );
+        assert_eq!(
+            names(
+                "SELECT level, COUNT(*), RANK() OVER (ORDER BY COUNT(*) DESC) \
+                 FROM t GROUP BY level ORDER BY level"
+            ),
+            vec!["LOW 2 1", "MEDIUM 1 2", "HIGH 1 2"]
+        );
+        // Counts and ranks are `BIGINT`, they don't overflow an `INT`.
+        assert_eq!(
+            names(
+                "SELECT RANK() OVER (ORDER BY first_name) * 2147483647 FROM t \
+                 ORDER BY first_name LIMIT 2"
+            ),
+            vec!["2147483647", "4294967294"]
+        );
+    }
+
+    #[test]
+    fn value_window_functions() {
+        assert_eq!(
+            names(
+                "SELECT first_name, LAG(first_name) OVER (ORDER BY first_name), \
+                 LEAD(first_name, 2, 'none') OVER (ORDER BY first_name) FROM t ORDER BY first_name"
+            ),
+            vec!["a NULL c", "b a d", "c b none", "d c none"]
+        );
+        assert_eq!(
+            names(
+                "SELECT first_name, FIRST_VALUE(first_name) OVER (ORDER BY age), \
+                 LAST_VALUE(first_name) OVER (ORDER BY age), \
+                 LAST_VALUE(first_name) OVER (ORDER BY age ROWS BETWEEN CURRENT ROW AND UNBOUNDED FOLLOWING) \
+                 FROM t ORDER BY first_name"
+            ),
+            vec!["a c d b", "b c b b", "c c c b", "d c d b"]
+        );
+    }
+
+    #[test]
+    fn windowed_aggregates() {
+        assert_eq!(
+            names(
+                "SELECT first_name, SUM(age) OVER (ORDER BY first_name), COUNT(*) OVER (PARTITION BY level), \
+                 SUM(age) OVER (ORDER BY first_name ROWS BETWEEN 1 PRECEDING AND CURRENT ROW), \
+                 SUM(age) OVER (ORDER BY age), MAX(first_name) OVER () FROM t ORDER BY first_name"
+            ),
+            vec!["a 10 1 10 29 d", "b 10 2 10 29 d", "c 19 2 9 9 d", "d 29 1 19 29 d"]
+        );
+        assert_eq!(
+            names(
+                "SELECT first_name, COUNT(age) OVER (ORDER BY first_name ROWS BETWEEN 2 FOLLOWING AND UNBOUNDED FOLLOWING) \
+                 FROM t ORDER BY first_name"
+            ),
+            vec!["a 2", "b 1", "c 0", "d 0"]
+        );
+    }
+
+    #[test]
+    fn reject_misplaced_window_functions() {
+        assert!(matches!(
+            select("SELECT first_name FROM t WHERE ROW_NUMBER() OVER () = 1"),
+            Err(ExecutorError::EvalErr(EvalError::MisplacedWindow(_)))
+        ));
+        assert!(matches!(
+            select("SELECT SUM(ROW_NUMBER() OVER ()) FROM t"),
+            Err(ExecutorError::EvalErr(EvalError::MisplacedWindow(_)))
+        ));
+        assert!(matches!(
+            select("SELECT RANK(age) OVER (ORDER BY age) FROM t"),
+            Err(ExecutorError::EvalErr(EvalError::InvalidArgument(_)))
+        ));
+        assert!(matches!(
+            select("SELECT level, RANK() OVER (ORDER BY age) FROM t GROUP BY level"),
+            Err(ExecutorError::NotGrouped(col)) if col == "age"
+        ));
+    }
+
+    /// Users `a`, `b` and `c` joined with orders of `a`, `a`, `c` and `z`.
+    fn join(kind: JoinKind, constraint: Option<&str>) -> Result<ResultSet, ExecutorError> {
+        let mut users = cols();
+        for col in &mut users {
+            col.table = Some("u".into());
+        }
+        let users = ResultSet {
+            entries: ["a", "b", "c"]
+                .iter()
+                .map(|name| row(&users, &[("first_name", name)]))
+                .collect(),
+            cols: users,
+        };
+
+        let orders = ResultSet {
+            cols: [("first_name", DataType::TEXT), ("amount", DataType::INT)]
+                .into_iter()
+                .map(|(name, dtype)| Column {
+                    table: Some("o".into()),
+                    name: name.into(),
+                    dtype: Some(dtype),
+                })
+                .collect(),
+            entries: [("a", 5), ("a", 7), ("c", 1), ("z", 3)]
+                .into_iter()
+                .map(|(name, amount)| vec![Value::Text(name.into()), Value::Int(amount)])
+                .collect(),
+        };
+
+        let constraint = constraint.map(|on| Parser::new(on).unwrap().parse_expr().unwrap());
+        execute_join(users, orders, kind, &constraint, Context::default())
+    }
+
+    fn joined(kind: JoinKind, constraint: &str) -> Vec<String> {
+        let result = join(kind, Some(constraint)).unwrap();
+        let amount = result.cols.len() - 1;
+        result
+            .entries
+            .iter()
+            .map(|row| format!("{} {}", row[0], row[amount]))
+            .collect()
+    }
+
+    /// The rows of `JSON_EACH` joined with documents 1 to 3, `from` is the
+    /// `FROM` clause of a query.
+    fn json_each(from: &str) -> Result<Vec<String>, ExecutorError> {
+        let query = format!("SELECT * FROM {}", from);
+        let join = match Parser::new(&query).unwrap().parse_statement().unwrap() {
+            Statement::Select(Query {
+                body: SetExpr::Select(select),
+                ..
+            }) => select.from.unwrap().joins[0].clone(),
+            _ => unreachable!(),
+        };
+        let docs = ResultSet {
+            cols: [("id", DataType::INT), ("doc", DataType::JSON)]
+                .into_iter()
+                .map(|(name, dtype)| Column {
+                    table: Some("f".into()),
+                    name: name.into(),
+                    dtype: Some(dtype),
+                })
+                .collect(),
+            entries: [(1, r#"{"b": 2, "a": 1}"#), (2, "[]"), (3, "7")]
+                .into_iter()
+                .map(|(id, doc)| {
+                    let doc = DataType::JSON.parse_value(doc).unwrap();
+                    vec![Value::Int(id), doc]
+                })
+                .collect(),
+        };
+
+        let result = execute_lateral_join(docs, &join, Context::default())?;
+        Ok(result
+            .entries
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(Value::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect())
+    }
+
+    #[test]
+    fn json_each_lateral_joins() {
+        assert_eq!(
+            json_each("f, JSON_EACH(f.doc) AS e").unwrap(),
+            vec![
+                "1 {\"a\":1,\"b\":2} a 1",
+                "1 {\"a\":1,\"b\":2} b 2",
+                "3 7 NULL 7"
+            ]
+        );
+        assert_eq!(
+            json_each("f LEFT JOIN json_each(doc) ON json_each.value > 1").unwrap(),
+            vec!["1 {\"a\":1,\"b\":2} b 2", "2 [] NULL NULL", "3 7 NULL 7"]
+        );
+        assert!(matches!(
+            json_each("f FULL JOIN JSON_EACH(doc) e ON true"),
+            Err(ExecutorError::EvalErr(EvalError::InvalidArgument(_)))
+        ));
+        assert!(matches!(
+            json_each("f, JSON_EACH(id) e"),
+            Err(ExecutorError::EvalErr(EvalError::InvalidArgument(_)))
+        ));
+        assert!(matches!(
+            json_each("f, JSON_KEYS(doc) e"),
+            Err(ExecutorError::EvalErr(EvalError::UnknownFunction(_)))
+        ));
+    }
+
+    #[test]
+    fn inner_and_outer_joins() {
+        let on = "u.first_name = o.first_name";
+        assert_eq!(joined(JoinKind::Inner, on), vec!["a 5", "a 7", "c 1"]);
+        assert_eq!(
+            joined(JoinKind::Left, on),
+            vec!["a 5", "a 7", "b NULL", "c 1"]
+        );
+        assert_eq!(
+            joined(JoinKind::Right, on),
+            vec!["a 5", "a 7", "c 1", "NULL 3"]
+        );
+        assert_eq!(
+            joined(JoinKind::Full, on),
+            vec!["a 5", "a 7", "b NULL", "c 1", "NULL 3"]
+        );
+    }
+
+    #[test]
+    fn hash_and_nested_loop_joins_agree() {
+        // The equality is matched with a hash table, the rest on each pair.
+        assert_eq!(
+            joined(JoinKind::Left, "o.first_name = u.first_name AND amount > 1"),
+            vec!["a 5", "a 7", "b NULL", "c NULL"]
+        );
+        assert_eq!(
+            joined(
+                JoinKind::Left,
+                "u.first_name >= o.first_name AND u.first_name <= o.first_name AND amount > 1"
+            ),
+            vec!["a 5", "a 7", "b NULL", "c NULL"]
+        );
+    }
+
+    #[test]
+    fn cross_join() {
+        let result = join(JoinKind::Cross, None).unwrap();
+        assert_eq!(result.cols.len(), cols().len() + 2);
+        assert_eq!(result.entries.len(), 12);
+    }
+
+    #[test]
+    fn reject_ambiguous_columns() {
+        assert!(matches!(
+            join(JoinKind::Inner, Some("first_name = 'a'")),
+            Err(ExecutorError::EvalErr(EvalError::AmbiguousCol(col))) if col == "first_name"
+        ));
+        assert!(matches!(
+            join(JoinKind::Inner, Some("x.first_name = 'a'")),
+            Err(ExecutorError::EvalErr(EvalError::ColNotFound(col))) if col == "x.first_name"
+        ));
+    }
+}
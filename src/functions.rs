@@ -0,0 +1,913 @@
+//! Scalar functions, computed from the values of a single row like
+//! `UPPER(name)`, unlike aggregates which run over a group of rows.
+//!
+//! Every function is described by a [`ScalarFunction`] in [`FUNCTIONS`], the
+//! arguments are checked against it before reading any row.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{DateTime, Utc};
+use rust_decimal::{prelude::ToPrimitive, Decimal, RoundingStrategy};
+
+use crate::{
+    ast::BinaryOperator,
+    datetime,
+    eval::{self, EvalError, EvalResult},
+    json,
+    types::DataType,
+    value::Value,
+};
+
+/// The values an argument accepts, checked when the type of the argument is
+/// known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Arg {
+    Any,
+    Text,
+    /// Text or a blob, text is read as its UTF-8 bytes.
+    Bytes,
+    /// A JSON document, text is parsed as one.
+    Json,
+    Number,
+    Integer,
+    /// Dates, times, timestamps and intervals.
+    Temporal,
+    /// Dates and timestamps, with or without a time zone.
+    Timestamp,
+}
+
+impl Arg {
+    fn accepts(&self, dtype: &DataType) -> bool {
+        match self {
+            Arg::Any => true,
+            Arg::Text => dtype.is_text(),
+            Arg::Bytes => dtype.is_text() || *dtype == DataType::BLOB,
+            Arg::Json => dtype.is_text() || *dtype == DataType::JSON,
+            Arg::Number => dtype.is_numeric(),
+            Arg::Integer => dtype.is_integer(),
+            Arg::Temporal => dtype.is_temporal(),
+            Arg::Timestamp => matches!(
+                dtype,
+                DataType::DATE | DataType::TIMESTAMP | DataType::TIMESTAMPTZ
+            ),
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Arg::Any => "a value",
+            Arg::Text => "text",
+            Arg::Bytes => "text or a blob",
+            Arg::Json => "JSON",
+            Arg::Number => "a number",
+            Arg::Integer => "an integer",
+            Arg::Temporal => "a date, time or interval",
+            Arg::Timestamp => "a date or timestamp",
+        }
+    }
+}
+
+/// The type of the value a function returns.
+#[derive(Debug)]
+enum Returns {
+    Type(DataType),
+    /// The type of the argument at the given position, like `ABS`.
+    Arg(usize),
+    /// The type every argument can be converted to, like `COALESCE`.
+    Common,
+}
+
+#[derive(Debug)]
+pub struct ScalarFunction {
+    pub name: &'static str,
+    /// The arguments in order, the last one repeats when `variadic` is set.
+    args: &'static [Arg],
+    /// Number of trailing arguments that can be left out.
+    optional: usize,
+    variadic: bool,
+    returns: Returns,
+    /// Strict functions return null as soon as an argument is null, without
+    /// being called.
+    strict: bool,
+    call: fn(&Call, &[Value]) -> EvalResult<Value>,
+}
+
+/// What a function knows about the call beyond its arguments.
+struct Call<'a> {
+    name: &'a str,
+    /// The time the statement started, every row sees the same `NOW()`.
+    now: DateTime<Utc>,
+}
+
+pub static FUNCTIONS: &[ScalarFunction] = &[
+    ScalarFunction {
+        name: "UPPER",
+        args: &[Arg::Text],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::TEXT),
+        strict: true,
+        call: |_, args| Ok(Value::Text(text(&args[0]).to_uppercase())),
+    },
+    ScalarFunction {
+        name: "LOWER",
+        args: &[Arg::Text],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::TEXT),
+        strict: true,
+        call: |_, args| Ok(Value::Text(text(&args[0]).to_lowercase())),
+    },
+    // The length of a blob is in bytes, text is in chars.
+    ScalarFunction {
+        name: "LENGTH",
+        args: &[Arg::Bytes],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::INT),
+        strict: true,
+        call: |_, args| match &args[0] {
+            Value::Blob(bytes) => Ok(Value::Int(bytes.len() as i64)),
+            value => Ok(Value::Int(text(value).chars().count() as i64)),
+        },
+    },
+    ScalarFunction {
+        name: "SUBSTR",
+        args: &[Arg::Text, Arg::Integer, Arg::Integer],
+        optional: 1,
+        variadic: false,
+        returns: Returns::Type(DataType::TEXT),
+        strict: true,
+        call: substr,
+    },
+    ScalarFunction {
+        name: "TRIM",
+        args: &[Arg::Text, Arg::Text],
+        optional: 1,
+        variadic: false,
+        returns: Returns::Type(DataType::TEXT),
+        strict: true,
+        call: |_, args| {
+            let value = text(&args[0]);
+            let trimmed = match args.get(1) {
+                Some(chars) => {
+                    let chars = text(chars).chars().collect::<Vec<_>>();
+                    value.trim_matches(chars.as_slice())
+                }
+                None => value.trim(),
+            };
+            Ok(Value::Text(trimmed.to_string()))
+        },
+    },
+    ScalarFunction {
+        name: "REPLACE",
+        args: &[Arg::Text, Arg::Text, Arg::Text],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::TEXT),
+        strict: true,
+        call: |_, args| {
+            let (value, from, to) = (text(&args[0]), text(&args[1]), text(&args[2]));
+            // Replacing an empty string would insert `to` between every char.
+            Ok(Value::Text(match from.is_empty() {
+                true => value,
+                false => value.replace(&from, &to),
+            }))
+        },
+    },
+    ScalarFunction {
+        name: "CONCAT",
+        args: &[Arg::Any],
+        optional: 0,
+        variadic: true,
+        returns: Returns::Type(DataType::TEXT),
+        // Unlike `||`, nulls are skipped.
+        strict: false,
+        call: |_, args| {
+            let values = args.iter().filter(|value| !value.is_null());
+            Ok(Value::Text(values.map(text).collect()))
+        },
+    },
+    ScalarFunction {
+        name: "ABS",
+        args: &[Arg::Number],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Arg(0),
+        strict: true,
+        call: |call, args| match &args[0] {
+            Value::Int(v) => v
+                .checked_abs()
+                .map(Value::Int)
+                .ok_or_else(|| EvalError::Overflow(format!("{}({})", call.name, v))),
+            Value::Decimal(v) => Ok(Value::Decimal(v.abs())),
+            value => Ok(Value::Float(number(call.name, value)?.abs())),
+        },
+    },
+    ScalarFunction {
+        name: "ROUND",
+        args: &[Arg::Number, Arg::Integer],
+        optional: 1,
+        variadic: false,
+        returns: Returns::Arg(0),
+        strict: true,
+        call: round,
+    },
+    ScalarFunction {
+        name: "FLOOR",
+        args: &[Arg::Number],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Arg(0),
+        strict: true,
+        call: |call, args| match &args[0] {
+            Value::Int(v) => Ok(Value::Int(*v)),
+            Value::Decimal(v) => Ok(Value::Decimal(v.floor())),
+            value => Ok(Value::Float(number(call.name, value)?.floor())),
+        },
+    },
+    ScalarFunction {
+        name: "CEIL",
+        args: &[Arg::Number],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Arg(0),
+        strict: true,
+        call: |call, args| match &args[0] {
+            Value::Int(v) => Ok(Value::Int(*v)),
+            Value::Decimal(v) => Ok(Value::Decimal(v.ceil())),
+            value => Ok(Value::Float(number(call.name, value)?.ceil())),
+        },
+    },
+    ScalarFunction {
+        name: "MOD",
+        args: &[Arg::Number, Arg::Number],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Common,
+        strict: true,
+        call: |_, args| eval::arithmetic(BinaryOperator::Modulo, &args[0], &args[1]),
+    },
+    ScalarFunction {
+        name: "POWER",
+        args: &[Arg::Number, Arg::Number],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::FLOAT),
+        strict: true,
+        call: |call, args| {
+            let (base, exponent) = (number(call.name, &args[0])?, number(call.name, &args[1])?);
+            let value = base.powf(exponent);
+            match value.is_finite() {
+                true => Ok(Value::Float(value)),
+                false => Err(EvalError::InvalidArgument(format!(
+                    "{}({}, {}) is not a finite number",
+                    call.name, base, exponent
+                ))),
+            }
+        },
+    },
+    ScalarFunction {
+        name: "COALESCE",
+        args: &[Arg::Any],
+        optional: 0,
+        variadic: true,
+        returns: Returns::Common,
+        strict: false,
+        call: |_, args| Ok(first_not_null(args)),
+    },
+    ScalarFunction {
+        name: "IFNULL",
+        args: &[Arg::Any, Arg::Any],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Common,
+        strict: false,
+        call: |_, args| Ok(first_not_null(args)),
+    },
+    ScalarFunction {
+        name: "NULLIF",
+        args: &[Arg::Any, Arg::Any],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Arg(0),
+        strict: false,
+        call: |_, args| {
+            let (a, b) = (&args[0], &args[1]);
+            let dtype = eval::comparison_type(&(a.clone(), None), &(b.clone(), None));
+            match dtype.compare(a, b)? {
+                Some(ordering) if ordering.is_eq() => Ok(Value::Null),
+                _ => Ok(a.clone()),
+            }
+        },
+    },
+    ScalarFunction {
+        name: "HEX",
+        args: &[Arg::Bytes],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::TEXT),
+        strict: true,
+        call: |_, args| Ok(Value::Text(hex::encode_upper(bytes(&args[0])))),
+    },
+    ScalarFunction {
+        name: "UNHEX",
+        args: &[Arg::Text],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::BLOB),
+        strict: true,
+        call: |call, args| decode(call.name, &text(&args[0]), "hex"),
+    },
+    ScalarFunction {
+        name: "ENCODE",
+        args: &[Arg::Bytes, Arg::Text],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::TEXT),
+        strict: true,
+        call: |call, args| {
+            let bytes = bytes(&args[0]);
+            match text(&args[1]).to_lowercase().as_str() {
+                "hex" => Ok(Value::Text(hex::encode(bytes))),
+                "base64" => Ok(Value::Text(BASE64.encode(bytes))),
+                format => Err(unknown_format(call.name, format)),
+            }
+        },
+    },
+    ScalarFunction {
+        name: "DECODE",
+        args: &[Arg::Text, Arg::Text],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::BLOB),
+        strict: true,
+        call: |call, args| decode(call.name, &text(&args[0]), &text(&args[1]).to_lowercase()),
+    },
+    // Paths start at the root like `$.tags[0]`, null if nothing is there.
+    ScalarFunction {
+        name: "JSON_EXTRACT",
+        args: &[Arg::Json, Arg::Text],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::JSON),
+        strict: true,
+        call: |_, args| {
+            let value = match json::document(&args[0])? {
+                Some(document) => json::extract(&document, &text(&args[1]))?,
+                None => None,
+            };
+            Ok(value.map_or(Value::Null, Value::Json))
+        },
+    },
+    // Null unless the value at the path is an array.
+    ScalarFunction {
+        name: "JSON_ARRAY_LENGTH",
+        args: &[Arg::Json, Arg::Text],
+        optional: 1,
+        variadic: false,
+        returns: Returns::Type(DataType::INT),
+        strict: true,
+        call: |_, args| {
+            let value = match (json::document(&args[0])?, args.get(1)) {
+                (Some(document), Some(path)) => json::extract(&document, &text(path))?,
+                (document, _) => document,
+            };
+            let length = value.as_ref().and_then(json::array_length);
+            Ok(length.map_or(Value::Null, Value::Int))
+        },
+    },
+    // A version 4 UUID, called again for every row.
+    ScalarFunction {
+        name: "GEN_RANDOM_UUID",
+        args: &[],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::UUID),
+        strict: true,
+        call: |_, _| Ok(Value::Uuid(uuid::Uuid::new_v4())),
+    },
+    // Timestamps with a time zone are in UTC, so is the current date. The
+    // clock is read once per statement, see `Context::now`.
+    ScalarFunction {
+        name: "NOW",
+        args: &[],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::TIMESTAMPTZ),
+        strict: true,
+        call: |call, _| Ok(Value::TimestampTz(call.now)),
+    },
+    ScalarFunction {
+        name: "CURRENT_DATE",
+        args: &[],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::DATE),
+        strict: true,
+        call: |call, _| Ok(Value::Date(call.now.date_naive())),
+    },
+    ScalarFunction {
+        name: "CURRENT_TIME",
+        args: &[],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::TIME),
+        strict: true,
+        call: |call, _| Ok(Value::Time(call.now.time())),
+    },
+    ScalarFunction {
+        name: "EXTRACT",
+        args: &[Arg::Text, Arg::Temporal],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::FLOAT),
+        strict: true,
+        call: |_, args| datetime::extract(&text(&args[0]), &args[1]),
+    },
+    ScalarFunction {
+        name: "DATE_TRUNC",
+        args: &[Arg::Text, Arg::Timestamp],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Arg(1),
+        strict: true,
+        call: |_, args| datetime::trunc(&text(&args[0]), &args[1]),
+    },
+    ScalarFunction {
+        name: "TO_CHAR",
+        args: &[Arg::Temporal, Arg::Text],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::TEXT),
+        strict: true,
+        call: |_, args| datetime::format(&args[0], &text(&args[1])),
+    },
+    ScalarFunction {
+        name: "TO_DATE",
+        args: &[Arg::Text, Arg::Text],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::DATE),
+        strict: true,
+        call: |_, args| datetime::parse_with(&text(&args[0]), &text(&args[1]), true),
+    },
+    ScalarFunction {
+        name: "TO_TIMESTAMP",
+        args: &[Arg::Text, Arg::Text],
+        optional: 0,
+        variadic: false,
+        returns: Returns::Type(DataType::TIMESTAMPTZ),
+        strict: true,
+        call: |_, args| datetime::parse_with(&text(&args[0]), &text(&args[1]), false),
+    },
+];
+
+/// Find a function by name, names are case insensitive.
+pub fn lookup(name: &str) -> Option<&'static ScalarFunction> {
+    let name = match name.to_uppercase().as_str() {
+        "SUBSTRING" => "SUBSTR",
+        "CEILING" => "CEIL",
+        "POW" => "POWER",
+        "CURRENT_TIMESTAMP" => "NOW",
+        "DATE_PART" => "EXTRACT",
+        name => return FUNCTIONS.iter().find(|f| f.name == name),
+    };
+
+    FUNCTIONS.iter().find(|f| f.name == name)
+}
+
+impl ScalarFunction {
+    /// Kind of the argument at `pos`.
+    fn arg(&self, pos: usize) -> Arg {
+        self.args[pos.min(self.args.len() - 1)]
+    }
+
+    /// Check the number of arguments and the type of those that are known.
+    pub fn check(&self, types: &[Option<DataType>]) -> EvalResult<()> {
+        let min = self.args.len() - self.optional;
+        let max = if self.variadic {
+            usize::MAX
+        } else {
+            self.args.len()
+        };
+        if types.len() < min || types.len() > max {
+            let expected = match (min, max) {
+                (min, usize::MAX) => format!("at least {} arguments", min),
+                (min, max) if min == max => format!("{} arguments", min),
+                (min, max) => format!("between {} and {} arguments", min, max),
+            };
+            return Err(EvalError::InvalidArgument(format!(
+                "{} expects {}, found {}",
+                self.name,
+                expected,
+                types.len()
+            )));
+        }
+
+        for (pos, dtype) in types.iter().enumerate() {
+            let arg = self.arg(pos);
+            if let Some(dtype) = dtype.as_ref().filter(|dtype| !arg.accepts(dtype)) {
+                return Err(EvalError::InvalidArgument(format!(
+                    "{} expects {} as argument {}, found {:?}",
+                    self.name,
+                    arg.describe(),
+                    pos + 1,
+                    dtype
+                )));
+            }
+        }
+
+        self.return_type(types).map(|_| ())
+    }
+
+    /// The type of the result, if it's known from the types of the arguments.
+    pub fn return_type(&self, types: &[Option<DataType>]) -> EvalResult<Option<DataType>> {
+        match &self.returns {
+            Returns::Type(dtype) => Ok(Some(dtype.clone())),
+            Returns::Arg(pos) => Ok(types.get(*pos).cloned().flatten()),
+            Returns::Common => {
+                let mut common: Option<DataType> = None;
+                for dtype in types.iter().flatten() {
+                    common = match common {
+                        Some(common) => match common.common_type(dtype) {
+                            Some(common) => Some(common),
+                            None => {
+                                return Err(EvalError::InvalidArgument(format!(
+                                    "{} types {:?} and {:?} cannot be matched",
+                                    self.name, common, dtype
+                                )))
+                            }
+                        },
+                        None => Some(dtype.clone()),
+                    };
+                }
+                Ok(common)
+            }
+        }
+    }
+
+    /// Call the function in a statement that started at `now`.
+    pub fn call(&self, args: &[Value], now: DateTime<Utc>) -> EvalResult<Value> {
+        if self.strict && args.iter().any(Value::is_null) {
+            return Ok(Value::Null);
+        }
+
+        (self.call)(
+            &Call {
+                name: self.name,
+                now,
+            },
+            args,
+        )
+    }
+}
+
+/// Text arguments of an unknown type are converted like `||` does.
+fn text(value: &Value) -> String {
+    match value {
+        Value::Text(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn bytes(value: &Value) -> Vec<u8> {
+    match value {
+        Value::Blob(bytes) => bytes.clone(),
+        value => text(value).into_bytes(),
+    }
+}
+
+/// Read a blob written as `hex` or `base64`, whitespace is ignored.
+fn decode(name: &str, value: &str, format: &str) -> EvalResult<Value> {
+    let value = value.split_whitespace().collect::<String>();
+    let bytes = match format {
+        "hex" => hex::decode(&value).ok(),
+        "base64" => BASE64.decode(&value).ok(),
+        format => return Err(unknown_format(name, format)),
+    };
+
+    bytes.map(Value::Blob).ok_or_else(|| {
+        EvalError::InvalidArgument(format!("{}: '{}' is not valid {}", name, value, format))
+    })
+}
+
+fn unknown_format(name: &str, format: &str) -> EvalError {
+    EvalError::InvalidArgument(format!(
+        "{} expects 'hex' or 'base64' as format, found '{}'",
+        name, format
+    ))
+}
+
+fn number(name: &str, value: &Value) -> EvalResult<f64> {
+    match value {
+        Value::Int(v) => Ok(*v as f64),
+        Value::Float(v) => Ok(*v),
+        Value::Decimal(v) => v.to_f64().ok_or_else(|| {
+            EvalError::InvalidArgument(format!("{} expects a number, found {}", name, v))
+        }),
+        value => Err(EvalError::InvalidArgument(format!(
+            "{} expects a number, found {}",
+            name, value
+        ))),
+    }
+}
+
+fn integer(name: &str, value: &Value) -> EvalResult<i64> {
+    match value {
+        Value::Int(v) => Ok(*v),
+        value => Err(EvalError::InvalidArgument(format!(
+            "{} expects an integer, found {}",
+            name, value
+        ))),
+    }
+}
+
+fn first_not_null(args: &[Value]) -> Value {
+    let value = args.iter().find(|value| !value.is_null());
+    value.cloned().unwrap_or(Value::Null)
+}
+
+/// `SUBSTR(text, start [, count])`, positions start at 1 and positions before
+/// the first char count towards `count` like in PostgreSQL.
+fn substr(Call { name, .. }: &Call, args: &[Value]) -> EvalResult<Value> {
+    let value = text(&args[0]);
+    let start = integer(name, &args[1])?;
+    let end = match args.get(2) {
+        Some(count) => match integer(name, count)? {
+            count if count < 0 => {
+                return Err(EvalError::InvalidArgument(format!(
+                    "{} expects a non negative length, found {}",
+                    name, count
+                )))
+            }
+            count => start.saturating_add(count),
+        },
+        None => i64::MAX,
+    };
+
+    let skip = start.max(1) - 1;
+    let take = end.saturating_sub(start.max(1)).max(0);
+    let chars = value.chars().skip(skip as usize).take(take as usize);
+    Ok(Value::Text(chars.collect()))
+}
+
+/// `ROUND(number [, digits])`, halfway values are rounded away from zero and
+/// negative digits round to tens, hundreds and so on.
+fn round(Call { name, .. }: &Call, args: &[Value]) -> EvalResult<Value> {
+    let digits = match args.get(1) {
+        Some(digits) => integer(name, digits)?,
+        None => 0,
+    };
+    let scale = 10f64.powi(digits.clamp(-308, 308) as i32);
+
+    match &args[0] {
+        Value::Int(v) if digits >= 0 => Ok(Value::Int(*v)),
+        Value::Int(v) => {
+            let rounded = (*v as f64 * scale).round() / scale;
+            match rounded >= i64::MIN as f64 && rounded < i64::MAX as f64 {
+                true => Ok(Value::Int(rounded as i64)),
+                false => Err(EvalError::Overflow(format!("{}({}, {})", name, v, digits))),
+            }
+        }
+        Value::Decimal(v) if digits >= 0 => Ok(Value::Decimal(v.round_dp_with_strategy(
+            digits.min(Decimal::MAX_SCALE as i64) as u32,
+            RoundingStrategy::MidpointAwayFromZero,
+        ))),
+        Value::Decimal(v) => {
+            let scale = Decimal::from_i128_with_scale(
+                10i128.pow(digits.unsigned_abs().min(Decimal::MAX_SCALE as u64) as u32),
+                0,
+            );
+            let rounded = (v / scale)
+                .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+                .checked_mul(scale);
+            rounded
+                .map(Value::Decimal)
+                .ok_or_else(|| EvalError::Overflow(format!("{}({}, {})", name, v, digits)))
+        }
+        value => {
+            let v = number(name, value)?;
+            let rounded = (v * scale).round() / scale;
+            // Scaling very large numbers overflows, they have no decimals.
+            Ok(Value::Float(if rounded.is_finite() { rounded } else { v }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::lookup;
+    use crate::{eval::EvalError, types::DataType, value::Value};
+
+    fn call(name: &str, args: &[Value]) -> Value {
+        lookup(name).unwrap().call(args, Utc::now()).unwrap()
+    }
+
+    fn text(value: &str) -> Value {
+        Value::Text(value.into())
+    }
+
+    #[test]
+    fn string_functions() {
+        assert_eq!(call("upper", &[text("Jone")]), text("JONE"));
+        assert_eq!(call("LOWER", &[text("Jone")]), text("jone"));
+        assert_eq!(call("LENGTH", &[text("héllo")]), Value::Int(5));
+        assert_eq!(call("TRIM", &[text("  a b ")]), text("a b"));
+        assert_eq!(call("TRIM", &[text("xxaxx"), text("x")]), text("a"));
+        assert_eq!(
+            call("REPLACE", &[text("a-b-c"), text("-"), text("+")]),
+            text("a+b+c")
+        );
+        assert_eq!(
+            call("CONCAT", &[text("a"), Value::Null, Value::Int(1)]),
+            text("a1")
+        );
+        assert_eq!(call("UPPER", &[Value::Null]), Value::Null);
+    }
+
+    #[test]
+    fn substrings() {
+        let substr = |args: &[i64]| {
+            let mut values = vec![text("hello")];
+            values.extend(args.iter().map(|v| Value::Int(*v)));
+            call("SUBSTR", &values)
+        };
+
+        assert_eq!(substr(&[2]), text("ello"));
+        assert_eq!(substr(&[2, 3]), text("ell"));
+        assert_eq!(substr(&[0, 2]), text("h"));
+        assert_eq!(substr(&[-5, 3]), text(""));
+        assert_eq!(substr(&[4, 10]), text("lo"));
+        assert_eq!(substr(&[9]), text(""));
+        assert!(matches!(
+            lookup("SUBSTRING")
+                .unwrap()
+                .call(&[text("a"), Value::Int(1), Value::Int(-1)], Utc::now()),
+            Err(EvalError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn math_functions() {
+        assert_eq!(call("ABS", &[Value::Int(-3)]), Value::Int(3));
+        assert_eq!(call("ABS", &[Value::Float(-1.5)]), Value::Float(1.5));
+        assert_eq!(call("ROUND", &[Value::Float(2.5)]), Value::Float(3.0));
+        assert_eq!(
+            call("ROUND", &[Value::Float(1.2345), Value::Int(2)]),
+            Value::Float(1.23)
+        );
+        assert_eq!(
+            call("ROUND", &[Value::Int(1250), Value::Int(-2)]),
+            Value::Int(1300)
+        );
+        assert_eq!(call("FLOOR", &[Value::Float(-1.5)]), Value::Float(-2.0));
+        assert_eq!(call("CEILING", &[Value::Float(1.2)]), Value::Float(2.0));
+        assert_eq!(call("MOD", &[Value::Int(7), Value::Int(3)]), Value::Int(1));
+        assert_eq!(
+            call("POWER", &[Value::Int(2), Value::Int(10)]),
+            Value::Float(1024.0)
+        );
+        assert!(matches!(
+            lookup("ABS")
+                .unwrap()
+                .call(&[Value::Int(i64::MIN)], Utc::now()),
+            Err(EvalError::Overflow(_))
+        ));
+        assert!(matches!(
+            lookup("MOD")
+                .unwrap()
+                .call(&[Value::Int(1), Value::Int(0)], Utc::now()),
+            Err(EvalError::DivisionByZero(_))
+        ));
+    }
+
+    #[test]
+    fn blob_functions() {
+        let blob = |bytes: &[u8]| Value::Blob(bytes.to_vec());
+
+        assert_eq!(call("LENGTH", &[blob(&[0xff, 0, 1])]), Value::Int(3));
+        assert_eq!(call("HEX", &[blob(&[0x0a, 0x1b])]), text("0A1B"));
+        assert_eq!(call("HEX", &[text("é")]), text("C3A9"));
+        assert_eq!(call("UNHEX", &[text("0a1B")]), blob(&[0x0a, 0x1b]));
+        assert_eq!(
+            call("ENCODE", &[blob(&[0xff, 0xfe, 0]), text("base64")]),
+            text("//4A")
+        );
+        assert_eq!(call("ENCODE", &[text("hi"), text("HEX")]), text("6869"));
+        assert_eq!(
+            call("DECODE", &[text("//4A\n"), text("base64")]),
+            blob(&[0xff, 0xfe, 0])
+        );
+        assert!(matches!(
+            lookup("UNHEX").unwrap().call(&[text("0G")], Utc::now()),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            lookup("DECODE")
+                .unwrap()
+                .call(&[text("00"), text("base32")], Utc::now()),
+            Err(EvalError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn json_functions() {
+        let doc = Value::Json(serde_json::json!({"tags": ["a", "b"], "n": 1}));
+
+        assert_eq!(
+            call("JSON_EXTRACT", &[doc.clone(), text("$.tags[1]")]),
+            Value::Json("b".into())
+        );
+        assert_eq!(
+            call(
+                "JSON_EXTRACT",
+                &[text(r#"{"a": {"b": null}}"#), text("$.a.b")]
+            ),
+            Value::Json(serde_json::Value::Null)
+        );
+        assert_eq!(
+            call("JSON_EXTRACT", &[doc.clone(), text("$.missing")]),
+            Value::Null
+        );
+        assert_eq!(
+            call("JSON_ARRAY_LENGTH", &[doc.clone(), text("$.tags")]),
+            Value::Int(2)
+        );
+        assert_eq!(
+            call("JSON_ARRAY_LENGTH", &[text("[1, 2, 3]")]),
+            Value::Int(3)
+        );
+        assert_eq!(call("JSON_ARRAY_LENGTH", &[text("{}")]), Value::Null);
+        assert!(matches!(
+            lookup("JSON_EXTRACT")
+                .unwrap()
+                .call(&[doc, text("tags")], Utc::now()),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            lookup("JSON_ARRAY_LENGTH")
+                .unwrap()
+                .call(&[text("{")], Utc::now()),
+            Err(EvalError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn random_uuids() {
+        let (a, b) = (call("GEN_RANDOM_UUID", &[]), call("GEN_RANDOM_UUID", &[]));
+
+        assert!(matches!(&a, Value::Uuid(id) if id.get_version_num() == 4));
+        assert_ne!(a, b);
+        assert!(matches!(
+            lookup("GEN_RANDOM_UUID").unwrap().check(&[None]),
+            Err(EvalError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn conditional_functions() {
+        assert_eq!(
+            call("COALESCE", &[Value::Null, Value::Int(2), Value::Int(3)]),
+            Value::Int(2)
+        );
+        assert_eq!(call("COALESCE", &[Value::Null]), Value::Null);
+        assert_eq!(call("IFNULL", &[Value::Null, text("x")]), text("x"));
+        assert_eq!(call("NULLIF", &[Value::Int(1), Value::Int(1)]), Value::Null);
+        assert_eq!(
+            call("NULLIF", &[Value::Int(1), Value::Float(2.0)]),
+            Value::Int(1)
+        );
+    }
+
+    #[test]
+    fn check_arguments() {
+        let check = |name: &str, types: &[Option<DataType>]| lookup(name).unwrap().check(types);
+
+        assert!(check("SUBSTR", &[Some(DataType::TEXT), Some(DataType::INT)]).is_ok());
+        assert!(check("UPPER", &[None]).is_ok());
+        assert!(check("COALESCE", &[Some(DataType::INT), Some(DataType::FLOAT)]).is_ok());
+        assert!(matches!(
+            check("UPPER", &[Some(DataType::INT)]),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(check("LENGTH", &[Some(DataType::BLOB)]).is_ok());
+        assert!(matches!(
+            check("UNHEX", &[Some(DataType::BLOB)]),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            check("SUBSTR", &[Some(DataType::TEXT)]),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            check("COALESCE", &[]),
+            Err(EvalError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            check("IFNULL", &[Some(DataType::INT), Some(DataType::TEXT)]),
+            Err(EvalError::InvalidArgument(_))
+        ));
+    }
+}
@@ -0,0 +1,253 @@
+//! JSON documents stored in `JSON` columns, see [`Value::Json`], and the
+//! operators and paths reading into them.
+//!
+//! Text is read as a document too, so `'{"a": 1}'->'a'` works without a cast.
+
+use std::cmp::Ordering;
+
+use serde_json::Value as JsonValue;
+
+use crate::{
+    eval::{EvalError, EvalResult},
+    value::Value,
+};
+
+/// A step of a path, a member of an object or an element of an array.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+    Key(String),
+    Index(i64),
+}
+
+/// The document a value holds, `None` for null.
+pub fn document(value: &Value) -> EvalResult<Option<JsonValue>> {
+    match value {
+        Value::Null => Ok(None),
+        Value::Json(json) => Ok(Some(json.clone())),
+        Value::Text(text) => serde_json::from_str(text)
+            .map(Some)
+            .map_err(|_| EvalError::InvalidArgument(format!("'{}' is not valid JSON", text))),
+        value => Err(EvalError::InvalidArgument(format!(
+            "expected JSON, found {}",
+            value
+        ))),
+    }
+}
+
+/// `json -> key`, a member of an object when the key is text or an element
+/// of an array when it's an integer, negative ones count from the end.
+pub fn get(json: &JsonValue, key: &Value) -> EvalResult<Option<JsonValue>> {
+    let step = match key {
+        Value::Text(key) => Step::Key(key.clone()),
+        Value::Int(index) => Step::Index(*index),
+        key => {
+            return Err(EvalError::InvalidArgument(format!(
+                "JSON keys must be text or integers, found {}",
+                key
+            )))
+        }
+    };
+
+    Ok(step.apply(json).cloned())
+}
+
+/// The text of a JSON value as returned by `->>`, strings lose their quotes
+/// and `null` is a null value.
+pub fn text(json: &JsonValue) -> Value {
+    match json {
+        JsonValue::Null => Value::Null,
+        JsonValue::String(text) => Value::Text(text.clone()),
+        json => Value::Text(json.to_string()),
+    }
+}
+
+/// Read into a document following a path like `$.tags[0]`, `None` if
+/// nothing is found at the end of the path.
+pub fn extract(json: &JsonValue, path: &str) -> EvalResult<Option<JsonValue>> {
+    let steps = parse_path(path).ok_or_else(|| {
+        EvalError::InvalidArgument(format!("'{}' is not a valid JSON path", path))
+    })?;
+
+    let mut json = json;
+    for step in &steps {
+        match step.apply(json) {
+            Some(value) => json = value,
+            None => return Ok(None),
+        }
+    }
+
+    Ok(Some(json.clone()))
+}
+
+/// The number of elements of an array, `None` for any other value.
+pub fn array_length(json: &JsonValue) -> Option<i64> {
+    json.as_array().map(|array| array.len() as i64)
+}
+
+/// The rows of `JSON_EACH`, a key and a value for each member of an object
+/// or element of an array, keyed by position. Any other value is a single
+/// row without a key.
+pub fn each(json: JsonValue) -> Vec<(Option<String>, JsonValue)> {
+    match json {
+        JsonValue::Object(members) => members
+            .into_iter()
+            .map(|(key, value)| (Some(key), value))
+            .collect(),
+        JsonValue::Array(elements) => elements
+            .into_iter()
+            .enumerate()
+            .map(|(pos, value)| (Some(pos.to_string()), value))
+            .collect(),
+        json => vec![(None, json)],
+    }
+}
+
+/// Order of two JSON values. Numbers and strings of the same kind compare
+/// by value, other values by kind first: null, booleans, numbers, strings,
+/// arrays then objects. Ties are broken by the text so only equal values
+/// are equal.
+pub fn compare(a: &JsonValue, b: &JsonValue) -> Ordering {
+    let rank = |json: &JsonValue| match json {
+        JsonValue::Null => 0,
+        JsonValue::Bool(_) => 1,
+        JsonValue::Number(_) => 2,
+        JsonValue::String(_) => 3,
+        JsonValue::Array(_) => 4,
+        JsonValue::Object(_) => 5,
+    };
+
+    let ordering = match (a, b) {
+        (JsonValue::Bool(a), JsonValue::Bool(b)) => a.cmp(b),
+        (JsonValue::Number(a), JsonValue::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+            a.total_cmp(&b)
+        }
+        (JsonValue::String(a), JsonValue::String(b)) => a.cmp(b),
+        (a, b) => rank(a).cmp(&rank(b)),
+    };
+
+    ordering.then_with(|| a.to_string().cmp(&b.to_string()))
+}
+
+impl Step {
+    fn apply<'j>(&self, json: &'j JsonValue) -> Option<&'j JsonValue> {
+        match (self, json) {
+            (Step::Key(key), JsonValue::Object(members)) => members.get(key),
+            (Step::Index(index), JsonValue::Array(elements)) => {
+                let index = match *index < 0 {
+                    true => elements.len().checked_sub(index.unsigned_abs() as usize)?,
+                    false => *index as usize,
+                };
+                elements.get(index)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parse a path like `$.name`, `$."first name"` or `$.tags[0]`, it always
+/// starts at the root, `$`.
+fn parse_path(path: &str) -> Option<Vec<Step>> {
+    let mut rest = path.trim().strip_prefix('$')?;
+    let mut steps = vec![];
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("[") {
+            let end = after.find(']')?;
+            steps.push(Step::Index(after[..end].trim().parse().ok()?));
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix(".\"") {
+            let end = after.find('"')?;
+            steps.push(Step::Key(after[..end].to_string()));
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return None;
+            }
+            steps.push(Step::Key(after[..end].to_string()));
+            rest = &after[end..];
+        } else {
+            return None;
+        }
+    }
+
+    Some(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use serde_json::json;
+
+    use super::{compare, each, extract, get, parse_path, text, Step};
+    use crate::value::Value;
+
+    #[test]
+    fn parse_paths() {
+        assert_eq!(parse_path("$"), Some(vec![]));
+        assert_eq!(
+            parse_path(r#"$.tags[-1]."first name".a"#),
+            Some(vec![
+                Step::Key("tags".into()),
+                Step::Index(-1),
+                Step::Key("first name".into()),
+                Step::Key("a".into()),
+            ])
+        );
+        assert_eq!(parse_path("tags"), None);
+        assert_eq!(parse_path("$..a"), None);
+        assert_eq!(parse_path("$[x]"), None);
+    }
+
+    #[test]
+    fn read_into_documents() {
+        let doc = json!({"name": "Jone", "tags": ["a", "b"], "age": 30, "none": null});
+
+        assert_eq!(
+            get(&doc, &Value::Text("age".into())).unwrap(),
+            Some(json!(30))
+        );
+        assert_eq!(
+            get(&doc["tags"], &Value::Int(-1)).unwrap(),
+            Some(json!("b"))
+        );
+        assert_eq!(get(&doc["tags"], &Value::Int(2)).unwrap(), None);
+        assert_eq!(get(&doc, &Value::Int(0)).unwrap(), None);
+        assert!(get(&doc, &Value::Bool(true)).is_err());
+
+        assert_eq!(extract(&doc, "$.tags[1]").unwrap(), Some(json!("b")));
+        assert_eq!(extract(&doc, "$.missing.a").unwrap(), None);
+        assert!(extract(&doc, "name").is_err());
+
+        assert_eq!(text(&doc["name"]), Value::Text("Jone".into()));
+        assert_eq!(text(&doc["tags"]), Value::Text(r#"["a","b"]"#.into()));
+        assert_eq!(text(&doc["none"]), Value::Null);
+    }
+
+    #[test]
+    fn each_member() {
+        assert_eq!(
+            each(json!({"b": 2, "a": [1]})),
+            vec![
+                (Some("a".to_string()), json!([1])),
+                (Some("b".to_string()), json!(2)),
+            ]
+        );
+        assert_eq!(
+            each(json!(["x"])),
+            vec![(Some("0".to_string()), json!("x"))]
+        );
+        assert_eq!(each(json!(1)), vec![(None, json!(1))]);
+    }
+
+    #[test]
+    fn compare_values() {
+        assert_eq!(compare(&json!(9), &json!(10)), Ordering::Less);
+        assert_eq!(compare(&json!("b"), &json!("a")), Ordering::Greater);
+        assert_eq!(compare(&json!(1), &json!("1")), Ordering::Less);
+        assert_eq!(compare(&json!([1]), &json!([1])), Ordering::Equal);
+        assert_ne!(compare(&json!(1), &json!(1.0)), Ordering::Equal);
+    }
+}
@@ -0,0 +1,548 @@
+//! Splits a raw query into tokens consumed by the [`Parser`](crate::parser::Parser).
+//!
+//! Keywords are not special at this level, they are lexed as [`TokenKind::Word`]
+//! and matched case insensitively by the parser.
+
+use std::{fmt, iter::Peekable, str::CharIndices};
+
+use crate::query_parser::QueryParserError;
+
+/// Byte offsets of a token inside the raw query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// One based line and column of the start of the span.
+    pub fn line_col(&self, src: &str) -> (usize, usize) {
+        let before = &src[..self.start];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let col = before[line_start..].chars().count() + 1;
+
+        (line, col)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A bare word like `SELECT`, `user` or `true`.
+    Word(String),
+    /// An identifier wrapped in backticks like `` `order` ``, never treated as a keyword.
+    QuotedIdent(String),
+    /// A single or double quoted string without the quotes. Double quotes make strings
+    /// rather than identifiers, so identifiers are only quoted with backticks.
+    Str(String),
+    /// The digits of a hex string like `X'0A1B'`, the value of a blob.
+    HexStr(String),
+    /// The text of a base64 string like `B64'Chs='`, the value of a blob.
+    Base64Str(String),
+    Number(String),
+    Comma,
+    Semicolon,
+    Dot,
+    LParen,
+    RParen,
+    Star,
+    Plus,
+    Minus,
+    Slash,
+    Percent,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    /// `||`, string concatenation.
+    Concat,
+    /// `->`, a member of a JSON document.
+    Arrow,
+    /// `->>`, a member of a JSON document as text.
+    LongArrow,
+    Eof,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+impl Token {
+    /// Check if the token is the given keyword, ignoring the case.
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.kind, TokenKind::Word(w) if w.eq_ignore_ascii_case(keyword))
+    }
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Word(w) => write!(f, "`{}`", w),
+            TokenKind::QuotedIdent(i) => write!(f, "`{}`", i),
+            TokenKind::Str(s) => write!(f, "string '{}'", s),
+            TokenKind::HexStr(s) => write!(f, "hex string X'{}'", s),
+            TokenKind::Base64Str(s) => write!(f, "base64 string B64'{}'", s),
+            TokenKind::Number(n) => write!(f, "number {}", n),
+            TokenKind::Comma => write!(f, "`,`"),
+            TokenKind::Semicolon => write!(f, "`;`"),
+            TokenKind::Dot => write!(f, "`.`"),
+            TokenKind::LParen => write!(f, "`(`"),
+            TokenKind::RParen => write!(f, "`)`"),
+            TokenKind::Star => write!(f, "`*`"),
+            TokenKind::Plus => write!(f, "`+`"),
+            TokenKind::Minus => write!(f, "`-`"),
+            TokenKind::Slash => write!(f, "`/`"),
+            TokenKind::Percent => write!(f, "`%`"),
+            TokenKind::Eq => write!(f, "`=`"),
+            TokenKind::NotEq => write!(f, "`!=`"),
+            TokenKind::Lt => write!(f, "`<`"),
+            TokenKind::LtEq => write!(f, "`<=`"),
+            TokenKind::Gt => write!(f, "`>`"),
+            TokenKind::GtEq => write!(f, "`>=`"),
+            TokenKind::Concat => write!(f, "`||`"),
+            TokenKind::Arrow => write!(f, "`->`"),
+            TokenKind::LongArrow => write!(f, "`->>`"),
+            TokenKind::Eof => write!(f, "end of query"),
+        }
+    }
+}
+
+pub struct Lexer<'a> {
+    src: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            chars: src.char_indices().peekable(),
+        }
+    }
+
+    /// Tokenize the whole query, the last token is always [`TokenKind::Eof`].
+    pub fn tokenize(src: &'a str) -> Result<Vec<Token>, QueryParserError> {
+        let mut lexer = Lexer::new(src);
+        let mut tokens = Vec::new();
+
+        loop {
+            let token = lexer.next_token()?;
+            let is_eof = token.kind == TokenKind::Eof;
+            tokens.push(token);
+
+            if is_eof {
+                return Ok(tokens);
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Token, QueryParserError> {
+        self.skip_whitespace_and_comments()?;
+
+        let (start, ch) = match self.chars.peek() {
+            Some(&c) => c,
+            None => {
+                return Ok(Token {
+                    kind: TokenKind::Eof,
+                    span: Span {
+                        start: self.src.len(),
+                        end: self.src.len(),
+                    },
+                })
+            }
+        };
+
+        let kind = match ch {
+            'x' | 'X' if self.src[start + 1..].starts_with('\'') => {
+                self.chars.next();
+                TokenKind::HexStr(self.quoted('\'', false)?)
+            }
+            'b' | 'B' if self.src[start + 1..].starts_with("64'") => {
+                self.chars.nth(2);
+                TokenKind::Base64Str(self.quoted('\'', false)?)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                TokenKind::Word(self.take_while(|c| c.is_alphanumeric() || c == '_'))
+            }
+            c if c.is_ascii_digit() => TokenKind::Number(self.number()?),
+            '\'' | '"' => TokenKind::Str(self.quoted(ch, true)?),
+            '`' => TokenKind::QuotedIdent(self.quoted(ch, false)?),
+            '=' | '!' | '<' | '>' | '|' => self.operator()?,
+            '-' if self.src[start..].starts_with("->") => {
+                self.chars.next();
+                self.chars.next();
+                match self.chars.next_if(|&(_, c)| c == '>') {
+                    Some(_) => TokenKind::LongArrow,
+                    None => TokenKind::Arrow,
+                }
+            }
+            _ => {
+                self.chars.next();
+                match ch {
+                    ',' => TokenKind::Comma,
+                    ';' => TokenKind::Semicolon,
+                    '.' => TokenKind::Dot,
+                    '(' => TokenKind::LParen,
+                    ')' => TokenKind::RParen,
+                    '*' => TokenKind::Star,
+                    '+' => TokenKind::Plus,
+                    '-' => TokenKind::Minus,
+                    '/' => TokenKind::Slash,
+                    '%' => TokenKind::Percent,
+                    _ => {
+                        return Err(QueryParserError::UnexpectedChar {
+                            span: Span {
+                                start,
+                                end: self.offset(),
+                            },
+                            found: ch,
+                        })
+                    }
+                }
+            }
+        };
+
+        Ok(Token {
+            kind,
+            span: Span {
+                start,
+                end: self.offset(),
+            },
+        })
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), QueryParserError> {
+        loop {
+            self.take_while(char::is_whitespace);
+
+            let rest = &self.src[self.offset()..];
+            if rest.starts_with("--") {
+                self.take_while(|c| c != '\n');
+            } else if rest.starts_with("/*") {
+                match rest.find("*/") {
+                    Some(end) => {
+                        let end = self.offset() + end + 2;
+                        while self.offset() < end {
+                            self.chars.next();
+                        }
+                    }
+                    None => {
+                        return Err(QueryParserError::UnterminatedComment {
+                            span: Span {
+                                start: self.offset(),
+                                end: self.src.len(),
+                            },
+                        })
+                    }
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Digits with an optional fraction and exponent, like `1.5e-3`. A number
+    /// can't run into a word, `1abc` is an error rather than `1 AS abc`.
+    fn number(&mut self) -> Result<String, QueryParserError> {
+        let mut number = self.take_while(|c| c.is_ascii_digit());
+
+        let rest = &self.src[self.offset()..];
+        if rest.starts_with('.') && rest[1..].starts_with(|c: char| c.is_ascii_digit()) {
+            self.chars.next();
+            number.push('.');
+            number.push_str(&self.take_while(|c| c.is_ascii_digit()));
+        }
+
+        let rest = &self.src[self.offset()..];
+        if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+            let sign = exponent.starts_with(['+', '-']) as usize;
+            if exponent[sign..].starts_with(|c: char| c.is_ascii_digit()) {
+                for _ in 0..=sign {
+                    number.push(self.chars.next().unwrap().1);
+                }
+                number.push_str(&self.take_while(|c| c.is_ascii_digit()));
+            }
+        }
+
+        match self.chars.peek() {
+            Some(&(start, c)) if c.is_alphanumeric() || c == '_' => {
+                Err(QueryParserError::UnexpectedChar {
+                    span: Span {
+                        start,
+                        end: start + c.len_utf8(),
+                    },
+                    found: c,
+                })
+            }
+            _ => Ok(number),
+        }
+    }
+
+    /// The value between quotes, a doubled quote stands for the quote itself.
+    /// Strings also read backslash escapes, unknown ones are kept as written
+    /// so `'50\%'` stays a `LIKE` escape.
+    fn quoted(&mut self, quote: char, backslash: bool) -> Result<String, QueryParserError> {
+        let start = self.offset();
+        self.chars.next();
+
+        let mut value = String::new();
+        while let Some((_, c)) = self.chars.next() {
+            match c {
+                c if c == quote => match self.chars.peek() {
+                    Some(&(_, next)) if next == quote => {
+                        self.chars.next();
+                        value.push(quote);
+                    }
+                    _ => return Ok(value),
+                },
+                '\\' if backslash => match self.chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, 't')) => value.push('\t'),
+                    Some((_, 'r')) => value.push('\r'),
+                    Some((_, '0')) => value.push('\0'),
+                    Some((_, c @ ('\\' | '\'' | '"'))) => value.push(c),
+                    Some((_, c)) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+
+        Err(QueryParserError::UnterminatedString {
+            span: Span {
+                start,
+                end: self.src.len(),
+            },
+        })
+    }
+
+    fn operator(&mut self) -> Result<TokenKind, QueryParserError> {
+        let start = self.offset();
+        let op = self.take_while(|c| matches!(c, '=' | '!' | '<' | '>' | '|'));
+
+        Ok(match op.as_str() {
+            "=" => TokenKind::Eq,
+            "!=" | "<>" => TokenKind::NotEq,
+            "<" => TokenKind::Lt,
+            "<=" => TokenKind::LtEq,
+            ">" => TokenKind::Gt,
+            ">=" => TokenKind::GtEq,
+            "||" => TokenKind::Concat,
+            _ => {
+                return Err(QueryParserError::InvalidOperator {
+                    span: Span {
+                        start,
+                        end: self.offset(),
+                    },
+                    op,
+                })
+            }
+        })
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut value = String::new();
+        while let Some(&(_, c)) = self.chars.peek() {
+            if !predicate(c) {
+                break;
+            }
+            value.push(c);
+            self.chars.next();
+        }
+
+        value
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars.peek().map(|&(i, _)| i).unwrap_or(self.src.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lexer, Span, TokenKind};
+    use crate::query_parser::QueryParserError;
+
+    fn kinds(src: &str) -> Vec<TokenKind> {
+        Lexer::tokenize(src)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    #[test]
+    fn tokenize_select() {
+        assert_eq!(
+            kinds("SELECT * FROM user WHERE age >= 12.5;"),
+            vec![
+                TokenKind::Word("SELECT".into()),
+                TokenKind::Star,
+                TokenKind::Word("FROM".into()),
+                TokenKind::Word("user".into()),
+                TokenKind::Word("WHERE".into()),
+                TokenKind::Word("age".into()),
+                TokenKind::GtEq,
+                TokenKind::Number("12.5".into()),
+                TokenKind::Semicolon,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_quoted_values() {
+        assert_eq!(
+            kinds(r#"'Cairo, EG' "John Doe" `order`"#),
+            vec![
+                TokenKind::Str("Cairo, EG".into()),
+                TokenKind::Str("John Doe".into()),
+                TokenKind::QuotedIdent("order".into()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_escaped_quotes() {
+        assert_eq!(
+            kinds(r#"'it''s' "say ""hi""" 'a\'b\\c\n' '50\%' `a``b` `a\b`"#),
+            vec![
+                TokenKind::Str("it's".into()),
+                TokenKind::Str("say \"hi\"".into()),
+                TokenKind::Str("a'b\\c\n".into()),
+                TokenKind::Str("50\\%".into()),
+                TokenKind::QuotedIdent("a`b".into()),
+                TokenKind::QuotedIdent("a\\b".into()),
+                TokenKind::Eof,
+            ]
+        );
+        assert!(matches!(
+            Lexer::tokenize(r"name = 'jone\'"),
+            Err(QueryParserError::UnterminatedString { .. })
+        ));
+    }
+
+    #[test]
+    fn tokenize_blob_strings() {
+        assert_eq!(
+            kinds("X'0A1B' x'' xs 'x'"),
+            vec![
+                TokenKind::HexStr("0A1B".into()),
+                TokenKind::HexStr("".into()),
+                TokenKind::Word("xs".into()),
+                TokenKind::Str("x".into()),
+                TokenKind::Eof,
+            ]
+        );
+        assert_eq!(
+            kinds("B64'Chs=' b64'' b64 'x' b6"),
+            vec![
+                TokenKind::Base64Str("Chs=".into()),
+                TokenKind::Base64Str("".into()),
+                TokenKind::Word("b64".into()),
+                TokenKind::Str("x".into()),
+                TokenKind::Word("b6".into()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_json_operators() {
+        assert_eq!(
+            kinds("doc->'a'->>0 - -1"),
+            vec![
+                TokenKind::Word("doc".into()),
+                TokenKind::Arrow,
+                TokenKind::Str("a".into()),
+                TokenKind::LongArrow,
+                TokenKind::Number("0".into()),
+                TokenKind::Minus,
+                TokenKind::Minus,
+                TokenKind::Number("1".into()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn skip_comments() {
+        assert_eq!(
+            kinds("-- all users\nSELECT /* every column */ *"),
+            vec![
+                TokenKind::Word("SELECT".into()),
+                TokenKind::Star,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn token_spans() {
+        let tokens = Lexer::tokenize("id <> 'a'").unwrap();
+        let spans = tokens
+            .iter()
+            .map(|t| (t.span.start, t.span.end))
+            .collect::<Vec<_>>();
+        assert_eq!(spans, vec![(0, 2), (3, 5), (6, 9), (9, 9)]);
+    }
+
+    #[test]
+    fn tokenize_arithmetic() {
+        assert_eq!(
+            kinds("-price * 2 % 3 || '$'"),
+            vec![
+                TokenKind::Minus,
+                TokenKind::Word("price".into()),
+                TokenKind::Star,
+                TokenKind::Number("2".into()),
+                TokenKind::Percent,
+                TokenKind::Number("3".into()),
+                TokenKind::Concat,
+                TokenKind::Str("$".into()),
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_numbers() {
+        assert_eq!(
+            kinds("1e3 1.5E+2 2e-1 7"),
+            vec![
+                TokenKind::Number("1e3".into()),
+                TokenKind::Number("1.5E+2".into()),
+                TokenKind::Number("2e-1".into()),
+                TokenKind::Number("7".into()),
+                TokenKind::Eof,
+            ]
+        );
+        assert_eq!(
+            Lexer::tokenize("SELECT 12abc"),
+            Err(QueryParserError::UnexpectedChar {
+                span: Span { start: 9, end: 10 },
+                found: 'a'
+            })
+        );
+        assert!(Lexer::tokenize("1e").is_err());
+        assert!(Lexer::tokenize("1e+x").is_err());
+    }
+
+    #[test]
+    fn unterminated_string() {
+        assert_eq!(
+            Lexer::tokenize("name = 'jone"),
+            Err(QueryParserError::UnterminatedString {
+                span: Span { start: 7, end: 12 }
+            })
+        );
+    }
+}
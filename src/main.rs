@@ -1,10 +1,20 @@
+mod ast;
 mod database;
+mod datetime;
+mod eval;
+mod executor;
+mod functions;
+mod json;
+mod lexer;
+mod parser;
 mod query_parser;
 mod query_planner;
 mod regex;
 mod table;
 mod types;
 mod utils;
+mod value;
+mod window;
 
 use query_planner::{QueryPlanner, QueryPlannerError};
 use thiserror::Error;
@@ -0,0 +1,1920 @@
+//! A recursive descent parser turning [tokens](crate::lexer::Token) into a [`Statement`].
+//!
+//! Expressions are parsed with precedence climbing, see [`BinaryOperator::precedence`].
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+use crate::{
+    ast::{
+        AlterTableOperation, BinaryOperator, ColumnDef, Cte, Expr, FrameBound, Join, JoinKind,
+        Literal, OrderByExpr, Query, Select, SelectItem, SetExpr, SetOperator, Statement,
+        TableFactor, TableWithJoins, UnaryOperator, WindowFrame, WindowSpec, With,
+    },
+    lexer::{Lexer, Span, Token, TokenKind},
+    query_parser::QueryParserError,
+    types::{DataType, DataTypesErr},
+};
+
+/// Words that can't be used as identifiers unless quoted with backticks.
+const RESERVED: &[&str] = &[
+    "SELECT",
+    "FROM",
+    "WHERE",
+    "INSERT",
+    "INTO",
+    "VALUES",
+    "UPDATE",
+    "SET",
+    "DELETE",
+    "AND",
+    "OR",
+    "NOT",
+    "TRUE",
+    "FALSE",
+    "NULL",
+    "IS",
+    "ORDER",
+    "BY",
+    "LIMIT",
+    "OFFSET",
+    "FETCH",
+    "GROUP",
+    "HAVING",
+    "DISTINCT",
+    "JOIN",
+    "INNER",
+    "LEFT",
+    "RIGHT",
+    "FULL",
+    "OUTER",
+    "CROSS",
+    "ON",
+    "AS",
+    "UNION",
+    "INTERSECT",
+    "EXCEPT",
+    "ALL",
+    "OVER",
+    "CASE",
+    "WHEN",
+    "THEN",
+    "ELSE",
+    "END",
+    "LIKE",
+    "ILIKE",
+    "BETWEEN",
+    "REGEXP",
+];
+
+/// Keywords of the postfix conditions, each can be negated with `NOT`.
+const POSTFIX_KEYWORDS: &[&str] = &["IN", "LIKE", "ILIKE", "BETWEEN", "REGEXP"];
+
+/// Types of the literals written as the type followed by a string, like
+/// `DATE '2024-01-31'`.
+const TYPED_LITERALS: &[&str] = &[
+    "DATE",
+    "TIME",
+    "TIMESTAMP",
+    "TIMESTAMPTZ",
+    "INTERVAL",
+    "BLOB",
+    "BYTEA",
+];
+
+/// Units that can follow a number in `INTERVAL '3' DAY`.
+const INTERVAL_UNITS: &[&str] = &["YEAR", "MONTH", "WEEK", "DAY", "HOUR", "MINUTE", "SECOND"];
+
+/// Functions that can be called without parentheses.
+const NILADIC_FUNCTIONS: &[&str] = &["CURRENT_DATE", "CURRENT_TIME", "CURRENT_TIMESTAMP"];
+
+type ParserResult<T> = Result<T, QueryParserError>;
+
+pub struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(src: &'a str) -> ParserResult<Self> {
+        Ok(Self {
+            src,
+            tokens: Lexer::tokenize(src)?,
+            pos: 0,
+        })
+    }
+
+    /// Parse a single statement optionally followed by a semicolon.
+    pub fn parse_statement(&mut self) -> ParserResult<Statement> {
+        let statement = match self.peek() {
+            t if t.is_keyword("SHOW") => self.parse_show()?,
+            t if t.is_keyword("CREATE") => self.parse_create()?,
+            t if t.is_keyword("DROP") => self.parse_drop()?,
+            t if t.is_keyword("USE") => self.parse_use()?,
+            t if t.is_keyword("TRUNCATE") => self.parse_truncate()?,
+            t if t.is_keyword("ALTER") => self.parse_alter()?,
+            t if t.is_keyword("SELECT") || t.is_keyword("WITH") => {
+                Statement::Select(self.parse_query()?)
+            }
+            t if t.is_keyword("INSERT") => self.parse_insert()?,
+            t if t.is_keyword("UPDATE") => self.parse_update()?,
+            t if t.is_keyword("DELETE") => self.parse_delete()?,
+            _ => return self.expected("a statement"),
+        };
+
+        self.consume(&TokenKind::Semicolon);
+        self.expect(&TokenKind::Eof)?;
+        Ok(statement)
+    }
+
+    fn parse_show(&mut self) -> ParserResult<Statement> {
+        self.expect_keyword("SHOW")?;
+
+        if self.parse_keyword("DATABASES") {
+            Ok(Statement::ShowDatabases)
+        } else if self.parse_keyword("TABLES") {
+            Ok(Statement::ShowTables)
+        } else if self.parse_keyword("CURRENT") {
+            self.expect_keyword("DATABASE")?;
+            Ok(Statement::ShowCurrentDatabase)
+        } else {
+            self.expected("`DATABASES`, `TABLES` or `CURRENT DATABASE`")
+        }
+    }
+
+    fn parse_create(&mut self) -> ParserResult<Statement> {
+        self.expect_keyword("CREATE")?;
+
+        if self.parse_keyword("DATABASE") {
+            return Ok(Statement::CreateDatabase(self.parse_identifier()?));
+        }
+
+        self.expect_keyword("TABLE")?;
+        let name = self.parse_identifier()?;
+        self.expect(&TokenKind::LParen)?;
+
+        let mut columns = Vec::new();
+        // A trailing comma before the closing paren is allowed.
+        while self.peek().kind != TokenKind::RParen {
+            columns.push(self.parse_column_def()?);
+            if !self.consume(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(&TokenKind::RParen)?;
+
+        Ok(Statement::CreateTable { name, columns })
+    }
+
+    fn parse_drop(&mut self) -> ParserResult<Statement> {
+        self.expect_keyword("DROP")?;
+
+        if self.parse_keyword("DATABASE") {
+            Ok(Statement::DropDatabase(self.parse_identifier()?))
+        } else {
+            self.expect_keyword("TABLE")?;
+            Ok(Statement::DropTable(self.parse_identifier()?))
+        }
+    }
+
+    fn parse_use(&mut self) -> ParserResult<Statement> {
+        self.expect_keyword("USE")?;
+        self.parse_keyword("DATABASE");
+        Ok(Statement::UseDatabase(self.parse_identifier()?))
+    }
+
+    fn parse_truncate(&mut self) -> ParserResult<Statement> {
+        self.expect_keyword("TRUNCATE")?;
+        self.parse_keyword("TABLE");
+        Ok(Statement::TruncateTable(self.parse_identifier()?))
+    }
+
+    fn parse_alter(&mut self) -> ParserResult<Statement> {
+        self.expect_keyword("ALTER")?;
+        self.expect_keyword("TABLE")?;
+        let name = self.parse_identifier()?;
+
+        let operation = if self.parse_keyword("ADD") {
+            self.parse_keyword("COLUMN");
+            AlterTableOperation::AddColumn(self.parse_column_def()?)
+        } else if self.parse_keyword("DROP") {
+            self.expect_keyword("COLUMN")?;
+            AlterTableOperation::DropColumn(self.parse_identifier()?)
+        } else if self.parse_keyword("ALTER") || self.parse_keyword("MODIFY") {
+            self.parse_keyword("COLUMN");
+            AlterTableOperation::AlterColumn(self.parse_column_def()?)
+        } else {
+            return self.expected("`ADD`, `DROP` or `ALTER`");
+        };
+
+        Ok(Statement::AlterTable { name, operation })
+    }
+
+    /// A `SELECT` or a combination of them, `ORDER BY` and `LIMIT` apply to
+    /// the whole combination.
+    fn parse_query(&mut self) -> ParserResult<Query> {
+        let with = if self.parse_keyword("WITH") {
+            Some(With {
+                recursive: self.parse_keyword("RECURSIVE"),
+                ctes: self.parse_comma_separated(Parser::parse_cte)?,
+            })
+        } else {
+            None
+        };
+        let body = self.parse_set_expr(0)?;
+
+        let order_by = if self.parse_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            self.parse_comma_separated(Parser::parse_order_by_expr)?
+        } else {
+            vec![]
+        };
+
+        let (limit, offset) = self.parse_limit()?;
+
+        Ok(Query {
+            with,
+            body,
+            order_by,
+            limit,
+            offset,
+        })
+    }
+
+    fn parse_cte(&mut self) -> ParserResult<Cte> {
+        let name = self.parse_identifier()?;
+        let columns = if self.consume(&TokenKind::LParen) {
+            let columns = self.parse_comma_separated(Parser::parse_identifier)?;
+            self.expect(&TokenKind::RParen)?;
+            columns
+        } else {
+            vec![]
+        };
+        self.expect_keyword("AS")?;
+        let query = self.parse_subquery()?;
+
+        Ok(Cte {
+            name,
+            columns,
+            query,
+        })
+    }
+
+    /// Set operations are parsed with precedence climbing like binary operators.
+    fn parse_set_expr(&mut self, precedence: u8) -> ParserResult<SetExpr> {
+        let mut expr = SetExpr::Select(Box::new(self.parse_select()?));
+
+        loop {
+            let op = match self.peek_set_operator() {
+                Some(op) if op.precedence() > precedence => op,
+                _ => break,
+            };
+            self.next();
+
+            let all = self.parse_keyword("ALL");
+            if !all {
+                self.parse_keyword("DISTINCT");
+            }
+            let right = self.parse_set_expr(op.precedence())?;
+            expr = SetExpr::SetOperation {
+                op,
+                all,
+                left: Box::new(expr),
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    fn peek_set_operator(&self) -> Option<SetOperator> {
+        let token = self.peek();
+        if token.is_keyword("UNION") {
+            Some(SetOperator::Union)
+        } else if token.is_keyword("INTERSECT") {
+            Some(SetOperator::Intersect)
+        } else if token.is_keyword("EXCEPT") {
+            Some(SetOperator::Except)
+        } else {
+            None
+        }
+    }
+
+    fn parse_select(&mut self) -> ParserResult<Select> {
+        self.expect_keyword("SELECT")?;
+        let distinct = self.parse_keyword("DISTINCT");
+        if !distinct {
+            self.parse_keyword("ALL");
+        }
+
+        let projection = if self.consume(&TokenKind::Star) {
+            vec![SelectItem::Wildcard]
+        } else {
+            self.parse_comma_separated(Parser::parse_select_item)?
+        };
+
+        let from = match projection.as_slice() {
+            // `*` needs the columns of a table.
+            [SelectItem::Wildcard] => {
+                self.expect_keyword("FROM")?;
+                Some(self.parse_table_with_joins()?)
+            }
+            _ if self.parse_keyword("FROM") => Some(self.parse_table_with_joins()?),
+            _ => None,
+        };
+        let selection = self.parse_where()?;
+
+        let group_by = if self.parse_keyword("GROUP") {
+            self.expect_keyword("BY")?;
+            self.parse_comma_separated(Parser::parse_expr)?
+        } else {
+            vec![]
+        };
+        let having = if self.parse_keyword("HAVING") {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+
+        Ok(Select {
+            distinct,
+            projection,
+            from,
+            selection,
+            group_by,
+            having,
+        })
+    }
+
+    /// `LIMIT n [OFFSET m]` or the standard `[OFFSET m {ROW|ROWS}] FETCH
+    /// {FIRST|NEXT} [n] {ROW|ROWS} ONLY`.
+    fn parse_limit(&mut self) -> ParserResult<(Option<usize>, Option<usize>)> {
+        if self.parse_keyword("LIMIT") {
+            let limit = self.parse_count()?;
+            let offset = if self.parse_keyword("OFFSET") {
+                Some(self.parse_count()?)
+            } else {
+                None
+            };
+
+            return Ok((Some(limit), offset));
+        }
+
+        let offset = if self.parse_keyword("OFFSET") {
+            let offset = self.parse_count()?;
+            let _ = self.parse_keyword("ROWS") || self.parse_keyword("ROW");
+            Some(offset)
+        } else {
+            None
+        };
+
+        let limit = if self.parse_keyword("FETCH") {
+            if !self.parse_keyword("FIRST") && !self.parse_keyword("NEXT") {
+                return self.expected("`FIRST` or `NEXT`");
+            }
+            let limit = match self.peek().kind {
+                TokenKind::Number(_) => self.parse_count()?,
+                _ => 1,
+            };
+            if !self.parse_keyword("ROWS") && !self.parse_keyword("ROW") {
+                return self.expected("`ROWS`");
+            }
+            self.expect_keyword("ONLY")?;
+            Some(limit)
+        } else {
+            None
+        };
+
+        Ok((limit, offset))
+    }
+
+    /// A non negative integer like the number of rows of `LIMIT`.
+    fn parse_count(&mut self) -> ParserResult<usize> {
+        match &self.peek().kind {
+            TokenKind::Number(n) => match n.parse() {
+                Ok(count) => {
+                    self.next();
+                    Ok(count)
+                }
+                Err(_) => self.expected("a non negative integer"),
+            },
+            _ => self.expected("a non negative integer"),
+        }
+    }
+
+    fn parse_select_item(&mut self) -> ParserResult<SelectItem> {
+        let is_ident = matches!(
+            self.peek().kind,
+            TokenKind::Word(_) | TokenKind::QuotedIdent(_)
+        );
+        if is_ident
+            && self.peek_nth(1).kind == TokenKind::Dot
+            && self.peek_nth(2).kind == TokenKind::Star
+        {
+            let table = self.parse_identifier()?;
+            self.next();
+            self.next();
+            return Ok(SelectItem::QualifiedWildcard(table));
+        }
+
+        let expr = self.parse_expr()?;
+        Ok(match self.parse_alias()? {
+            Some(alias) => SelectItem::ExprWithAlias { expr, alias },
+            None => SelectItem::Expr(expr),
+        })
+    }
+
+    /// `a [JOIN b ON ...]...`, a comma between tables is a cross join.
+    fn parse_table_with_joins(&mut self) -> ParserResult<TableWithJoins> {
+        let relation = self.parse_table_factor()?;
+
+        let mut joins = vec![];
+        loop {
+            let kind = if self.consume(&TokenKind::Comma) {
+                JoinKind::Cross
+            } else if self.parse_keyword("CROSS") {
+                self.expect_keyword("JOIN")?;
+                JoinKind::Cross
+            } else if self.parse_keyword("JOIN") {
+                JoinKind::Inner
+            } else if self.parse_keyword("INNER") {
+                self.expect_keyword("JOIN")?;
+                JoinKind::Inner
+            } else if let Some(kind) = self.parse_outer_join_kind() {
+                self.parse_keyword("OUTER");
+                self.expect_keyword("JOIN")?;
+                kind
+            } else {
+                break;
+            };
+
+            let relation = self.parse_table_factor()?;
+            let constraint = if kind == JoinKind::Cross {
+                None
+            } else {
+                self.expect_keyword("ON")?;
+                Some(self.parse_expr()?)
+            };
+
+            joins.push(Join {
+                relation,
+                kind,
+                constraint,
+            });
+        }
+
+        Ok(TableWithJoins { relation, joins })
+    }
+
+    fn parse_outer_join_kind(&mut self) -> Option<JoinKind> {
+        if self.parse_keyword("LEFT") {
+            Some(JoinKind::Left)
+        } else if self.parse_keyword("RIGHT") {
+            Some(JoinKind::Right)
+        } else if self.parse_keyword("FULL") {
+            Some(JoinKind::Full)
+        } else {
+            None
+        }
+    }
+
+    /// A table name or a subquery followed by an alias, `AS` can be omitted.
+    /// Subqueries must have an alias.
+    fn parse_table_factor(&mut self) -> ParserResult<TableFactor> {
+        if self.peek().kind == TokenKind::LParen {
+            let subquery = Box::new(self.parse_subquery()?);
+            return match self.parse_alias()? {
+                Some(alias) => Ok(TableFactor::Derived { subquery, alias }),
+                None => self.expected("an alias for the subquery"),
+            };
+        }
+
+        let name = self.parse_identifier()?;
+        if self.consume(&TokenKind::LParen) {
+            let args = match self.peek().kind {
+                TokenKind::RParen => vec![],
+                _ => self.parse_comma_separated(Parser::parse_expr)?,
+            };
+            self.expect(&TokenKind::RParen)?;
+            let alias = self.parse_alias()?;
+            return Ok(TableFactor::Function { name, args, alias });
+        }
+        let alias = self.parse_alias()?;
+
+        Ok(TableFactor::Table { name, alias })
+    }
+
+    /// A query wrapped in parentheses.
+    fn parse_subquery(&mut self) -> ParserResult<Query> {
+        self.expect(&TokenKind::LParen)?;
+        let query = self.parse_query()?;
+        self.expect(&TokenKind::RParen)?;
+
+        Ok(query)
+    }
+
+    fn parse_alias(&mut self) -> ParserResult<Option<String>> {
+        if self.parse_keyword("AS") {
+            return Ok(Some(self.parse_identifier()?));
+        }
+
+        // Keywords like `FROM`, `JOIN` or `WHERE` end the item instead.
+        Ok(self.parse_identifier().ok())
+    }
+
+    fn parse_order_by_expr(&mut self) -> ParserResult<OrderByExpr> {
+        let expr = self.parse_expr()?;
+        let desc = if self.parse_keyword("DESC") {
+            true
+        } else {
+            self.parse_keyword("ASC");
+            false
+        };
+
+        let nulls_first = if self.parse_keyword("NULLS") {
+            if self.parse_keyword("FIRST") {
+                Some(true)
+            } else if self.parse_keyword("LAST") {
+                Some(false)
+            } else {
+                return self.expected("`FIRST` or `LAST`");
+            }
+        } else {
+            None
+        };
+
+        Ok(OrderByExpr {
+            expr,
+            desc,
+            nulls_first,
+        })
+    }
+
+    fn parse_insert(&mut self) -> ParserResult<Statement> {
+        self.expect_keyword("INSERT")?;
+        self.expect_keyword("INTO")?;
+        let table = self.parse_identifier()?;
+
+        let columns = if self.consume(&TokenKind::LParen) {
+            let columns = self.parse_comma_separated(Parser::parse_identifier)?;
+            self.expect(&TokenKind::RParen)?;
+            Some(columns)
+        } else {
+            None
+        };
+
+        self.expect_keyword("VALUES")?;
+        let mut rows = Vec::new();
+        loop {
+            self.expect(&TokenKind::LParen)?;
+            rows.push(self.parse_comma_separated(Parser::parse_expr)?);
+            self.expect(&TokenKind::RParen)?;
+
+            // Rows are comma separated but the comma is tolerated when missing.
+            self.consume(&TokenKind::Comma);
+            if self.peek().kind != TokenKind::LParen {
+                break;
+            }
+        }
+
+        Ok(Statement::Insert {
+            table,
+            columns,
+            rows,
+        })
+    }
+
+    fn parse_update(&mut self) -> ParserResult<Statement> {
+        self.expect_keyword("UPDATE")?;
+        let table = self.parse_identifier()?;
+        self.expect_keyword("SET")?;
+
+        let assignments = self.parse_comma_separated(|p| {
+            let col = p.parse_identifier()?;
+            p.expect(&TokenKind::Eq)?;
+            Ok((col, p.parse_expr()?))
+        })?;
+        let selection = self.parse_where()?;
+
+        Ok(Statement::Update {
+            table,
+            assignments,
+            selection,
+        })
+    }
+
+    fn parse_delete(&mut self) -> ParserResult<Statement> {
+        self.expect_keyword("DELETE")?;
+        self.expect_keyword("FROM")?;
+        let table = self.parse_identifier()?;
+        self.expect_keyword("WHERE")?;
+        let selection = self.parse_expr()?;
+
+        Ok(Statement::Delete { table, selection })
+    }
+
+    fn parse_where(&mut self) -> ParserResult<Option<Expr>> {
+        if self.parse_keyword("WHERE") {
+            Ok(Some(self.parse_expr()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// A column name and its data type, followed by `NULL`, `NOT NULL` and
+    /// `DEFAULT expr` in any order. Columns are nullable unless told
+    /// otherwise.
+    fn parse_column_def(&mut self) -> ParserResult<ColumnDef> {
+        let name = self.parse_identifier()?;
+        let datatype = self.parse_data_type()?;
+        let mut not_null = false;
+        let mut default = None;
+
+        loop {
+            if self.parse_keyword("NOT") {
+                self.expect_keyword("NULL")?;
+                not_null = true;
+            } else if self.parse_keyword("NULL") {
+                not_null = false;
+            } else if self.parse_keyword("DEFAULT") {
+                default = Some(self.parse_expr()?);
+            } else {
+                break;
+            }
+        }
+
+        Ok(ColumnDef {
+            name,
+            datatype,
+            not_null,
+            default,
+        })
+    }
+
+    /// Data types are handed over to [`DataType::parse`] as raw text, this
+    /// takes every token up to the next top level comma, closing paren or
+    /// column constraint.
+    fn parse_data_type(&mut self) -> ParserResult<DataType> {
+        let start = self.peek().span.start;
+        let mut end = start;
+        let mut depth = 0;
+
+        loop {
+            match self.peek().kind {
+                TokenKind::Eof | TokenKind::Semicolon => break,
+                TokenKind::Comma | TokenKind::RParen if depth == 0 => break,
+                _ if depth == 0
+                    && ["NOT", "NULL", "DEFAULT"]
+                        .iter()
+                        .any(|k| self.peek().is_keyword(k)) =>
+                {
+                    break
+                }
+                TokenKind::LParen => depth += 1,
+                TokenKind::RParen => depth -= 1,
+                _ => {}
+            }
+            end = self.next().span.end;
+        }
+
+        if start == end {
+            return self.expected("a data type");
+        }
+
+        DataType::parse(&self.src[start..end]).map_err(|source| QueryParserError::DataTypeErr {
+            span: Span { start, end },
+            source,
+        })
+    }
+
+    pub fn parse_expr(&mut self) -> ParserResult<Expr> {
+        self.parse_subexpr(0)
+    }
+
+    fn parse_subexpr(&mut self, min_precedence: u8) -> ParserResult<Expr> {
+        let mut expr = self.parse_prefix()?;
+
+        loop {
+            let is_postfix = |token: &Token| POSTFIX_KEYWORDS.iter().any(|k| token.is_keyword(k));
+            let negated = self.peek().is_keyword("NOT") && is_postfix(self.peek_nth(1));
+            if is_postfix(self.peek()) || negated {
+                if Expr::POSTFIX_PRECEDENCE <= min_precedence {
+                    break;
+                }
+
+                if negated {
+                    self.next();
+                }
+                expr = self.parse_postfix(expr, negated)?;
+                continue;
+            }
+
+            if self.peek().is_keyword("IS") {
+                if Expr::POSTFIX_PRECEDENCE <= min_precedence {
+                    break;
+                }
+
+                self.next();
+                let negated = self.parse_keyword("NOT");
+                self.expect_keyword("NULL")?;
+                expr = Expr::IsNull {
+                    expr: Box::new(expr),
+                    negated,
+                };
+                continue;
+            }
+
+            let op = match self.peek_binary_operator() {
+                Some(op) => op,
+                None => break,
+            };
+            let precedence = op.precedence();
+            if precedence <= min_precedence {
+                break;
+            }
+
+            self.next();
+            let right = self.parse_subexpr(precedence)?;
+            expr = Expr::BinaryOp {
+                left: Box::new(expr),
+                op,
+                right: Box::new(right),
+            };
+        }
+
+        Ok(expr)
+    }
+
+    /// `[NOT] IN`, `LIKE`, `ILIKE`, `BETWEEN` or `REGEXP` applied to `expr`,
+    /// the `NOT` is already consumed.
+    fn parse_postfix(&mut self, expr: Expr, negated: bool) -> ParserResult<Expr> {
+        let expr = Box::new(expr);
+        let operand = |parser: &mut Self| -> ParserResult<Box<Expr>> {
+            Ok(Box::new(parser.parse_subexpr(Expr::POSTFIX_PRECEDENCE)?))
+        };
+
+        if self.parse_keyword("IN") {
+            let is_subquery =
+                self.peek_nth(1).is_keyword("SELECT") || self.peek_nth(1).is_keyword("WITH");
+            if self.peek().kind == TokenKind::LParen && is_subquery {
+                return Ok(Expr::InSubquery {
+                    expr,
+                    subquery: Box::new(self.parse_subquery()?),
+                    negated,
+                });
+            }
+
+            self.expect(&TokenKind::LParen)?;
+            let list = self.parse_comma_separated(Parser::parse_expr)?;
+            self.expect(&TokenKind::RParen)?;
+            return Ok(Expr::InList {
+                expr,
+                list,
+                negated,
+            });
+        }
+
+        if self.parse_keyword("BETWEEN") {
+            let low = operand(self)?;
+            self.expect_keyword("AND")?;
+            return Ok(Expr::Between {
+                expr,
+                low,
+                high: operand(self)?,
+                negated,
+            });
+        }
+
+        if self.parse_keyword("REGEXP") {
+            let regexp = Expr::BinaryOp {
+                left: expr,
+                op: BinaryOperator::Regexp,
+                right: operand(self)?,
+            };
+            return Ok(match negated {
+                true => Expr::UnaryOp {
+                    op: UnaryOperator::Not,
+                    expr: Box::new(regexp),
+                },
+                false => regexp,
+            });
+        }
+
+        let case_insensitive = self.parse_keyword("ILIKE");
+        if !case_insensitive {
+            self.expect_keyword("LIKE")?;
+        }
+        let pattern = operand(self)?;
+        let escape = match self.parse_keyword("ESCAPE") {
+            true => Some(operand(self)?),
+            false => None,
+        };
+        Ok(Expr::Like {
+            expr,
+            pattern,
+            escape,
+            negated,
+            case_insensitive,
+        })
+    }
+
+    /// Blobs are a cast of their text form, checked here to report bad
+    /// digits before running the query.
+    fn blob_literal(&self, span: Span, value: String) -> ParserResult<Expr> {
+        DataType::BLOB
+            .parse_value(&value)
+            .map_err(|source| QueryParserError::DataTypeErr { span, source })?;
+
+        Ok(Expr::Cast {
+            expr: Box::new(Expr::Literal(Literal::String(value))),
+            data_type: DataType::BLOB,
+        })
+    }
+
+    fn parse_prefix(&mut self) -> ParserResult<Expr> {
+        let token = self.peek().clone();
+
+        let expr = match token.kind {
+            _ if token.is_keyword("NOT") => {
+                self.next();
+                let op = UnaryOperator::Not;
+                let expr = self.parse_subexpr(op.precedence())?;
+                return Ok(Expr::UnaryOp {
+                    op,
+                    expr: Box::new(expr),
+                });
+            }
+            _ if token.is_keyword("EXISTS") => {
+                self.next();
+                return Ok(Expr::Exists(Box::new(self.parse_subquery()?)));
+            }
+            _ if token.is_keyword("TRUE") => Expr::Literal(Literal::Boolean(true)),
+            _ if token.is_keyword("FALSE") => Expr::Literal(Literal::Boolean(false)),
+            _ if token.is_keyword("NULL") => Expr::Literal(Literal::Null),
+            TokenKind::Number(n) => Expr::Literal(Literal::Number(n)),
+            TokenKind::Str(s) => Expr::Literal(Literal::String(s)),
+            TokenKind::HexStr(digits) => self.blob_literal(token.span, format!("\\x{}", digits))?,
+            TokenKind::Base64Str(text) => {
+                let bytes = BASE64
+                    .decode(&text)
+                    .map_err(|_| QueryParserError::DataTypeErr {
+                        span: token.span,
+                        source: DataTypesErr::InvalidBlob(format!(
+                            "'{}' is not valid base64",
+                            text
+                        )),
+                    })?;
+                self.blob_literal(token.span, format!("\\x{}", hex::encode(bytes)))?
+            }
+            _ if token.is_keyword("CASE") => {
+                self.next();
+                return self.parse_case();
+            }
+            _ if token.is_keyword("CAST") && self.peek_nth(1).kind == TokenKind::LParen => {
+                self.next();
+                return self.parse_cast();
+            }
+            _ if token.is_keyword("EXTRACT")
+                && self.peek_nth(1).kind == TokenKind::LParen
+                && self.peek_nth(3).is_keyword("FROM") =>
+            {
+                self.next();
+                return self.parse_extract();
+            }
+            TokenKind::Word(ref word)
+                if TYPED_LITERALS.iter().any(|t| t.eq_ignore_ascii_case(word))
+                    && (matches!(self.peek_nth(1).kind, TokenKind::Str(_))
+                        || token.is_keyword("INTERVAL")
+                            && matches!(self.peek_nth(1).kind, TokenKind::Number(_))) =>
+            {
+                self.next();
+                let data_type =
+                    DataType::parse(word).map_err(|source| QueryParserError::DataTypeErr {
+                        span: token.span,
+                        source,
+                    })?;
+                return self.parse_typed_literal(data_type);
+            }
+            TokenKind::Word(ref name)
+                if NILADIC_FUNCTIONS
+                    .iter()
+                    .any(|f| f.eq_ignore_ascii_case(name))
+                    && self.peek_nth(1).kind != TokenKind::LParen =>
+            {
+                Expr::Function {
+                    name: name.clone(),
+                    args: vec![],
+                    distinct: false,
+                }
+            }
+            TokenKind::Minus => {
+                self.next();
+                match self.peek().kind.clone() {
+                    TokenKind::Number(n) => Expr::Literal(Literal::Number(format!("-{}", n))),
+                    _ => {
+                        let op = UnaryOperator::Minus;
+                        let expr = self.parse_subexpr(op.precedence())?;
+                        return Ok(Expr::UnaryOp {
+                            op,
+                            expr: Box::new(expr),
+                        });
+                    }
+                }
+            }
+            TokenKind::LParen
+                if self.peek_nth(1).is_keyword("SELECT") || self.peek_nth(1).is_keyword("WITH") =>
+            {
+                return Ok(Expr::Subquery(Box::new(self.parse_subquery()?)));
+            }
+            TokenKind::LParen => {
+                self.next();
+                let expr = self.parse_expr()?;
+                self.expect(&TokenKind::RParen)?;
+                return Ok(Expr::Nested(Box::new(expr)));
+            }
+            TokenKind::Word(_) | TokenKind::QuotedIdent(_) => {
+                let ident = self.parse_identifier()?;
+                if matches!(token.kind, TokenKind::Word(_)) && self.peek().kind == TokenKind::LParen
+                {
+                    return self.parse_function(ident);
+                }
+                if self.consume(&TokenKind::Dot) {
+                    return Ok(Expr::QualifiedIdentifier {
+                        table: ident,
+                        column: self.parse_identifier()?,
+                    });
+                }
+                return Ok(Expr::Identifier(ident));
+            }
+            _ => return self.expected("an expression"),
+        };
+
+        self.next();
+        Ok(expr)
+    }
+
+    /// The rest of `CAST(expr AS type)`, the keyword is already consumed.
+    fn parse_cast(&mut self) -> ParserResult<Expr> {
+        self.expect(&TokenKind::LParen)?;
+        let expr = self.parse_expr()?;
+        self.expect_keyword("AS")?;
+        let data_type = self.parse_data_type()?;
+        self.expect(&TokenKind::RParen)?;
+
+        Ok(Expr::Cast {
+            expr: Box::new(expr),
+            data_type,
+        })
+    }
+
+    /// The rest of `EXTRACT(field FROM expr)`, the keyword is already
+    /// consumed. The field is passed to the function as a string.
+    fn parse_extract(&mut self) -> ParserResult<Expr> {
+        self.expect(&TokenKind::LParen)?;
+        let field = match self.next().kind {
+            TokenKind::Word(field) | TokenKind::Str(field) => field,
+            _ => return self.expected("a date field"),
+        };
+        self.expect_keyword("FROM")?;
+        let expr = self.parse_expr()?;
+        self.expect(&TokenKind::RParen)?;
+
+        Ok(Expr::Function {
+            name: "EXTRACT".into(),
+            args: vec![Expr::Literal(Literal::String(field)), expr],
+            distinct: false,
+        })
+    }
+
+    /// The value of a typed literal like `DATE '2024-01-31'`, the type is
+    /// already consumed. It's a cast of the string, intervals can be a number
+    /// followed by a unit like `INTERVAL 3 DAY`.
+    fn parse_typed_literal(&mut self, data_type: DataType) -> ParserResult<Expr> {
+        let (mut value, numeric) = match self.next().kind {
+            TokenKind::Str(value) => {
+                let numeric = value.trim().parse::<f64>().is_ok();
+                (value, numeric)
+            }
+            TokenKind::Number(value) => (value, true),
+            _ => return self.expected("a string"),
+        };
+        if data_type == DataType::INTERVAL && numeric {
+            let unit = INTERVAL_UNITS.iter().find(|unit| {
+                let plural = format!("{}S", unit);
+                self.peek().is_keyword(unit) || self.peek().is_keyword(&plural)
+            });
+            if let Some(unit) = unit {
+                self.next();
+                value = format!("{} {}", value, unit);
+            }
+        }
+
+        Ok(Expr::Cast {
+            expr: Box::new(Expr::Literal(Literal::String(value))),
+            data_type,
+        })
+    }
+
+    /// The rest of a `CASE` expression, the keyword is already consumed.
+    fn parse_case(&mut self) -> ParserResult<Expr> {
+        let operand = match self.peek().is_keyword("WHEN") {
+            true => None,
+            false => Some(Box::new(self.parse_expr()?)),
+        };
+
+        let mut branches = Vec::new();
+        while self.parse_keyword("WHEN") {
+            let when = self.parse_expr()?;
+            self.expect_keyword("THEN")?;
+            branches.push((when, self.parse_expr()?));
+        }
+        if branches.is_empty() {
+            return self.expected("`WHEN`");
+        }
+
+        let else_result = match self.parse_keyword("ELSE") {
+            true => Some(Box::new(self.parse_expr()?)),
+            false => None,
+        };
+        self.expect_keyword("END")?;
+
+        Ok(Expr::Case {
+            operand,
+            branches,
+            else_result,
+        })
+    }
+
+    /// The arguments of a function call, the name is already consumed.
+    fn parse_function(&mut self, name: String) -> ParserResult<Expr> {
+        self.expect(&TokenKind::LParen)?;
+        let distinct = self.parse_keyword("DISTINCT");
+
+        let args = if self.consume(&TokenKind::Star) {
+            vec![Expr::Wildcard]
+        } else if self.peek().kind == TokenKind::RParen {
+            vec![]
+        } else {
+            self.parse_comma_separated(Parser::parse_expr)?
+        };
+        self.expect(&TokenKind::RParen)?;
+
+        if self.peek().is_keyword("OVER") {
+            if distinct {
+                return self.expected("a function without DISTINCT");
+            }
+            self.next();
+            return Ok(Expr::Window {
+                name,
+                args,
+                window: self.parse_window_spec()?,
+            });
+        }
+
+        Ok(Expr::Function {
+            name,
+            args,
+            distinct,
+        })
+    }
+
+    /// `(PARTITION BY ... ORDER BY ... ROWS ...)` after `OVER`, every clause
+    /// is optional.
+    fn parse_window_spec(&mut self) -> ParserResult<WindowSpec> {
+        self.expect(&TokenKind::LParen)?;
+
+        let partition_by = if self.parse_keyword("PARTITION") {
+            self.expect_keyword("BY")?;
+            self.parse_comma_separated(Parser::parse_expr)?
+        } else {
+            vec![]
+        };
+        let order_by = if self.parse_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            self.parse_comma_separated(Parser::parse_order_by_expr)?
+        } else {
+            vec![]
+        };
+        let frame = if self.parse_keyword("ROWS") {
+            Some(self.parse_window_frame()?)
+        } else {
+            None
+        };
+        self.expect(&TokenKind::RParen)?;
+
+        Ok(WindowSpec {
+            partition_by,
+            order_by,
+            frame,
+        })
+    }
+
+    /// `BETWEEN start AND end` or only the start, the frame then ends with
+    /// the current row.
+    fn parse_window_frame(&mut self) -> ParserResult<WindowFrame> {
+        let between = self.parse_keyword("BETWEEN");
+        let start_span = self.peek().span;
+        let start = self.parse_frame_bound()?;
+        if start == FrameBound::UnboundedFollowing {
+            return Err(QueryParserError::UnexpectedToken {
+                span: start_span,
+                expected: "a frame start".into(),
+                found: format!("`{}`", start),
+            });
+        }
+        if !between {
+            return Ok(WindowFrame {
+                start,
+                end: FrameBound::CurrentRow,
+            });
+        }
+
+        self.expect_keyword("AND")?;
+        let end_span = self.peek().span;
+        let end = self.parse_frame_bound()?;
+        let backwards = matches!(
+            (start, end),
+            (FrameBound::CurrentRow, FrameBound::Preceding(_))
+                | (
+                    FrameBound::Following(_),
+                    FrameBound::Preceding(_) | FrameBound::CurrentRow
+                )
+        );
+        if end == FrameBound::UnboundedPreceding || backwards {
+            return Err(QueryParserError::UnexpectedToken {
+                span: end_span,
+                expected: format!("a frame end after {}", start),
+                found: format!("`{}`", end),
+            });
+        }
+
+        Ok(WindowFrame { start, end })
+    }
+
+    fn parse_frame_bound(&mut self) -> ParserResult<FrameBound> {
+        if self.parse_keyword("CURRENT") {
+            self.expect_keyword("ROW")?;
+            return Ok(FrameBound::CurrentRow);
+        }
+
+        let count = if self.parse_keyword("UNBOUNDED") {
+            None
+        } else if let TokenKind::Number(_) = self.peek().kind {
+            Some(self.parse_count()?)
+        } else {
+            return self.expected("`UNBOUNDED`, `CURRENT ROW` or a number of rows");
+        };
+
+        if self.parse_keyword("PRECEDING") {
+            Ok(count.map_or(FrameBound::UnboundedPreceding, FrameBound::Preceding))
+        } else if self.parse_keyword("FOLLOWING") {
+            Ok(count.map_or(FrameBound::UnboundedFollowing, FrameBound::Following))
+        } else {
+            self.expected("`PRECEDING` or `FOLLOWING`")
+        }
+    }
+
+    fn peek_binary_operator(&self) -> Option<BinaryOperator> {
+        let token = self.peek();
+        if token.is_keyword("AND") {
+            return Some(BinaryOperator::And);
+        } else if token.is_keyword("OR") {
+            return Some(BinaryOperator::Or);
+        }
+
+        match token.kind {
+            TokenKind::Eq => Some(BinaryOperator::Eq),
+            TokenKind::NotEq => Some(BinaryOperator::NotEq),
+            TokenKind::Gt => Some(BinaryOperator::Gt),
+            TokenKind::Lt => Some(BinaryOperator::Lt),
+            TokenKind::GtEq => Some(BinaryOperator::GtEq),
+            TokenKind::LtEq => Some(BinaryOperator::LtEq),
+            TokenKind::Plus => Some(BinaryOperator::Plus),
+            TokenKind::Minus => Some(BinaryOperator::Minus),
+            TokenKind::Star => Some(BinaryOperator::Multiply),
+            TokenKind::Slash => Some(BinaryOperator::Divide),
+            TokenKind::Percent => Some(BinaryOperator::Modulo),
+            TokenKind::Concat => Some(BinaryOperator::StringConcat),
+            TokenKind::Arrow => Some(BinaryOperator::JsonGet),
+            TokenKind::LongArrow => Some(BinaryOperator::JsonGetText),
+            _ => None,
+        }
+    }
+
+    fn parse_identifier(&mut self) -> ParserResult<String> {
+        match &self.peek().kind {
+            TokenKind::Word(w) if !RESERVED.iter().any(|r| r.eq_ignore_ascii_case(w)) => {
+                let ident = w.clone();
+                self.next();
+                Ok(ident)
+            }
+            TokenKind::QuotedIdent(ident) => {
+                let ident = ident.clone();
+                self.next();
+                Ok(ident)
+            }
+            _ => self.expected("an identifier"),
+        }
+    }
+
+    fn parse_comma_separated<T>(
+        &mut self,
+        mut f: impl FnMut(&mut Parser<'a>) -> ParserResult<T>,
+    ) -> ParserResult<Vec<T>> {
+        let mut values = vec![f(self)?];
+        while self.consume(&TokenKind::Comma) {
+            values.push(f(self)?);
+        }
+
+        Ok(values)
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    /// The token `n` positions after the current one, `Eof` past the end.
+    fn peek_nth(&self, n: usize) -> &Token {
+        let last = self.tokens.len() - 1;
+        &self.tokens[(self.pos + n).min(last)]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if token.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+
+        token
+    }
+
+    fn consume(&mut self, kind: &TokenKind) -> bool {
+        if &self.peek().kind == kind {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: &TokenKind) -> ParserResult<()> {
+        if self.consume(kind) {
+            Ok(())
+        } else {
+            self.expected(&kind.to_string())
+        }
+    }
+
+    fn parse_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek().is_keyword(keyword) {
+            self.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> ParserResult<()> {
+        if self.parse_keyword(keyword) {
+            Ok(())
+        } else {
+            self.expected(&format!("`{}`", keyword))
+        }
+    }
+
+    fn expected<T>(&self, expected: &str) -> ParserResult<T> {
+        Err(QueryParserError::UnexpectedToken {
+            span: self.peek().span,
+            expected: expected.to_string(),
+            found: self.peek().kind.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Parser;
+    use crate::{
+        ast::{
+            BinaryOperator, Expr, JoinKind, Literal, OrderByExpr, Query, Select, SelectItem,
+            SetExpr, SetOperator, Statement, TableFactor, TableWithJoins, UnaryOperator,
+        },
+        lexer::Span,
+        query_parser::QueryParserError,
+        types::DataTypesErr,
+    };
+
+    /// The single `SELECT` of a query.
+    fn parse_select(query: &str) -> Select {
+        match Parser::new(query).unwrap().parse_statement().unwrap() {
+            Statement::Select(Query {
+                body: SetExpr::Select(select),
+                ..
+            }) => *select,
+            _ => panic!("Unexpected statement"),
+        }
+    }
+
+    #[test]
+    fn parse_nested_expression() {
+        let expr = Parser::new("(age) >= -12").unwrap().parse_expr().unwrap();
+
+        assert_eq!(
+            expr,
+            Expr::BinaryOp {
+                left: Box::new(Expr::Nested(Box::new(Expr::Identifier("age".into())))),
+                op: BinaryOperator::GtEq,
+                right: Box::new(Expr::Literal(Literal::Number("-12".into()))),
+            }
+        );
+    }
+
+    #[test]
+    fn logical_operators_precedence() {
+        let expr = Parser::new("a = 1 OR b = 2 AND NOT c = 3")
+            .unwrap()
+            .parse_expr()
+            .unwrap();
+
+        let cmp = |col: &str, value: &str| Expr::BinaryOp {
+            left: Box::new(Expr::Identifier(col.into())),
+            op: BinaryOperator::Eq,
+            right: Box::new(Expr::Literal(Literal::Number(value.into()))),
+        };
+
+        assert_eq!(
+            expr,
+            Expr::BinaryOp {
+                left: Box::new(cmp("a", "1")),
+                op: BinaryOperator::Or,
+                right: Box::new(Expr::BinaryOp {
+                    left: Box::new(cmp("b", "2")),
+                    op: BinaryOperator::And,
+                    right: Box::new(Expr::UnaryOp {
+                        op: UnaryOperator::Not,
+                        expr: Box::new(cmp("c", "3")),
+                    }),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn is_null_binds_like_a_comparison() {
+        let expr = Parser::new("NOT a IS NULL AND b IS NOT NULL")
+            .unwrap()
+            .parse_expr()
+            .unwrap();
+
+        let is_null = |col: &str, negated| Expr::IsNull {
+            expr: Box::new(Expr::Identifier(col.into())),
+            negated,
+        };
+
+        assert_eq!(
+            expr,
+            Expr::BinaryOp {
+                left: Box::new(Expr::UnaryOp {
+                    op: UnaryOperator::Not,
+                    expr: Box::new(is_null("a", false)),
+                }),
+                op: BinaryOperator::And,
+                right: Box::new(is_null("b", true)),
+            }
+        );
+    }
+
+    #[test]
+    fn parentheses_override_precedence() {
+        let expr = Parser::new("(a OR b) AND c").unwrap().parse_expr().unwrap();
+
+        assert_eq!(
+            expr,
+            Expr::BinaryOp {
+                left: Box::new(Expr::Nested(Box::new(Expr::BinaryOp {
+                    left: Box::new(Expr::Identifier("a".into())),
+                    op: BinaryOperator::Or,
+                    right: Box::new(Expr::Identifier("b".into())),
+                }))),
+                op: BinaryOperator::And,
+                right: Box::new(Expr::Identifier("c".into())),
+            }
+        );
+    }
+
+    #[test]
+    fn select_with_database_in_the_condition() {
+        let statement = Parser::new("SELECT name FROM user WHERE name = 'database'")
+            .unwrap()
+            .parse_statement()
+            .unwrap();
+
+        assert_eq!(
+            statement,
+            Statement::Select(Query {
+                with: None,
+                body: SetExpr::Select(Box::new(Select {
+                    distinct: false,
+                    projection: vec![SelectItem::Expr(Expr::Identifier("name".into()))],
+                    from: Some(TableWithJoins {
+                        relation: TableFactor::Table {
+                            name: "user".into(),
+                            alias: None,
+                        },
+                        joins: vec![],
+                    }),
+                    selection: Some(Expr::BinaryOp {
+                        left: Box::new(Expr::Identifier("name".into())),
+                        op: BinaryOperator::Eq,
+                        right: Box::new(Expr::Literal(Literal::String("database".into()))),
+                    }),
+                    group_by: vec![],
+                    having: None,
+                })),
+                order_by: vec![],
+                limit: None,
+                offset: None,
+            })
+        );
+    }
+
+    #[test]
+    fn select_with_order_by() {
+        let statement =
+            Parser::new("SELECT * FROM user ORDER BY age DESC, name NULLS FIRST, id ASC")
+                .unwrap()
+                .parse_statement()
+                .unwrap();
+
+        let key = |col: &str, desc, nulls_first| OrderByExpr {
+            expr: Expr::Identifier(col.into()),
+            desc,
+            nulls_first,
+        };
+
+        if let Statement::Select(Query { order_by, .. }) = statement {
+            assert_eq!(
+                order_by,
+                vec![
+                    key("age", true, None),
+                    key("name", false, Some(true)),
+                    key("id", false, None),
+                ]
+            );
+        } else {
+            panic!("Unexpected statement")
+        }
+    }
+
+    #[test]
+    fn select_with_limit_and_offset() {
+        let limit = |query: &str| match Parser::new(query).unwrap().parse_statement() {
+            Ok(Statement::Select(Query { limit, offset, .. })) => Ok((limit, offset)),
+            Ok(_) => panic!("Unexpected statement"),
+            Err(e) => Err(e),
+        };
+
+        assert_eq!(limit("SELECT * FROM t LIMIT 10"), Ok((Some(10), None)));
+        assert_eq!(
+            limit("SELECT * FROM t ORDER BY id LIMIT 10 OFFSET 5"),
+            Ok((Some(10), Some(5)))
+        );
+        assert_eq!(
+            limit("SELECT * FROM t OFFSET 5 ROWS FETCH NEXT 3 ROWS ONLY"),
+            Ok((Some(3), Some(5)))
+        );
+        assert_eq!(
+            limit("SELECT * FROM t FETCH FIRST ROW ONLY"),
+            Ok((Some(1), None))
+        );
+        assert_eq!(limit("SELECT * FROM t OFFSET 2"), Ok((None, Some(2))));
+        assert_eq!(
+            limit("SELECT * FROM t LIMIT -1"),
+            Err(QueryParserError::UnexpectedToken {
+                span: Span { start: 22, end: 23 },
+                expected: "a non negative integer".into(),
+                found: "`-`".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn select_with_aggregates() {
+        let Select {
+            projection,
+            group_by,
+            having,
+            ..
+        } = parse_select(
+            "SELECT country, COUNT(*), count(DISTINCT age) FROM user GROUP BY country HAVING MAX(age) > 30",
+        );
+
+        let function = |name: &str, arg: Expr, distinct| Expr::Function {
+            name: name.into(),
+            args: vec![arg],
+            distinct,
+        };
+
+        assert_eq!(
+            projection,
+            vec![
+                SelectItem::Expr(Expr::Identifier("country".into())),
+                SelectItem::Expr(function("COUNT", Expr::Wildcard, false)),
+                SelectItem::Expr(function("count", Expr::Identifier("age".into()), true)),
+            ]
+        );
+        assert_eq!(group_by, vec![Expr::Identifier("country".into())]);
+        assert_eq!(
+            having,
+            Some(Expr::BinaryOp {
+                left: Box::new(function("MAX", Expr::Identifier("age".into()), false)),
+                op: BinaryOperator::Gt,
+                right: Box::new(Expr::Literal(Literal::Number("30".into()))),
+            })
+        );
+    }
+
+    #[test]
+    fn quoted_identifier_can_be_a_keyword() {
+        assert_eq!(
+            parse_select("SELECT `from` FROM user").projection,
+            vec![SelectItem::Expr(Expr::Identifier("from".into()))]
+        );
+    }
+
+    #[test]
+    fn select_with_joins() {
+        let Select {
+            projection, from, ..
+        } = parse_select(
+            "SELECT u.*, o.total FROM user AS u LEFT OUTER JOIN orders o ON u.id = o.user_id \
+             CROSS JOIN tags, groups JOIN roles ON role = name",
+        );
+        let from = from.unwrap();
+
+        assert_eq!(
+            projection,
+            vec![
+                SelectItem::QualifiedWildcard("u".into()),
+                SelectItem::Expr(Expr::QualifiedIdentifier {
+                    table: "o".into(),
+                    column: "total".into()
+                })
+            ]
+        );
+        assert_eq!(
+            from.relation,
+            TableFactor::Table {
+                name: "user".into(),
+                alias: Some("u".into())
+            }
+        );
+        assert_eq!(
+            from.joins.iter().map(|join| join.kind).collect::<Vec<_>>(),
+            vec![
+                JoinKind::Left,
+                JoinKind::Cross,
+                JoinKind::Cross,
+                JoinKind::Inner
+            ]
+        );
+        assert_eq!(
+            from.to_string(),
+            "user AS u LEFT JOIN orders AS o ON u.id = o.user_id CROSS JOIN tags \
+             CROSS JOIN groups JOIN roles ON role = name"
+        );
+    }
+
+    #[test]
+    fn select_with_set_operations() {
+        let query = match Parser::new(
+            "SELECT DISTINCT a FROM x UNION ALL SELECT b FROM y INTERSECT SELECT c FROM z \
+             EXCEPT SELECT d FROM w ORDER BY a LIMIT 1",
+        )
+        .unwrap()
+        .parse_statement()
+        .unwrap()
+        {
+            Statement::Select(query) => query,
+            _ => panic!("Unexpected statement"),
+        };
+
+        // ((x UNION ALL (y INTERSECT z)) EXCEPT w)
+        let (op, all, left) = match query.body {
+            SetExpr::SetOperation { op, all, left, .. } => (op, all, left),
+            _ => panic!("Expected a set operation"),
+        };
+        assert_eq!((op, all), (SetOperator::Except, false));
+        match *left {
+            SetExpr::SetOperation {
+                op,
+                all,
+                left,
+                right,
+            } => {
+                assert_eq!((op, all), (SetOperator::Union, true));
+                assert!(matches!(*left, SetExpr::Select(select) if select.distinct));
+                assert!(matches!(
+                    *right,
+                    SetExpr::SetOperation {
+                        op: SetOperator::Intersect,
+                        ..
+                    }
+                ));
+            }
+            _ => panic!("Expected a set operation"),
+        }
+        assert_eq!(query.order_by.len(), 1);
+        assert_eq!(query.limit, Some(1));
+    }
+
+    #[test]
+    fn select_with_subqueries() {
+        let Select {
+            projection,
+            from,
+            selection,
+            ..
+        } = parse_select(
+            "SELECT (SELECT MAX(id) FROM y) FROM (SELECT id FROM x) AS t \
+             WHERE id NOT IN (SELECT id FROM z) AND NOT EXISTS (SELECT * FROM w WHERE w.id = t.id)",
+        );
+        let from = from.unwrap();
+
+        assert!(matches!(
+            projection.as_slice(),
+            [SelectItem::Expr(Expr::Subquery(_))]
+        ));
+        assert!(matches!(
+            &from.relation,
+            TableFactor::Derived { alias, .. } if alias == "t"
+        ));
+        assert_eq!(
+            selection.unwrap().to_string(),
+            "id NOT IN (SELECT id FROM z) AND NOT EXISTS (SELECT * FROM w WHERE w.id = t.id)"
+        );
+
+        let statement = Parser::new("SELECT * FROM (SELECT id FROM x)")
+            .unwrap()
+            .parse_statement();
+        assert!(matches!(
+            statement,
+            Err(QueryParserError::UnexpectedToken { expected, .. }) if expected == "an alias for the subquery"
+        ));
+    }
+
+    #[test]
+    fn select_with_common_table_expressions() {
+        let query = "WITH RECURSIVE sub (id) AS (SELECT id FROM x UNION SELECT x.id FROM x JOIN sub ON x.parent = sub.id), \
+                     top AS (SELECT id FROM sub) SELECT id FROM top";
+        let Statement::Select(Query {
+            with: Some(with), ..
+        }) = Parser::new(query).unwrap().parse_statement().unwrap()
+        else {
+            panic!("Unexpected statement");
+        };
+
+        assert!(with.recursive);
+        assert_eq!(
+            with.ctes
+                .iter()
+                .map(|cte| (cte.name.as_str(), cte.columns.clone()))
+                .collect::<Vec<_>>(),
+            vec![("sub", vec!["id".to_string()]), ("top", vec![])]
+        );
+        assert_eq!(
+            with.to_string(),
+            "WITH RECURSIVE sub (id) AS (SELECT id FROM x UNION SELECT x.id FROM x JOIN sub ON x.parent = sub.id), \
+             top AS (SELECT id FROM sub)"
+        );
+    }
+
+    #[test]
+    fn select_with_window_functions() {
+        let Select { projection, .. } = parse_select(
+            "SELECT RANK() OVER (PARTITION BY a, b ORDER BY c DESC), SUM(x) OVER (ROWS 2 PRECEDING), \
+             LAG(x, 1) OVER () FROM t",
+        );
+
+        assert_eq!(
+            projection
+                .iter()
+                .map(SelectItem::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "RANK() OVER (PARTITION BY a, b ORDER BY c DESC)",
+                "SUM(x) OVER (ROWS BETWEEN 2 PRECEDING AND CURRENT ROW)",
+                "LAG(x, 1) OVER ()",
+            ]
+        );
+
+        let error = |query| Parser::new(query).unwrap().parse_statement().unwrap_err();
+        assert_eq!(
+            error("SELECT SUM(x) OVER (ROWS BETWEEN CURRENT ROW AND 1 PRECEDING) FROM t"),
+            QueryParserError::UnexpectedToken {
+                span: Span { start: 49, end: 50 },
+                expected: "a frame end after CURRENT ROW".into(),
+                found: "`1 PRECEDING`".into(),
+            }
+        );
+        assert!(matches!(
+            error("SELECT COUNT(DISTINCT x) OVER () FROM t"),
+            QueryParserError::UnexpectedToken { expected, .. } if expected == "a function without DISTINCT"
+        ));
+    }
+
+    #[test]
+    fn parse_date_expressions() {
+        let parse = |expr: &str| Parser::new(expr).unwrap().parse_expr().unwrap().to_string();
+
+        assert_eq!(
+            parse("DATE '2024-01-31' + INTERVAL '3' day"),
+            "CAST('2024-01-31' AS DATE) + CAST('3 DAY' AS INTERVAL)"
+        );
+        assert_eq!(parse("interval 2 HOURS"), "CAST('2 HOUR' AS INTERVAL)");
+        assert_eq!(parse("EXTRACT(year FROM born)"), "EXTRACT('year', born)");
+        assert_eq!(parse("CURRENT_DATE - born"), "CURRENT_DATE() - born");
+        assert_eq!(parse("date"), "date");
+    }
+
+    #[test]
+    fn parse_json_operators() {
+        let parse = |expr: &str| Parser::new(expr).unwrap().parse_expr().unwrap();
+
+        assert_eq!(
+            parse("-doc->'a'->>0 = 'x'").to_string(),
+            "-doc -> 'a' ->> 0 = 'x'"
+        );
+        assert!(matches!(
+            parse("doc->'a'->>0 || 'x'"),
+            Expr::BinaryOp {
+                op: BinaryOperator::StringConcat,
+                ref left,
+                ..
+            } if matches!(**left, Expr::BinaryOp { op: BinaryOperator::JsonGetText, .. })
+        ));
+
+        let select = parse_select("SELECT e.key FROM f, JSON_EACH(f.doc->'tags') AS e");
+        assert_eq!(
+            select.from.unwrap().joins[0].relation,
+            TableFactor::Function {
+                name: "JSON_EACH".into(),
+                args: vec![parse("f.doc->'tags'")],
+                alias: Some("e".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_blob_literals() {
+        let parse = |expr: &str| Parser::new(expr).unwrap().parse_expr();
+
+        assert_eq!(
+            parse("X'0a1B' || bytea 'ab'").unwrap().to_string(),
+            "CAST('\\\\x0a1B' AS BLOB) || CAST('ab' AS BLOB)"
+        );
+        assert!(matches!(
+            parse("X'0G'"),
+            Err(QueryParserError::DataTypeErr {
+                span: Span { start: 0, end: 5 },
+                ..
+            })
+        ));
+        assert!(parse("X'ABC'").is_err());
+        assert_eq!(
+            parse("B64'Chs=' || b64''").unwrap().to_string(),
+            "CAST('\\\\x0a1b' AS BLOB) || CAST('\\\\x' AS BLOB)"
+        );
+        assert!(matches!(
+            parse("B64'Ch!'"),
+            Err(QueryParserError::DataTypeErr {
+                span: Span { start: 0, end: 8 },
+                source: DataTypesErr::InvalidBlob(_),
+            })
+        ));
+    }
+
+    #[test]
+    fn parse_postfix_predicates() {
+        let parse = |expr: &str| Parser::new(expr).unwrap().parse_expr().unwrap();
+
+        assert_eq!(
+            parse("name NOT LIKE 'a%' ESCAPE '!'"),
+            Expr::Like {
+                expr: Box::new(Expr::Identifier("name".into())),
+                pattern: Box::new(Expr::Literal(Literal::String("a%".into()))),
+                escape: Some(Box::new(Expr::Literal(Literal::String("!".into())))),
+                negated: true,
+                case_insensitive: false,
+            }
+        );
+        assert_eq!(
+            parse("age BETWEEN 1 AND 2 + 3 AND name ILIKE 'x'").to_string(),
+            "age BETWEEN 1 AND 2 + 3 AND name ILIKE 'x'"
+        );
+        assert!(matches!(
+            parse("age BETWEEN 1 AND 2 AND ok"),
+            Expr::BinaryOp { op: BinaryOperator::And, ref left, .. }
+                if matches!(**left, Expr::Between { .. })
+        ));
+        assert_eq!(
+            parse("age NOT IN (1, 2)"),
+            Expr::InList {
+                expr: Box::new(Expr::Identifier("age".into())),
+                list: vec![
+                    Expr::Literal(Literal::Number("1".into())),
+                    Expr::Literal(Literal::Number("2".into())),
+                ],
+                negated: true,
+            }
+        );
+        assert!(matches!(
+            parse("age IN (SELECT 1 FROM t)"),
+            Expr::InSubquery { negated: false, .. }
+        ));
+        assert_eq!(
+            parse("name NOT REGEXP '^a' OR name REGEXP 'b'").to_string(),
+            "NOT name REGEXP '^a' OR name REGEXP 'b'"
+        );
+
+        let err = Parser::new("age BETWEEN 1 OR 2").unwrap().parse_expr();
+        assert!(matches!(err, Err(QueryParserError::UnexpectedToken { .. })));
+    }
+
+    #[test]
+    fn select_with_computed_columns() {
+        let Select { projection, .. } = parse_select(
+            "SELECT price * qty AS total, -price + 1 net, 'a' || name || 'b', \
+             CASE WHEN qty > 1 THEN 'many' ELSE 'one' END AS amount, CASE qty WHEN 1 THEN 'one' END FROM t",
+        );
+
+        assert_eq!(
+            projection[0],
+            SelectItem::ExprWithAlias {
+                expr: Expr::BinaryOp {
+                    left: Box::new(Expr::Identifier("price".into())),
+                    op: BinaryOperator::Multiply,
+                    right: Box::new(Expr::Identifier("qty".into())),
+                },
+                alias: "total".into(),
+            }
+        );
+        assert_eq!(
+            projection
+                .iter()
+                .map(SelectItem::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "price * qty AS total",
+                "-price + 1 AS net",
+                "'a' || name || 'b'",
+                "CASE WHEN qty > 1 THEN 'many' ELSE 'one' END AS amount",
+                "CASE qty WHEN 1 THEN 'one' END",
+            ]
+        );
+
+        // Arithmetic binds tighter than comparisons and concatenation.
+        let expr = Parser::new("a + b * c > d || e")
+            .unwrap()
+            .parse_expr()
+            .unwrap();
+        assert!(matches!(
+            expr,
+            Expr::BinaryOp { op: BinaryOperator::Gt, ref right, .. }
+                if matches!(**right, Expr::BinaryOp { op: BinaryOperator::StringConcat, .. })
+        ));
+        let Expr::BinaryOp { left, .. } = expr else {
+            unreachable!()
+        };
+        assert_eq!(left.to_string(), "a + b * c");
+        assert!(matches!(
+            *left,
+            Expr::BinaryOp { op: BinaryOperator::Plus, ref right, .. }
+                if matches!(**right, Expr::BinaryOp { op: BinaryOperator::Multiply, .. })
+        ));
+
+        let statement = Parser::new("SELECT CASE qty ELSE 1 END FROM t")
+            .unwrap()
+            .parse_statement();
+        assert!(matches!(
+            statement,
+            Err(QueryParserError::UnexpectedToken { expected, .. }) if expected == "`WHEN`"
+        ));
+    }
+
+    #[test]
+    fn trailing_tokens_are_rejected() {
+        let statement = Parser::new("DROP TABLE user user")
+            .unwrap()
+            .parse_statement();
+
+        assert_eq!(
+            statement,
+            Err(QueryParserError::UnexpectedToken {
+                span: Span { start: 16, end: 20 },
+                expected: "end of query".into(),
+                found: "`user`".into(),
+            })
+        );
+    }
+}
@@ -1,10 +1,10 @@
-use regex::Regex;
 use thiserror::Error;
 
 use crate::{
-    regex::*,
+    ast::{self, AlterTableOperation, ColumnDef, Expr, Literal, Statement},
+    lexer::Span,
+    parser::Parser,
     types::{DataType, DataTypesErr},
-    utils::{get_cols, get_comma_separated_values},
 };
 
 pub type ColName = String;
@@ -21,31 +21,46 @@ pub enum TableQuery {
     Create {
         cols: Vec<String>,
         types: Vec<DataType>,
+        not_null: Vec<bool>,
+        defaults: Vec<Option<Expr>>,
     },
     DropTable,
     Truncate,
     AddCol {
         col_name: String,
         datatype: DataType,
+        not_null: bool,
+        default: Option<Expr>,
     },
     AlterCol {
         col_name: String,
         datatype: DataType,
+        not_null: bool,
+        default: Option<Expr>,
     },
     DropCol(ColName),
-    Select {
-        cols: SelectCols,
-        condition: Option<Condition>,
-    },
     Insert {
         cols: SelectCols,
-        values: Vec<Vec<String>>,
+        /// Raw values, `None` is a `NULL`.
+        values: Vec<Vec<Option<String>>>,
+    },
+    Update {
+        assignments: Vec<Assignment>,
+        condition: Option<Expr>,
     },
     Delete {
-        condition: Condition,
+        condition: Expr,
     },
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct Assignment {
+    pub col: ColName,
+    /// Computed from the row being updated, plain values are read like the
+    /// values of an `INSERT`.
+    pub value: Expr,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum SelectCols {
     All,
@@ -57,6 +72,7 @@ pub enum Query {
     ShowAllDBs,
     ShowCurrDB,
     ShowTables,
+    Select(ast::Query),
     Database {
         name: String,
         action: DatabaseAction,
@@ -67,229 +83,270 @@ pub enum Query {
     },
 }
 
+/// Every error points to the part of the query that caused it, see [`QueryParserError::report`].
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum QueryParserError {
-    #[error("Failed to parse the query")]
-    BadQuery(String),
-    #[error("Invalid DB query")]
-    InvalidDBAction(String),
-    #[error("Invalid query")]
-    InvalidTableAction(String),
-    #[error("Invalid condition")]
-    InvalidCondition(String),
-    #[error("Invalid Operator")]
-    InvalidOperator(String),
-    #[error("Data type error")]
-    DataTypeErr(#[from] DataTypesErr),
+    #[error("expected {expected}, found {found}")]
+    UnexpectedToken {
+        span: Span,
+        expected: String,
+        found: String,
+    },
+    #[error("unexpected character `{found}`")]
+    UnexpectedChar { span: Span, found: char },
+    #[error("unterminated string")]
+    UnterminatedString { span: Span },
+    #[error("unterminated comment")]
+    UnterminatedComment { span: Span },
+    #[error("invalid value `{value}`, expected a literal")]
+    InvalidValue { span: Span, value: String },
+    #[error("invalid operator `{op}`")]
+    InvalidOperator { span: Span, op: String },
+    #[error("{source}")]
+    DataTypeErr { span: Span, source: DataTypesErr },
 }
 
-pub struct QueryParser;
-impl QueryParser {
-    pub fn parse(mut query: &str) -> Result<Query, QueryParserError> {
-        query = query.trim();
-        let re_show = Regex::new(RE_SHOW_QUERY).unwrap();
-
-        if let Some(caps) = re_show.captures(query) {
-            return match caps["query"].to_lowercase().as_str() {
-                "databases" => Ok(Query::ShowAllDBs),
-                "current database" => Ok(Query::ShowCurrDB),
-                "tables" => Ok(Query::ShowTables),
-                _ => Err(QueryParserError::BadQuery(query.to_string())),
-            };
+impl QueryParserError {
+    pub fn span(&self) -> Span {
+        match self {
+            QueryParserError::UnexpectedToken { span, .. }
+            | QueryParserError::UnexpectedChar { span, .. }
+            | QueryParserError::UnterminatedString { span }
+            | QueryParserError::UnterminatedComment { span }
+            | QueryParserError::InvalidValue { span, .. }
+            | QueryParserError::InvalidOperator { span, .. }
+            | QueryParserError::DataTypeErr { span, .. } => *span,
         }
+    }
 
-        let re_db = Regex::new(RE_DB).unwrap();
-        if let Some(caps) = re_db.captures(query) {
-            let name = caps["name"].to_string();
-            let action = &caps["action"];
-
-            let action = match action.to_lowercase().as_str() {
-                "create" => DatabaseAction::Create,
-                "drop" => DatabaseAction::Drop,
-                "use" => DatabaseAction::Use,
-                _ => return Err(QueryParserError::InvalidDBAction(action.to_string())),
-            };
-
-            return Ok(Query::Database { name, action });
-        }
-
-        let re_create_table = Regex::new(RE_CREATE_TABLE).unwrap();
-        if let Some(caps) = re_create_table.captures(query) {
-            let table_name = caps["name"].to_string();
-            let re_entries = Regex::new(RE_TABLE_ENTRIES).unwrap();
-            let mut types = Vec::new();
-            let mut cols = Vec::new();
-            for caps in re_entries.captures_iter(&caps["entries"]) {
-                types.push(DataType::parse(&caps["col_type"])?);
-                cols.push(caps["col_name"].to_string())
-            }
-
-            return Ok(Query::Table {
-                name: table_name,
-                query: TableQuery::Create { cols, types },
-            });
-        }
+    /// Render the error with the offending line of the query and a caret
+    /// under the failing token, e.g.
+    ///
+    /// ```text
+    /// error: expected `FROM`, found `WHERE`
+    ///  --> 1:11
+    ///   |
+    /// 1 | SELECT id WHERE age > 12
+    ///   |           ^^^^^
+    /// ```
+    pub fn report(&self, query: &str) -> String {
+        let span = self.span();
+        let (line_no, col) = span.line_col(query);
+        let line = query.lines().nth(line_no - 1).unwrap_or_default();
+
+        let line_start = query[..span.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line_end = line_start + line.len();
+        // Keep tabs so the caret lines up with the query as it was typed.
+        let padding = query[line_start..span.start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        let width = query[span.start..span.end.clamp(span.start, line_end)]
+            .chars()
+            .count()
+            .max(1);
+        let gutter = " ".repeat(line_no.to_string().len());
+
+        format!(
+            "error: {}\n{gutter}--> {}:{}\n{gutter} |\n{} | {}\n{gutter} | {}{}",
+            self,
+            line_no,
+            col,
+            line_no,
+            line,
+            padding,
+            "^".repeat(width),
+            gutter = gutter,
+        )
+    }
+}
 
-        let re_table = Regex::new(RE_TABLE).unwrap();
+pub struct QueryParser;
+impl QueryParser {
+    pub fn parse(query: &str) -> Result<Query, QueryParserError> {
+        let statement = Parser::new(query)?.parse_statement()?;
+        let span = Span {
+            start: query.len() - query.trim_start().len(),
+            end: query.trim_end().len(),
+        };
+
+        Query::from_statement(statement, span)
+    }
+}
 
-        if let Some(caps) = re_table.captures(query) {
-            let table_name = caps["name"].to_string();
-            match caps["action"].to_lowercase().as_str() {
-                "drop" => {
-                    return Ok(Query::Table {
-                        name: table_name,
-                        query: TableQuery::DropTable,
-                    })
-                }
-                "truncate" => {
-                    return Ok(Query::Table {
-                        name: table_name,
-                        query: TableQuery::Truncate,
-                    })
+impl Query {
+    /// Lower the syntax tree into the query shape understood by the planner,
+    /// errors found at this stage point to the whole statement `span`.
+    fn from_statement(statement: Statement, span: Span) -> Result<Self, QueryParserError> {
+        let table = |name: String, query: TableQuery| Query::Table { name, query };
+
+        let query = match statement {
+            Statement::ShowDatabases => Query::ShowAllDBs,
+            Statement::ShowCurrentDatabase => Query::ShowCurrDB,
+            Statement::ShowTables => Query::ShowTables,
+            Statement::CreateDatabase(name) => Query::Database {
+                name,
+                action: DatabaseAction::Create,
+            },
+            Statement::DropDatabase(name) => Query::Database {
+                name,
+                action: DatabaseAction::Drop,
+            },
+            Statement::UseDatabase(name) => Query::Database {
+                name,
+                action: DatabaseAction::Use,
+            },
+            Statement::CreateTable { name, columns } => {
+                let mut cols = Vec::new();
+                let mut types = Vec::new();
+                let mut not_null = Vec::new();
+                let mut defaults = Vec::new();
+                for column in columns {
+                    cols.push(column.name);
+                    types.push(column.datatype);
+                    not_null.push(column.not_null);
+                    defaults.push(column.default);
                 }
-                _ => {
-                    return Err(QueryParserError::InvalidTableAction(
-                        caps["action"].to_string(),
-                    ))
-                }
-            };
-        }
 
-        let re_drop_col = Regex::new(RE_DROP_COL).unwrap();
-        if let Some(caps) = re_drop_col.captures(query) {
-            return Ok(Query::Table {
-                name: caps["table_name"].to_string(),
-                query: TableQuery::DropCol(caps["col_name"].to_string()),
-            });
-        }
-
-        let re_alter_col = Regex::new(RE_ALTER_COL).unwrap();
-        if let Some(caps) = re_alter_col.captures(query) {
-            return Ok(Query::Table {
-                name: caps["table_name"].to_string(),
-                query: TableQuery::AlterCol {
-                    col_name: caps["col_name"].to_string(),
-                    datatype: DataType::parse(&caps["datatype"])?,
-                },
-            });
-        }
-
-        let re_add_col = Regex::new(RE_ADD_COL).unwrap();
-        if let Some(caps) = re_add_col.captures(query) {
-            return Ok(Query::Table {
-                name: caps["table_name"].to_string(),
-                query: TableQuery::AddCol {
-                    col_name: caps["col_name"].to_string(),
-                    datatype: DataType::parse(&caps["datatype"])?,
-                },
-            });
-        }
-
-        let re_select = Regex::new(RE_SELECT).unwrap();
-        if let Some(caps) = re_select.captures(query) {
-            let condition = caps.name("condition").map(|_| &caps["condition"]);
+                table(
+                    name,
+                    TableQuery::Create {
+                        cols,
+                        types,
+                        not_null,
+                        defaults,
+                    },
+                )
+            }
+            Statement::DropTable(name) => table(name, TableQuery::DropTable),
+            Statement::TruncateTable(name) => table(name, TableQuery::Truncate),
+            Statement::AlterTable { name, operation } => {
+                let query = match operation {
+                    AlterTableOperation::AddColumn(ColumnDef {
+                        name,
+                        datatype,
+                        not_null,
+                        default,
+                    }) => TableQuery::AddCol {
+                        col_name: name,
+                        datatype,
+                        not_null,
+                        default,
+                    },
+                    AlterTableOperation::AlterColumn(ColumnDef {
+                        name,
+                        datatype,
+                        not_null,
+                        default,
+                    }) => TableQuery::AlterCol {
+                        col_name: name,
+                        datatype,
+                        not_null,
+                        default,
+                    },
+                    AlterTableOperation::DropColumn(col) => TableQuery::DropCol(col),
+                };
+                table(name, query)
+            }
+            Statement::Select(query) => Query::Select(query),
+            Statement::Insert {
+                table: name,
+                columns,
+                rows,
+            } => {
+                let cols = match columns {
+                    Some(cols) => SelectCols::Cols(cols),
+                    None => SelectCols::All,
+                };
+                let values = rows
+                    .into_iter()
+                    .map(|row| row.into_iter().map(|v| raw_value(v, span)).collect())
+                    .collect::<Result<_, _>>()?;
 
-            return Ok(Query::Table {
-                name: caps["table_name"].to_string(),
-                query: TableQuery::Select {
-                    condition: match condition {
-                        None => None,
-                        Some(c) => Some(Condition::parse(c)?),
+                table(name, TableQuery::Insert { cols, values })
+            }
+            Statement::Update {
+                table: name,
+                assignments,
+                selection,
+            } => {
+                let assignments = assignments
+                    .into_iter()
+                    .map(|(col, value)| Assignment { col, value })
+                    .collect();
+                table(
+                    name,
+                    TableQuery::Update {
+                        assignments,
+                        condition: selection,
                     },
-                    cols: get_cols(&caps["cols"]),
+                )
+            }
+            Statement::Delete {
+                table: name,
+                selection,
+            } => table(
+                name,
+                TableQuery::Delete {
+                    condition: selection,
                 },
-            });
-        }
-
-        let re_insert = Regex::new(RE_INSERT).unwrap();
-        if let Some(caps) = re_insert.captures(query) {
-            let cols = match caps.name("cols") {
-                Some(_) => SelectCols::Cols(get_comma_separated_values(&caps["cols"])),
-                None => SelectCols::All,
-            };
-
-            let re_values = Regex::new(RE_INSERT_VALUES_VALUES).unwrap();
-            let values = re_values
-                .captures_iter(&caps["values"])
-                .map(|caps| get_comma_separated_values(&caps["row"]))
-                .collect::<Vec<Vec<_>>>();
-
-            return Ok(Query::Table {
-                name: caps["table_name"].to_string(),
-                query: TableQuery::Insert { cols, values },
-            });
-        }
-
-        let re_delete = Regex::new(RE_DELETE_FROM_TABLE).unwrap();
-        if let Some(caps) = re_delete.captures(query) {
-            let condition = Condition::parse(&caps["condition"])?;
-            return Ok(Query::Table {
-                name: caps["table_name"].to_string(),
-                query: TableQuery::Delete { condition },
-            });
-        }
+            ),
+        };
 
-        Err(QueryParserError::BadQuery(query.to_string()))
+        Ok(query)
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum Operator {
-    Eq,
-    NotEq,
-    Gt,
-    Lt,
-    GtEq,
-    LtEq,
-}
-
-#[derive(Debug, PartialEq, Eq)]
-pub struct Condition {
-    pub key: String,
-    pub value: String,
-    pub operator: Operator,
-}
-
-impl Condition {
-    fn parse(query: &str) -> Result<Condition, QueryParserError> {
-        let re = Regex::new(RE_KEY_VALUE).unwrap();
-
-        match re.captures(query) {
-            Some(caps) => {
-                let operator = match &caps["operator"] {
-                    "=" => Operator::Eq,
-                    "!=" => Operator::NotEq,
-                    ">" => Operator::Gt,
-                    ">=" => Operator::GtEq,
-                    "<" => Operator::Lt,
-                    "<=" => Operator::LtEq,
-                    _ => {
-                        return Err(QueryParserError::InvalidOperator(
-                            caps["operator"].to_string(),
-                        ))
-                    }
-                };
-
-                Ok(Condition {
-                    key: caps["key"].to_string(),
-                    value: caps["value"].to_string(),
-                    operator,
-                })
-            }
-            None => Err(QueryParserError::InvalidCondition(query.to_string())),
+/// Bare words are accepted as values, so `name = jone` is the same as `name = 'jone'`.
+/// `NULL` is the only value without a raw form.
+fn raw_value(expr: Expr, span: Span) -> Result<Option<String>, QueryParserError> {
+    match expr {
+        Expr::Identifier(value)
+        | Expr::Literal(Literal::Number(value))
+        | Expr::Literal(Literal::String(value)) => Ok(Some(value)),
+        Expr::Literal(Literal::Boolean(value)) => Ok(Some(value.to_string())),
+        Expr::Literal(Literal::Null) => Ok(None),
+        Expr::Nested(expr) => raw_value(*expr, span),
+        // Typed literals like `X'0A1B'` or `DATE '2024-01-31'` are read as
+        // the type of the column, like any other value.
+        Expr::Cast { expr, .. } if matches!(*expr, Expr::Literal(Literal::String(_))) => {
+            raw_value(*expr, span)
         }
+        expr => Err(QueryParserError::InvalidValue {
+            span,
+            value: expr.to_string(),
+        }),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        query_parser::{Condition, DatabaseAction, Operator, Query, SelectCols, TableQuery},
+        ast::{self, BinaryOperator, Expr, Literal, Select, SelectItem, SetExpr},
+        lexer::Span,
+        parser::Parser,
+        query_parser::{Assignment, DatabaseAction, Query, SelectCols, TableQuery},
         types::DataType,
     };
 
     use super::{QueryParser, QueryParserError};
 
+    fn parse_condition(query: &str) -> Result<Expr, QueryParserError> {
+        Parser::new(query)?.parse_expr()
+    }
+
+    fn compare(key: &str, op: BinaryOperator, value: Expr) -> Expr {
+        Expr::BinaryOp {
+            left: Box::new(Expr::Identifier(key.into())),
+            op,
+            right: Box::new(value),
+        }
+    }
+
+    fn number(value: &str) -> Expr {
+        Expr::Literal(Literal::Number(value.into()))
+    }
+
     #[test]
     fn create_database() {
         let all_caps = QueryParser::parse("CREATE DATABASE demo").unwrap();
@@ -343,7 +400,7 @@ mod tests {
         let query = QueryParser::parse("CREATE TABLE user(id int, name varchar, age int)").unwrap();
         if let Query::Table {
             name,
-            query: TableQuery::Create { cols, types },
+            query: TableQuery::Create { cols, types, .. },
         } = query
         {
             assert_eq!(name, "user".to_string());
@@ -372,7 +429,7 @@ mod tests {
         .unwrap();
         if let Query::Table {
             name,
-            query: TableQuery::Create { cols, types },
+            query: TableQuery::Create { cols, types, .. },
         } = query
         {
             assert_eq!(name, "blog".to_string());
@@ -389,6 +446,65 @@ mod tests {
         }
     }
 
+    #[test]
+    fn create_table_with_not_null_columns() {
+        let query = QueryParser::parse(
+            "CREATE TABLE user (id INT NOT NULL, name VARCHAR(12) NULL, age INT)",
+        )
+        .unwrap();
+        if let Query::Table {
+            query: TableQuery::Create {
+                types, not_null, ..
+            },
+            ..
+        } = query
+        {
+            assert_eq!(
+                types,
+                vec![DataType::INT, DataType::VARCHAR(12), DataType::INT]
+            );
+            assert_eq!(not_null, vec![true, false, false]);
+        } else {
+            panic!("Unexpected query");
+        }
+    }
+
+    #[test]
+    fn create_table_with_column_defaults() {
+        let query = QueryParser::parse(
+            "CREATE TABLE user (id UUID DEFAULT gen_random_uuid() NOT NULL, age INT NULL DEFAULT 1 + 1, name TEXT)",
+        )
+        .unwrap();
+        if let Query::Table {
+            query:
+                TableQuery::Create {
+                    types,
+                    not_null,
+                    defaults,
+                    ..
+                },
+            ..
+        } = query
+        {
+            assert_eq!(types, vec![DataType::UUID, DataType::INT, DataType::TEXT]);
+            assert_eq!(not_null, vec![true, false, false]);
+            let defaults = defaults
+                .iter()
+                .map(|d| d.as_ref().map(Expr::to_string))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                defaults,
+                vec![
+                    Some("gen_random_uuid()".to_string()),
+                    Some("1 + 1".to_string()),
+                    None
+                ]
+            );
+        } else {
+            panic!("Unexpected query");
+        }
+    }
+
     #[test]
     fn drop_table() {
         let query = QueryParser::parse(r#"DROP TABLE demo"#).unwrap();
@@ -439,7 +555,9 @@ mod tests {
 
         if let Query::Table {
             name,
-            query: TableQuery::AlterCol { col_name, datatype },
+            query: TableQuery::AlterCol {
+                col_name, datatype, ..
+            },
         } = query
         {
             assert_eq!(name, "demo".to_string());
@@ -456,7 +574,9 @@ mod tests {
 
         if let Query::Table {
             name,
-            query: TableQuery::AddCol { col_name, datatype },
+            query: TableQuery::AddCol {
+                col_name, datatype, ..
+            },
         } = query
         {
             assert_eq!(name, "demo".to_string());
@@ -484,25 +604,40 @@ mod tests {
     //     }
     // }
 
+    /// The single `SELECT` of a query, if it is one.
+    fn select(query: Query) -> Option<Select> {
+        match query {
+            Query::Select(ast::Query {
+                body: SetExpr::Select(select),
+                ..
+            }) => Some(*select),
+            _ => None,
+        }
+    }
+
+    fn columns(cols: &[&str]) -> Vec<SelectItem> {
+        cols.iter()
+            .map(|col| SelectItem::Expr(Expr::Identifier(col.to_string())))
+            .collect()
+    }
+
     #[test]
     fn parse_select_statment_with_condition() {
         let query = QueryParser::parse("SELECT id,name FROM user WHERE age >= 12").unwrap();
 
-        if let Query::Table {
-            name,
-            query: TableQuery::Select { cols, condition },
-        } = query
+        if let Some(Select {
+            projection,
+            from,
+            selection,
+            ..
+        }) = select(query)
         {
-            assert_eq!(name, "user".to_string());
-            assert_eq!(cols, SelectCols::Cols(vec!["id".into(), "name".into()]));
-            assert!(condition.is_some());
+            assert_eq!(from.unwrap().to_string(), "user");
+            assert_eq!(projection, columns(&["id", "name"]));
+            assert!(selection.is_some());
             assert_eq!(
-                condition.unwrap(),
-                Condition {
-                    key: "age".into(),
-                    value: "12".into(),
-                    operator: Operator::GtEq
-                }
+                selection.unwrap(),
+                compare("age", BinaryOperator::GtEq, number("12"))
             );
         } else {
             panic!("Unexpected query")
@@ -513,21 +648,19 @@ mod tests {
     fn parse_select_statment_with_all_cols_and_condition() {
         let query = QueryParser::parse("SELECT * FROM user WHERE age=12").unwrap();
 
-        if let Query::Table {
-            name,
-            query: TableQuery::Select { cols, condition },
-        } = query
+        if let Some(Select {
+            projection,
+            from,
+            selection,
+            ..
+        }) = select(query)
         {
-            assert_eq!(name, "user".to_string());
-            assert_eq!(cols, SelectCols::All);
-            assert!(condition.is_some());
+            assert_eq!(from.unwrap().to_string(), "user");
+            assert_eq!(projection, vec![SelectItem::Wildcard]);
+            assert!(selection.is_some());
             assert_eq!(
-                condition.unwrap(),
-                Condition {
-                    key: "age".into(),
-                    value: "12".into(),
-                    operator: Operator::Eq
-                }
+                selection.unwrap(),
+                compare("age", BinaryOperator::Eq, number("12"))
             );
         } else {
             panic!("Unexpected query")
@@ -538,14 +671,16 @@ mod tests {
     fn parse_select_statment() {
         let query = QueryParser::parse("SELECT id,name FROM user").unwrap();
 
-        if let Query::Table {
-            name,
-            query: TableQuery::Select { cols, condition },
-        } = query
+        if let Some(Select {
+            projection,
+            from,
+            selection,
+            ..
+        }) = select(query)
         {
-            assert_eq!(name, "user".to_string());
-            assert_eq!(cols, SelectCols::Cols(vec!["id".into(), "name".into()]));
-            assert!(condition.is_none());
+            assert_eq!(from.unwrap().to_string(), "user");
+            assert_eq!(projection, columns(&["id", "name"]));
+            assert!(selection.is_none());
         } else {
             panic!("Unexpected query")
         }
@@ -564,7 +699,7 @@ mod tests {
             assert_eq!(cols, SelectCols::All);
             assert_eq!(
                 values,
-                vec![vec!["value1".to_string(), "value2".to_string()]]
+                vec![vec![Some("value1".to_string()), Some("value2".to_string())]]
             );
         } else {
             panic!("Unexpected query")
@@ -580,9 +715,9 @@ mod tests {
         .unwrap();
 
         let expected_values = vec![
-            vec!["val1".to_string(), "val2".to_string()],
-            vec!["val3".to_string(), "val4".to_string()],
-            vec!["val5".to_string(), "val6".to_string()],
+            vec![Some("val1".to_string()), Some("val2".to_string())],
+            vec![Some("val3".to_string()), Some("val4".to_string())],
+            vec![Some("val5".to_string()), Some("val6".to_string())],
         ];
 
         if let Query::Table {
@@ -607,9 +742,9 @@ mod tests {
         .unwrap();
 
         let expected_values = vec![
-            vec!["1".to_string(), "2".to_string()],
-            vec!["3".to_string(), "4".to_string()],
-            vec!["5".to_string(), "6".to_string()],
+            vec![Some("1".to_string()), Some("2".to_string())],
+            vec![Some("3".to_string()), Some("4".to_string())],
+            vec![Some("5".to_string()), Some("6".to_string())],
         ];
 
         if let Query::Table {
@@ -628,6 +763,63 @@ mod tests {
         }
     }
 
+    #[test]
+    fn insert_quoted_values() {
+        let query = QueryParser::parse(
+            r#"INSERT INTO user VALUES ('John Doe', 'Cairo, EG'), ("O'Brien", 'it''s \'ok\'')"#,
+        )
+        .unwrap();
+
+        let expected_values = vec![
+            vec![Some("John Doe".to_string()), Some("Cairo, EG".to_string())],
+            vec![Some("O'Brien".to_string()), Some("it's 'ok'".to_string())],
+        ];
+        assert_eq!(
+            query,
+            Query::Table {
+                name: "user".into(),
+                query: TableQuery::Insert {
+                    cols: SelectCols::All,
+                    values: expected_values,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn insert_null_value() {
+        let query = QueryParser::parse("INSERT INTO user VALUES (NULL, 'NULL', null)").unwrap();
+
+        if let Query::Table {
+            query: TableQuery::Insert { values, .. },
+            ..
+        } = query
+        {
+            assert_eq!(values, vec![vec![None, Some("NULL".to_string()), None]]);
+        } else {
+            panic!("Unexpected query")
+        }
+    }
+
+    #[test]
+    fn insert_typed_literals() {
+        let query =
+            QueryParser::parse("INSERT INTO file VALUES (X'FF00', DATE '2024-01-31')").unwrap();
+
+        if let Query::Table {
+            query: TableQuery::Insert { values, .. },
+            ..
+        } = query
+        {
+            assert_eq!(
+                values,
+                vec![vec![Some("\\xFF00".into()), Some("2024-01-31".into())]]
+            );
+        } else {
+            panic!("Unexpected query")
+        }
+    }
+
     #[test]
     fn delete_from_table() {
         let query = QueryParser::parse(
@@ -644,17 +836,94 @@ mod tests {
             assert_eq!(name, "table_name".to_string());
             assert_eq!(
                 condition,
-                Condition {
-                    key: "name".into(),
-                    value: "jone".into(),
-                    operator: Operator::Eq
-                }
+                compare("name", BinaryOperator::Eq, Expr::Identifier("jone".into()))
             );
         } else {
             panic!("Unexpected query")
         }
     }
 
+    #[test]
+    fn update_table() {
+        let query = QueryParser::parse(
+            "UPDATE user
+            SET name = jone, age = 21
+            WHERE id = 1;",
+        )
+        .unwrap();
+
+        if let Query::Table {
+            name,
+            query:
+                TableQuery::Update {
+                    assignments,
+                    condition,
+                },
+        } = query
+        {
+            assert_eq!(name, "user".to_string());
+            assert_eq!(
+                assignments,
+                vec![
+                    Assignment {
+                        col: "name".into(),
+                        value: Expr::Identifier("jone".into())
+                    },
+                    Assignment {
+                        col: "age".into(),
+                        value: number("21")
+                    }
+                ]
+            );
+            assert_eq!(
+                condition,
+                Some(compare("id", BinaryOperator::Eq, number("1")))
+            );
+        } else {
+            panic!("Unexpected query")
+        }
+    }
+
+    #[test]
+    fn update_table_without_condition() {
+        let query = QueryParser::parse("UPDATE user SET is_married = 'true'").unwrap();
+
+        if let Query::Table {
+            name,
+            query:
+                TableQuery::Update {
+                    assignments,
+                    condition,
+                },
+        } = query
+        {
+            assert_eq!(name, "user".to_string());
+            assert_eq!(
+                assignments,
+                vec![Assignment {
+                    col: "is_married".into(),
+                    value: Expr::Literal(Literal::String("true".into()))
+                }]
+            );
+            assert!(condition.is_none());
+        } else {
+            panic!("Unexpected query")
+        }
+    }
+
+    #[test]
+    fn update_with_invalid_assignment() {
+        let query = QueryParser::parse("UPDATE user SET name jone");
+        assert_eq!(
+            query,
+            Err(QueryParserError::UnexpectedToken {
+                span: Span { start: 21, end: 25 },
+                expected: "`=`".into(),
+                found: "`jone`".into(),
+            })
+        );
+    }
+
     #[test]
     fn show_queries() {
         let show_dbs = QueryParser::parse("SHOW DATABASES").unwrap();
@@ -668,34 +937,65 @@ mod tests {
 
     #[test]
     fn parse_eq_condition() {
-        let con = Condition::parse("name = jone").unwrap();
+        let con = parse_condition("name = jone").unwrap();
 
         assert_eq!(
             con,
-            Condition {
-                key: "name".into(),
-                value: "jone".into(),
-                operator: Operator::Eq
-            }
+            compare("name", BinaryOperator::Eq, Expr::Identifier("jone".into()))
         )
     }
 
     #[test]
     fn parse_less_than_or_equal_condition() {
-        let con = Condition::parse("age <= 21").unwrap();
+        let con = parse_condition("age <= 21").unwrap();
+        assert_eq!(con, compare("age", BinaryOperator::LtEq, number("21")))
+    }
+
+    #[test]
+    fn parse_invalid_condition() {
+        let con = parse_condition("age !! 21");
         assert_eq!(
             con,
-            Condition {
-                key: "age".into(),
-                value: "21".into(),
-                operator: Operator::LtEq
-            }
-        )
+            Err(QueryParserError::InvalidOperator {
+                span: Span { start: 4, end: 6 },
+                op: "!!".into()
+            })
+        );
     }
 
     #[test]
-    fn parse_invalid_condition() {
-        let con = Condition::parse("age !! 21");
-        assert_eq!(con, Err(QueryParserError::InvalidOperator("!!".into())));
+    fn report_points_to_the_failing_token() {
+        let query = "SELECT * WHERE age > 12";
+        let err = QueryParser::parse(query).unwrap_err();
+
+        assert_eq!(
+            err.report(query),
+            [
+                "error: expected `FROM`, found `WHERE`",
+                " --> 1:10",
+                "  |",
+                "1 | SELECT * WHERE age > 12",
+                "  |          ^^^^^",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn report_multi_line_query() {
+        let query = "CREATE TABLE user (\n  id INT,\n  age NUMBER\n)";
+        let err = QueryParser::parse(query).unwrap_err();
+
+        assert_eq!(
+            err.report(query),
+            [
+                "error: Invalid type `NUMBER`",
+                " --> 3:7",
+                "  |",
+                "3 |   age NUMBER",
+                "  |       ^^^^^^",
+            ]
+            .join("\n")
+        );
     }
 }
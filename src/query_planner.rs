@@ -1,5 +1,6 @@
 use crate::{
     database::{Database, DatabaseError},
+    executor::{Executor, ExecutorError},
     query_parser::{DatabaseAction, Query, QueryParser, QueryParserError, TableQuery},
     table::{Table, TableError},
     utils::display_entries,
@@ -7,6 +8,7 @@ use crate::{
 use inquire::{validator::Validation, InquireError, Text};
 use thiserror::Error;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Error)]
 pub enum QueryPlannerError {
     #[error("Unabel to read from stdin")]
@@ -17,10 +19,13 @@ pub enum QueryPlannerError {
     DatabaseError(#[from] DatabaseError),
     #[error("Table Error")]
     TableError(#[from] TableError),
+    #[error("Executor Error")]
+    ExecutorError(#[from] ExecutorError),
 }
 
 pub struct QueryPlanner;
 impl QueryPlanner {
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> Result<(), QueryPlannerError> {
         let keywords = include_str!("../mysql5.0_keywords.txt")
             .split("\n")
@@ -47,7 +52,7 @@ impl QueryPlanner {
                 .filter(|keyword| keyword.starts_with(&q.to_uppercase()))
                 .take(4)
                 .map(|k| {
-                    let mut as_string = (&input_tokens[0..num_of_tokens - 1]).to_vec().join(" ");
+                    let mut as_string = input_tokens[0..num_of_tokens - 1].to_vec().join(" ");
                     as_string.push(' ');
                     as_string.push_str(&k);
 
@@ -74,14 +79,17 @@ impl QueryPlanner {
                 continue;
             }
 
-            if let Err(e) = QueryPlanner::execute_query(&query.unwrap()) {
-                eprintln!("{:?}", e);
+            let query = query.unwrap();
+            match QueryPlanner::execute_query(&query) {
+                Err(QueryPlannerError::QueryError(e)) => eprintln!("{}", e.report(&query)),
+                Err(e) => eprintln!("{:?}", e),
+                Ok(_) => {}
             }
         }
     }
 
     fn execute_query(raw_query: &str) -> Result<(), QueryPlannerError> {
-        let query = QueryParser::parse(raw_query.trim())?;
+        let query = QueryParser::parse(raw_query)?;
         match query {
             Query::Database { name, action } => match action {
                 DatabaseAction::Create => Database::new(&name)?,
@@ -92,29 +100,48 @@ impl QueryPlanner {
                 let curr_db = Database::get_curr_db()?;
                 let table = Table::new(&curr_db, &name)?;
                 match query {
-                    TableQuery::Create { cols, types } => table.create(cols, types)?,
+                    TableQuery::Create {
+                        cols,
+                        types,
+                        not_null,
+                        defaults,
+                    } => table.create(cols, types, not_null, defaults)?,
                     TableQuery::DropTable => table.drop()?,
                     TableQuery::Truncate => table.truncate()?,
                     TableQuery::DropCol(col) => table.remove_col(&col)?,
-                    TableQuery::AlterCol { col_name, datatype } => {
-                        table.alter(&col_name, datatype)?
-                    }
-                    TableQuery::AddCol { col_name, datatype } => {
-                        table.add_col(&col_name, datatype)?
-                    }
-                    TableQuery::Select { cols, condition } => {
-                        let entries = table.select(cols, condition)?;
-
-                        if entries.is_empty() {
-                            println!("\tNo entries")
-                        } else {
-                            display_entries(entries);
-                        }
-                    }
+                    TableQuery::AlterCol {
+                        col_name,
+                        datatype,
+                        not_null,
+                        default,
+                    } => table.alter(&col_name, datatype, not_null, default)?,
+                    TableQuery::AddCol {
+                        col_name,
+                        datatype,
+                        not_null,
+                        default,
+                    } => table.add_col(&col_name, datatype, not_null, default)?,
                     TableQuery::Insert { cols, values } => table.insert(cols, values)?,
+                    TableQuery::Update {
+                        assignments,
+                        condition,
+                    } => {
+                        let updated = table.update(assignments, condition)?;
+                        println!("[{}@{}] {} entries updated", name, curr_db, updated);
+                    }
                     TableQuery::Delete { condition } => table.delete(condition)?,
                 }
             }
+            Query::Select(query) => {
+                let curr_db = Database::get_curr_db()?;
+                let result = Executor::new(&curr_db).query(&query)?;
+
+                if result.entries.is_empty() {
+                    println!("\tNo entries")
+                } else {
+                    display_entries(result);
+                }
+            }
             Query::ShowAllDBs => Database::get_dbs()?.iter().for_each(|db| {
                 println!("{}", db);
             }),
@@ -1,16 +1,26 @@
-use serde::{Deserialize, Serialize};
+use serde::{de::Error as _, Deserialize, Serialize};
 use serde_json::json;
-use std::{collections::HashMap, fs, io};
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+};
 use thiserror::Error;
 
 use crate::{
+    ast::{Expr, Literal},
     database::{Database, DatabaseError},
-    query_parser::{Condition, Operator, SelectCols},
+    eval::{Column, EvalError, Scope},
+    executor::Executor,
+    parser::Parser,
+    query_parser::{Assignment, SelectCols},
     types::{DataType, DataTypesErr},
     utils::{get_db_path, get_schema_path, get_table_path},
+    value::Value,
 };
 
-pub type TableEntries = Vec<HashMap<String, String>>;
+/// A single entry, values are in the same order as the schema columns.
+pub type Row = Vec<Value>;
+pub type TableEntries = Vec<Row>;
 
 pub struct Table<'a> {
     pub db: &'a str,
@@ -37,9 +47,15 @@ pub enum TableError {
     TypeErr(#[from] DataTypesErr),
     #[error("Column already exist")]
     ColAlreadyExist(String),
+    #[error("Column can't be null")]
+    NotNull(String),
+    #[error("Invalid default value")]
+    InvalidDefault(String),
+    #[error("Evaluation error")]
+    EvalErr(#[from] EvalError),
 }
 
-type TableResult<T> = Result<T, TableError>;
+pub type TableResult<T> = Result<T, TableError>;
 
 impl<'a> Table<'a> {
     pub fn new(db: &'a str, table_name: &'a str) -> TableResult<Self> {
@@ -47,8 +63,29 @@ impl<'a> Table<'a> {
         Ok(Self { db, table_name })
     }
 
-    pub fn create(&self, cols: Vec<String>, types: Vec<DataType>) -> TableResult<()> {
-        let schema = json!({ "cols": cols, "types": types });
+    pub fn create(
+        &self,
+        cols: Vec<String>,
+        types: Vec<DataType>,
+        not_null: Vec<bool>,
+        defaults: Vec<Option<Expr>>,
+    ) -> TableResult<()> {
+        let scope = Scope::new(&[]);
+        for (dtype, default) in types.iter().zip(&defaults) {
+            if let Some(expr) = default {
+                default_value(&scope, expr, dtype)?;
+            }
+        }
+        let defaults = defaults
+            .iter()
+            .map(|default| default.as_ref().map(Expr::to_string))
+            .collect::<Vec<_>>();
+        let schema = json!({
+            "cols": cols,
+            "types": types,
+            "not_null": not_null,
+            "defaults": defaults,
+        });
         let schema = serde_json::to_string_pretty(&schema)?;
 
         Database::exists_or_err(self.db)?;
@@ -61,7 +98,7 @@ impl<'a> Table<'a> {
         Ok(())
     }
 
-    pub fn insert(&self, cols: SelectCols, values: Vec<Vec<String>>) -> TableResult<()> {
+    pub fn insert(&self, cols: SelectCols, values: Vec<Vec<Option<String>>>) -> TableResult<()> {
         Database::exists_or_err(self.db)?;
 
         let schema = self.read_schema()?;
@@ -70,7 +107,7 @@ impl<'a> Table<'a> {
             SelectCols::All => schema.cols.clone(),
         };
 
-        let mut col_type_map = HashMap::new();
+        let mut col_types = Vec::new();
         for col in &cols {
             let col_pos = match schema.cols.iter().position(|c| c == col) {
                 Some(pos) => pos,
@@ -82,9 +119,20 @@ impl<'a> Table<'a> {
                 None => return Err(TableError::ColTypeNotFound(col.to_string())),
             };
 
-            col_type_map.insert(col, dtype);
+            col_types.push((col_pos, dtype));
         }
 
+        // Columns missing from the query take their default, computed again
+        // for every row, or are left empty.
+        let defaults = schema.default_exprs()?;
+        let defaults = defaults
+            .iter()
+            .enumerate()
+            .filter(|(pos, _)| !col_types.iter().any(|(col_pos, _)| col_pos == pos))
+            .filter_map(|(pos, default)| Some((pos, default.as_ref()?)))
+            .collect::<Vec<_>>();
+        let scope = Scope::new(&[]);
+
         let mut new_entries = Vec::new();
         for (idx, row) in values.iter().enumerate() {
             if row.len() != cols.len() {
@@ -97,16 +145,21 @@ impl<'a> Table<'a> {
                 )));
             }
 
-            let mut map = HashMap::new();
-            for (col, val) in cols.iter().zip(row) {
-                col_type_map[col].is_valid(val)?;
-                map.insert(col.clone(), val.clone());
+            let mut entry = vec![Value::Null; schema.cols.len()];
+            for (pos, expr) in &defaults {
+                entry[*pos] = default_value(&scope, expr, &schema.types[*pos])?;
             }
+            for ((col_pos, dtype), val) in col_types.iter().zip(row) {
+                if let Some(val) = val {
+                    entry[*col_pos] = dtype.parse_value(val)?;
+                }
+            }
+            schema.check_not_null(&entry)?;
 
-            new_entries.push(map);
+            new_entries.push(entry);
         }
 
-        let mut all_entries = self.read()?;
+        let mut all_entries = self.read(&schema)?;
         all_entries.extend(new_entries);
         println!(
             "[{}@{}] {:?} entries",
@@ -118,47 +171,49 @@ impl<'a> Table<'a> {
         Ok(())
     }
 
-    pub fn select(
+    pub fn update(
         &self,
-        cols: SelectCols,
-        condition: Option<Condition>,
-    ) -> TableResult<TableEntries> {
-        let all_entries = self.read()?;
-
-        let entries = all_entries
-            .into_iter()
-            .filter(|e| Table::match_query(&condition, e))
-            .map(|entry| match &cols {
-                SelectCols::All => entry,
-                SelectCols::Cols(selectd_cols) => {
-                    let mut map = HashMap::new();
-                    selectd_cols.into_iter().for_each(|col| {
-                        map.insert(col.clone(), entry.get(col.trim()).unwrap().clone());
-                    });
-                    map
-                }
-            })
-            .collect::<Vec<HashMap<_, _>>>();
+        assignments: Vec<Assignment>,
+        condition: Option<Expr>,
+    ) -> TableResult<usize> {
+        let schema = self.read_schema()?;
+        let cols = schema.columns(self.table_name);
+        let executor = Executor::new(self.db);
+        let scope = Scope::with_context(&cols, executor.context());
 
-        Ok(entries)
+        let mut entries = self.read(&schema)?;
+        let updated = schema.update(&scope, &assignments, &condition, &mut entries)?;
+        self.write(&entries)?;
+        Ok(updated)
     }
 
-    pub fn delete(&self, condition: Condition) -> TableResult<()> {
-        let all_entries = self.read()?;
+    pub fn delete(&self, condition: Expr) -> TableResult<()> {
+        let schema = self.read_schema()?;
+        let cols = schema.columns(self.table_name);
+        let executor = Executor::new(self.db);
+        let scope = Scope::with_context(&cols, executor.context());
+        scope.validate_condition(&condition, false)?;
         let condition = Some(condition);
+        let all_entries = self.read(&schema)?;
 
-        let entries = all_entries
-            .into_iter()
-            .filter(|e| !Table::match_query(&condition, e))
-            .collect::<Vec<HashMap<_, _>>>();
+        let mut entries = Vec::new();
+        for entry in all_entries {
+            if !scope.matches(&condition, &entry)? {
+                entries.push(entry);
+            }
+        }
 
         self.write(&entries)?;
         Ok(())
     }
 
-    pub fn alter(&self, col_name: &str, datatype: DataType) -> TableResult<()> {
-        // Todo: Update the actual table
-        // Update schema
+    pub fn alter(
+        &self,
+        col_name: &str,
+        datatype: DataType,
+        not_null: bool,
+        default: Option<Expr>,
+    ) -> TableResult<()> {
         self.exists_or_err()?;
         let mut schema = self.read_schema()?;
         let p = schema.cols.iter().position(|c| c == &col_name.to_string());
@@ -168,7 +223,24 @@ impl<'a> Table<'a> {
             Some(pos) => match schema.types.get(pos) {
                 None => Err(TableError::ColTypeNotFound(col_name.into())),
                 Some(_) => {
+                    if let Some(expr) = &default {
+                        default_value(&Scope::new(&[]), expr, &datatype)?;
+                    }
+
+                    // Existing values are converted, the query fails if any of them can't be.
+                    let mut entries = self.read(&schema)?;
+                    for entry in entries.iter_mut() {
+                        if !entry[pos].is_null() {
+                            entry[pos] = datatype.parse_value(&entry[pos].to_string())?;
+                        } else if not_null {
+                            return Err(TableError::NotNull(col_name.into()));
+                        }
+                    }
+
                     schema.types[pos] = datatype;
+                    schema.not_null[pos] = not_null;
+                    schema.defaults[pos] = default.as_ref().map(Expr::to_string);
+                    self.write(&entries)?;
                     self.write_schema(schema)?;
 
                     Ok(())
@@ -194,7 +266,13 @@ impl<'a> Table<'a> {
         Ok(())
     }
 
-    pub fn add_col(&self, col_name: &str, datatype: DataType) -> TableResult<()> {
+    pub fn add_col(
+        &self,
+        col_name: &str,
+        datatype: DataType,
+        not_null: bool,
+        default: Option<Expr>,
+    ) -> TableResult<()> {
         // todo: Every column should be unique
         // TODO: Add the new column to the data with the default value of this type
         let mut schema = self.read_schema()?;
@@ -208,18 +286,25 @@ impl<'a> Table<'a> {
                 schema.types.len()
             )))
         } else {
-            schema.cols.push(col_name.into());
-            schema.types.push(datatype.clone());
-
-            let all_entries = self.read()?;
+            // Existing entries take the default of the column, if any.
+            let scope = Scope::new(&[]);
+            let all_entries = self.read(&schema)?;
             let new_entries = all_entries
                 .into_iter()
                 .map(|mut entry| {
-                    entry.insert(col_name.into(), datatype.default());
+                    entry.push(match &default {
+                        Some(expr) => default_value(&scope, expr, &datatype)?,
+                        None => datatype.default(),
+                    });
 
-                    entry
+                    Ok(entry)
                 })
-                .collect::<Vec<HashMap<_, _>>>();
+                .collect::<TableResult<Vec<_>>>()?;
+
+            schema.cols.push(col_name.into());
+            schema.types.push(datatype);
+            schema.not_null.push(not_null);
+            schema.defaults.push(default.as_ref().map(Expr::to_string));
 
             self.write(&new_entries)?;
             self.write_schema(schema)?;
@@ -228,16 +313,23 @@ impl<'a> Table<'a> {
     }
 
     pub fn remove_col<T: Into<String> + Copy>(&self, col_name: T) -> TableResult<()> {
-        // Todo: Col should be removed from the table
         let mut schema = self.read_schema()?;
         let pos = schema.cols.iter().position(|c| c == &col_name.into());
 
         match pos {
             Some(pos) => {
+                let mut entries = self.read(&schema)?;
+                entries.iter_mut().for_each(|entry| {
+                    entry.remove(pos);
+                });
+
                 schema.cols.remove(pos);
                 schema.types.remove(pos);
+                schema.not_null.remove(pos);
+                schema.defaults.remove(pos);
 
                 debug_assert_eq!(schema.cols.len(), schema.types.len());
+                self.write(&entries)?;
                 self.write_schema(schema)?;
                 Ok(())
             }
@@ -245,7 +337,29 @@ impl<'a> Table<'a> {
         }
     }
 
-    fn read(&self) -> Result<TableEntries, TableError> {
+    /// The columns of the table along with its entries, entries are only
+    /// read from the file and converted to typed values as they are consumed.
+    pub fn scan(&self) -> TableResult<(Vec<Column>, impl Iterator<Item = TableResult<Row>>)> {
+        let schema = self.read_schema()?;
+        let cols = schema.columns(self.table_name);
+        let file = File::open(get_table_path(self))?;
+        let entries = JsonEntries::new(BufReader::new(file));
+
+        Ok((
+            cols,
+            entries.map(move |entry| schema.entry_from_json(&entry?)),
+        ))
+    }
+
+    fn read(&self, schema: &Schema) -> Result<TableEntries, TableError> {
+        self.read_json()?
+            .iter()
+            .map(|entry| schema.entry_from_json(entry))
+            .collect()
+    }
+
+    /// Entries as they are stored, see [`Schema::entry_from_json`].
+    fn read_json(&self) -> TableResult<Vec<serde_json::Value>> {
         self.exists_or_err()?;
         let table = get_table_path(self);
 
@@ -267,8 +381,12 @@ impl<'a> Table<'a> {
         let schema = get_schema_path(self);
 
         let content = fs::read_to_string(schema)?;
+        let mut schema: Schema = serde_json::from_str(&content)?;
+        // Schemas written before `NOT NULL` existed only have nullable columns.
+        schema.not_null.resize(schema.cols.len(), false);
+        schema.defaults.resize(schema.cols.len(), None);
 
-        Ok(serde_json::from_str(&content)?)
+        Ok(schema)
     }
 
     fn write_schema(&self, schema: Schema) -> TableResult<()> {
@@ -295,30 +413,6 @@ impl<'a> Table<'a> {
         }
     }
 
-    fn match_query(condition: &Option<Condition>, entry: &HashMap<String, String>) -> bool {
-        if condition.is_none() {
-            return true;
-        }
-
-        let Condition {
-            key,
-            value,
-            operator,
-        } = condition.as_ref().unwrap();
-
-        match entry.get(key) {
-            None => false,
-            Some(v) => match operator {
-                Operator::Eq => v == value,
-                Operator::NotEq => v != value,
-                Operator::Gt => v > value,
-                Operator::Lt => v < value,
-                Operator::GtEq => v >= value,
-                Operator::LtEq => v <= value,
-            },
-        }
-    }
-
     fn get_col_pos(&self, schema: &Schema, col_name: &str) -> Option<usize> {
         schema.cols.iter().position(|c| c == col_name)
     }
@@ -326,18 +420,474 @@ impl<'a> Table<'a> {
     fn col_exist(&self, schema: &Schema, col_name: &str) -> bool {
         self.get_col_pos(schema, col_name).is_some()
     }
+}
 
-    fn col_exist_or_err(&self, schema: &Schema, col_name: &str) -> TableResult<()> {
-        if !self.col_exist(schema, col_name) {
-            Err(TableError::ColNotFound(col_name.to_string()))
-        } else {
-            Ok(())
-        }
-    }
+/// Compute the `DEFAULT` of a column of type `dtype` in a scope without
+/// columns, it can't read other columns nor run subqueries. The scope is
+/// shared by every row of a statement so they see the same `NOW()`.
+fn default_value(scope: &Scope, expr: &Expr, dtype: &DataType) -> TableResult<Value> {
+    scope.validate(expr, false)?;
+    Ok(dtype.cast(&scope.eval(expr, &[], None)?.0)?)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct Schema {
     cols: Vec<String>,
     types: Vec<DataType>,
+    #[serde(default)]
+    not_null: Vec<bool>,
+    /// The SQL of the `DEFAULT` of each column, if any.
+    #[serde(default)]
+    defaults: Vec<Option<String>>,
+}
+
+impl Schema {
+    fn columns(&self, table: &str) -> Vec<Column> {
+        self.cols
+            .iter()
+            .zip(&self.types)
+            .map(|(name, dtype)| Column {
+                table: Some(table.to_string()),
+                name: name.clone(),
+                dtype: Some(dtype.clone()),
+            })
+            .collect()
+    }
+
+    /// Apply the assignments of an `UPDATE` to the entries matching the
+    /// condition, returns the number of entries updated. Values must be
+    /// quoted like in any other expression, a bare word is a column.
+    fn update(
+        &self,
+        scope: &Scope,
+        assignments: &[Assignment],
+        condition: &Option<Expr>,
+        entries: &mut [Row],
+    ) -> TableResult<usize> {
+        if let Some(condition) = condition {
+            scope.validate_condition(condition, false)?;
+        }
+
+        let mut values = Vec::new();
+        for Assignment { col, value } in assignments {
+            let col_pos = match self.cols.iter().position(|c| c == col) {
+                Some(pos) => pos,
+                None => return Err(TableError::ColNotFound(col.to_string())),
+            };
+            let dtype = match self.types.get(col_pos) {
+                Some(dtype) => dtype,
+                None => return Err(TableError::ColTypeNotFound(col.to_string())),
+            };
+            // Plain values are parsed once as written, anything else is
+            // computed for each row.
+            let parsed = match value {
+                Expr::Literal(Literal::Null) => Some(Value::Null),
+                Expr::Literal(Literal::Number(raw) | Literal::String(raw)) => {
+                    Some(dtype.parse_value(raw)?)
+                }
+                Expr::Literal(Literal::Boolean(value)) => {
+                    Some(dtype.parse_value(&value.to_string())?)
+                }
+                expr => {
+                    scope.validate(expr, false)?;
+                    None
+                }
+            };
+            values.push((col_pos, dtype, parsed, value));
+        }
+
+        let mut updated = 0;
+        for entry in entries.iter_mut() {
+            if scope.matches(condition, entry)? {
+                // Every value is computed from the row before the update.
+                let row = entry.clone();
+                for (col_pos, dtype, parsed, expr) in &values {
+                    entry[*col_pos] = match parsed {
+                        Some(value) => value.clone(),
+                        None => dtype.cast(&scope.eval(expr, &row, None)?.0)?,
+                    };
+                }
+                self.check_not_null(entry)?;
+                updated += 1;
+            }
+        }
+
+        Ok(updated)
+    }
+
+    /// The `DEFAULT` of each column, parsed back from its SQL.
+    fn default_exprs(&self) -> TableResult<Vec<Option<Expr>>> {
+        self.defaults
+            .iter()
+            .map(|default| {
+                default
+                    .as_deref()
+                    .map(|sql| {
+                        Parser::new(sql)
+                            .and_then(|mut parser| parser.parse_expr())
+                            .map_err(|_| TableError::InvalidDefault(sql.to_string()))
+                    })
+                    .transpose()
+            })
+            .collect()
+    }
+
+    fn check_not_null(&self, entry: &[Value]) -> TableResult<()> {
+        match self
+            .not_null
+            .iter()
+            .zip(entry)
+            .position(|(not_null, value)| *not_null && value.is_null())
+        {
+            Some(pos) => Err(TableError::NotNull(self.cols[pos].clone())),
+            None => Ok(()),
+        }
+    }
+
+    /// Entries are stored as arrays in the same order as the columns, tables
+    /// written before that store each entry as an object of raw strings.
+    fn entry_from_json(&self, json: &serde_json::Value) -> TableResult<Row> {
+        match json {
+            serde_json::Value::Array(values) if values.len() == self.cols.len() => self
+                .types
+                .iter()
+                .zip(values)
+                .map(|(dtype, value)| Ok(dtype.value_from_json(value)?))
+                .collect(),
+            serde_json::Value::Object(map) => self
+                .cols
+                .iter()
+                .zip(&self.types)
+                .map(|(col, dtype)| {
+                    Ok(dtype.value_from_json(map.get(col).unwrap_or(&serde_json::Value::Null))?)
+                })
+                .collect(),
+            json => Err(TableError::NumberMismatch(format!(
+                "entry {} doesn't match the columns {:?}",
+                json, self.cols
+            ))),
+        }
+    }
+}
+
+/// Reads the entries of a table file one at a time, so a scan that stops
+/// early never reads the rest of the file.
+struct JsonEntries<R> {
+    reader: R,
+    started: bool,
+    done: bool,
+}
+
+impl<R: BufRead> JsonEntries<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            started: false,
+            done: false,
+        }
+    }
+
+    /// The next byte that isn't whitespace, without consuming it.
+    fn peek(&mut self) -> TableResult<Option<u8>> {
+        loop {
+            match self.reader.fill_buf()?.first() {
+                Some(byte) if byte.is_ascii_whitespace() => self.reader.consume(1),
+                byte => return Ok(byte.copied()),
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: &[u8]) -> TableResult<u8> {
+        match self.peek()? {
+            Some(byte) if expected.contains(&byte) => {
+                self.reader.consume(1);
+                Ok(byte)
+            }
+            found => Err(serde_json::Error::custom(format!(
+                "expected one of {:?} in the table entries, found {:?}",
+                expected.iter().map(|&b| b as char).collect::<Vec<_>>(),
+                found.map(char::from)
+            ))
+            .into()),
+        }
+    }
+
+    fn next_entry(&mut self) -> TableResult<Option<serde_json::Value>> {
+        if !self.started {
+            self.started = true;
+            self.expect(b"[")?;
+            if self.peek()? == Some(b']') {
+                return Ok(None);
+            }
+        } else if self.expect(b",]")? == b']' {
+            return Ok(None);
+        }
+        self.peek()?;
+        // Entries are arrays or objects, the deserializer stops at their closing
+        // bracket without reading ahead.
+        let mut de = serde_json::Deserializer::from_reader(&mut self.reader);
+        Ok(Some(serde_json::Value::deserialize(&mut de)?))
+    }
+}
+
+impl<R: BufRead> Iterator for JsonEntries<R> {
+    type Item = TableResult<serde_json::Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let entry = self.next_entry().transpose();
+        self.done = !matches!(entry, Some(Ok(_)));
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{default_value, JsonEntries, Row, Schema, TableError};
+    use crate::{
+        ast::Expr,
+        eval::{EvalError, Scope},
+        parser::Parser,
+        query_parser::Assignment,
+        types::{DataType, DataTypesErr},
+        value::Value,
+    };
+
+    fn schema() -> Schema {
+        Schema {
+            cols: vec![
+                "first_name".into(),
+                "last_name".into(),
+                "age".into(),
+                "height".into(),
+                "country".into(),
+                "is_married".into(),
+                "level".into(),
+            ],
+            types: vec![
+                DataType::TEXT,
+                DataType::TEXT,
+                DataType::INT,
+                DataType::FLOAT,
+                DataType::VARCHAR(2),
+                DataType::BOOL,
+                DataType::ENUM(vec!["LOW".into(), "MEDIUM".into(), "HIGH".into()]),
+            ],
+            not_null: vec![true, false, false, false, false, false, false],
+            defaults: vec![None, None, Some("6 * 3".into()), None, None, None, None],
+        }
+    }
+
+    #[test]
+    fn read_typed_and_legacy_entries() {
+        let schema = schema();
+
+        let typed = json!(["jone", "doe", 21, 1.8, "EG", false, "LOW"]);
+        let legacy = json!({
+            "first_name": "jone",
+            "last_name": "doe",
+            "age": "21",
+            "height": "1.8",
+            "country": "EG",
+            "is_married": "false",
+            "level": "LOW"
+        });
+
+        let expected = vec![
+            Value::Text("jone".into()),
+            Value::Text("doe".into()),
+            Value::Int(21),
+            Value::Float(1.8),
+            Value::Text("EG".into()),
+            Value::Bool(false),
+            Value::Text("LOW".into()),
+        ];
+        assert_eq!(schema.entry_from_json(&typed).unwrap(), expected);
+        assert_eq!(schema.entry_from_json(&legacy).unwrap(), expected);
+        assert_eq!(json!(expected), typed);
+    }
+
+    #[test]
+    fn read_ints_stored_out_of_range() {
+        let schema = schema();
+        let legacy = json!({"first_name": "jone", "age": "3000000000"});
+        let typed = json!(["jone", null, 3000000000i64, null, null, null, null]);
+
+        for entry in [legacy, typed] {
+            let entry = schema.entry_from_json(&entry).unwrap();
+            assert_eq!(entry[2], Value::Int(3000000000));
+        }
+        assert!(matches!(
+            schema.types[2].parse_value("3000000000"),
+            Err(DataTypesErr::InvalidInt(_))
+        ));
+    }
+
+    fn update(
+        entries: &mut [Row],
+        assignments: &[(&str, &str)],
+        condition: Option<&str>,
+    ) -> Result<usize, TableError> {
+        let schema = schema();
+        let cols = schema.columns("t");
+        let expr = |sql: &str| Parser::new(sql).unwrap().parse_expr().unwrap();
+        let assignments = assignments
+            .iter()
+            .map(|(col, value)| Assignment {
+                col: col.to_string(),
+                value: expr(value),
+            })
+            .collect::<Vec<_>>();
+
+        schema.update(
+            &Scope::new(&cols),
+            &assignments,
+            &condition.map(expr),
+            entries,
+        )
+    }
+
+    #[test]
+    fn update_matching_entries() {
+        let schema = schema();
+        let mut entries = [
+            json!(["jone", "doe", 21, 1.8, "EG", false, "LOW"]),
+            json!(["jane", "doe", 30, null, "US", true, "HIGH"]),
+            json!(["ali", "omar", 40, 1.7, "EG", false, "MEDIUM"]),
+        ]
+        .map(|entry| schema.entry_from_json(&entry).unwrap());
+
+        let updated = update(
+            &mut entries,
+            &[("age", "age + 1"), ("height", "2"), ("level", "'HIGH'")],
+            Some("country = 'EG'"),
+        );
+        assert_eq!(updated.unwrap(), 2);
+        assert_eq!(entries[0][2], Value::Int(22));
+        assert_eq!(entries[0][3], Value::Float(2.0));
+        assert_eq!(entries[2][6], Value::Text("HIGH".into()));
+        assert_eq!(entries[1][2], Value::Int(30));
+
+        assert_eq!(
+            update(&mut entries, &[("is_married", "true")], None).unwrap(),
+            3
+        );
+        assert!(entries.iter().all(|entry| entry[5] == Value::Bool(true)));
+        assert_eq!(
+            update(&mut entries, &[("age", "0")], Some("age > 100")).unwrap(),
+            0
+        );
+
+        // Bare words are columns, the value is copied from the entry itself.
+        assert_eq!(
+            update(&mut entries, &[("last_name", "first_name")], None).unwrap(),
+            3
+        );
+        assert_eq!(entries[0][1], Value::Text("jone".into()));
+        assert_eq!(entries[2][1], Value::Text("ali".into()));
+    }
+
+    #[test]
+    fn reject_invalid_updates() {
+        let schema = schema();
+        let mut entries = [json!(["jone", "doe", 21, 1.8, "EG", false, "LOW"])]
+            .map(|entry| schema.entry_from_json(&entry).unwrap());
+        let before = entries.clone();
+
+        assert!(matches!(
+            update(&mut entries, &[("last_name", "nmae")], None),
+            Err(TableError::EvalErr(EvalError::ColNotFound(col))) if col == "nmae"
+        ));
+        assert!(matches!(
+            update(&mut entries, &[("nmae", "'x'")], None),
+            Err(TableError::ColNotFound(col)) if col == "nmae"
+        ));
+        assert!(matches!(
+            update(&mut entries, &[("age", "'ten'")], None),
+            Err(TableError::TypeErr(DataTypesErr::InvalidInt(_)))
+        ));
+        assert!(matches!(
+            update(&mut entries, &[("country", "'EGY'")], None),
+            Err(TableError::TypeErr(DataTypesErr::InvalidStr(_)))
+        ));
+        // Values are checked before any entry is touched.
+        assert_eq!(entries[..], before[..]);
+        assert!(matches!(
+            update(&mut entries, &[("first_name", "NULL")], None),
+            Err(TableError::NotNull(col)) if col == "first_name"
+        ));
+    }
+
+    #[test]
+    fn reject_null_in_not_null_column() {
+        let schema = schema();
+        let mut entry = vec![Value::Null; schema.cols.len()];
+        assert!(matches!(
+            schema.check_not_null(&entry),
+            Err(TableError::NotNull(col)) if col == "first_name"
+        ));
+
+        entry[0] = Value::Text("jone".into());
+        assert!(schema.check_not_null(&entry).is_ok());
+    }
+
+    #[test]
+    fn compute_column_defaults() {
+        let schema = schema();
+        let scope = Scope::new(&[]);
+        let default_value = |expr: &Expr, dtype: &DataType| default_value(&scope, expr, dtype);
+        let defaults = schema.default_exprs().unwrap();
+        let age = defaults[2].as_ref().unwrap();
+        assert!(defaults[0].is_none());
+        assert_eq!(
+            default_value(age, &schema.types[2]).unwrap(),
+            Value::Int(18)
+        );
+
+        let expr = |sql: &str| Parser::new(sql).unwrap().parse_expr().unwrap();
+        assert!(matches!(
+            default_value(&expr("gen_random_uuid()"), &DataType::UUID).unwrap(),
+            Value::Uuid(_)
+        ));
+        assert!(matches!(
+            default_value(&expr("'abc'"), &DataType::INT),
+            Err(TableError::TypeErr(_))
+        ));
+        assert!(matches!(
+            default_value(&expr("age + 1"), &DataType::INT),
+            Err(TableError::EvalErr(_))
+        ));
+
+        let now = expr("NOW()");
+        let first = default_value(&now, &DataType::TIMESTAMPTZ).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        assert_eq!(default_value(&now, &DataType::TIMESTAMPTZ).unwrap(), first);
+    }
+
+    #[test]
+    fn stream_entries() {
+        let entries = |content: &str| {
+            JsonEntries::new(content.as_bytes())
+                .map(|entry| entry.ok())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(entries("[]"), vec![]);
+        assert_eq!(
+            entries("[\n  [1, \"a\"],\n  {\"x\": [2]}\n]"),
+            vec![Some(json!([1, "a"])), Some(json!({"x": [2]}))]
+        );
+        assert_eq!(entries("[[1] [2]]"), vec![Some(json!([1])), None]);
+
+        // Entries past the ones taken are never read.
+        let mut stream = JsonEntries::new("[[1],[2],[3".as_bytes());
+        assert_eq!(stream.next().unwrap().unwrap(), json!([1]));
+        assert_eq!(stream.next().unwrap().unwrap(), json!([2]));
+        assert!(stream.next().unwrap().is_err());
+        assert!(stream.next().is_none());
+    }
 }
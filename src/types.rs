@@ -1,42 +1,84 @@
-use std::num::ParseIntError;
+use std::{cmp::Ordering, num::ParseIntError};
 
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
 use regex::Regex;
+use rust_decimal::{prelude::ToPrimitive, Decimal, RoundingStrategy};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::regex::{RE_ENUM, RE_ENUM_VALUES, RE_VARCHAR};
+use crate::{
+    datetime::{self, Interval},
+    regex::{RE_DECIMAL, RE_ENUM, RE_ENUM_VALUES, RE_VARCHAR},
+    value::Value,
+};
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Error, PartialEq, Eq)]
 pub enum DataTypesErr {
-    #[error("Invalid Type")]
+    #[error("Invalid type `{0}`")]
     InvalidType(String),
-    #[error("Invalid varchar Type")]
+    #[error("Invalid varchar size: {0}")]
     InvalidVarchar(#[from] ParseIntError),
-    #[error("Invalid number")]
+    #[error("{0}")]
     InvalidInt(String),
-    #[error("Invalid float")]
+    #[error("{0}")]
     InvalidFloat(String),
-    #[error("Invalid float")]
+    #[error("{0}")]
+    InvalidDecimal(String),
+    #[error("{0}")]
     InvalidEnum(String),
-    #[error("Invalid boolean")]
+    #[error("{0}")]
     InvalidBool(String),
-    #[error("Invalid string")]
+    #[error("{0}")]
     InvalidStr(String),
+    #[error("{0}")]
+    InvalidDateTime(String),
+    #[error("{0}")]
+    InvalidBlob(String),
+    #[error("{0}")]
+    InvalidJson(String),
+    #[error("{0}")]
+    InvalidUuid(String),
+    #[error("{0}")]
+    InvalidValue(String),
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Clone)]
 pub enum DataType {
     // Numeric datatypes
+    TINYINT,
+    SMALLINT,
     INTEGER,
     INT,
+    BIGINT,
+    /// An integer that can't be negative, like `SMALLINT UNSIGNED`. The size
+    /// is in bits, a 64 bits one stops at the end of the range of `BIGINT`.
+    UNSIGNED(u32),
     FLOAT,
+    /// An exact number of any size, like a `DECIMAL` without a precision.
     DEC,
+    /// An exact number with at most `precision` digits, `scale` of them
+    /// after the decimal point.
+    DECIMAL(u32, u32),
     // String datatypes
     TEXT,
     VARCHAR(usize),
     ENUM(Vec<String>),
     BOOLEAN,
     BOOL,
+    // Date and time datatypes
+    DATE,
+    TIME,
+    TIMESTAMP,
+    TIMESTAMPTZ,
+    INTERVAL,
+    /// Raw bytes, also called `BYTEA`.
+    BLOB,
+    /// A JSON document, also called `JSONB`.
+    JSON,
+    /// 16 bytes, shown in the canonical lowercase form with hyphens.
+    UUID,
 }
 
 impl DataType {
@@ -44,8 +86,27 @@ impl DataType {
         let re_varchar = Regex::new(RE_VARCHAR).unwrap();
         let re_enum = Regex::new(RE_ENUM).unwrap();
         let re_enum_values = Regex::new(RE_ENUM_VALUES).unwrap();
+        let re_decimal = Regex::new(RE_DECIMAL).unwrap();
         let dt = datatype.trim();
 
+        if let Some(caps) = re_decimal.captures(dt) {
+            let invalid = || DataTypesErr::InvalidDecimal(format!("Invalid decimal `{}`", dt));
+            let precision = caps["precision"].parse::<u32>().map_err(|_| invalid())?;
+            let scale = match caps.name("scale") {
+                Some(scale) => scale.as_str().parse::<u32>().map_err(|_| invalid())?,
+                None => 0,
+            };
+            if precision == 0 || precision > Decimal::MAX_SCALE || scale > precision {
+                return Err(DataTypesErr::InvalidDecimal(format!(
+                    "Invalid decimal `{}`: precision must be 1 to {}, scale at most precision",
+                    dt,
+                    Decimal::MAX_SCALE
+                )));
+            }
+
+            return Ok(DataType::DECIMAL(precision, scale));
+        }
+
         if let Some(caps) = re_varchar.captures(dt) {
             let size = match caps.name("size") {
                 Some(_) => match caps["size"].parse::<usize>() {
@@ -67,69 +128,698 @@ impl DataType {
             return Ok(DataType::ENUM(values));
         }
 
+        // `TIMESTAMP WITH TIME ZONE` can be split over several lines.
+        let dt = dt.split_whitespace().collect::<Vec<_>>().join(" ");
         let dt = dt.to_uppercase();
+        if let Some(base) = dt.strip_suffix("UNSIGNED") {
+            return match base.trim() {
+                "" => Ok(DataType::UNSIGNED(64)),
+                base => match DataType::parse(base)? {
+                    DataType::TINYINT => Ok(DataType::UNSIGNED(8)),
+                    DataType::SMALLINT => Ok(DataType::UNSIGNED(16)),
+                    DataType::INTEGER | DataType::INT => Ok(DataType::UNSIGNED(32)),
+                    DataType::BIGINT => Ok(DataType::UNSIGNED(64)),
+                    _ => Err(DataTypesErr::InvalidType(datatype.trim().into())),
+                },
+            };
+        }
+
         let dt = match dt {
+            _ if DataType::TINYINT.as_string() == dt => DataType::TINYINT,
+            _ if DataType::SMALLINT.as_string() == dt => DataType::SMALLINT,
             _ if DataType::INTEGER.as_string() == dt => DataType::INTEGER,
             _ if DataType::INT.as_string() == dt => DataType::INT,
+            _ if DataType::BIGINT.as_string() == dt => DataType::BIGINT,
             _ if DataType::FLOAT.as_string() == dt => DataType::FLOAT,
             _ if DataType::DEC.as_string() == dt => DataType::DEC,
+            _ if dt == "DECIMAL" || dt == "NUMERIC" => DataType::DEC,
             _ if DataType::TEXT.as_string() == dt => DataType::TEXT,
             _ if DataType::BOOLEAN.as_string() == dt => DataType::BOOLEAN,
             _ if DataType::BOOL.as_string() == dt => DataType::BOOL,
+            _ if DataType::DATE.as_string() == dt => DataType::DATE,
+            _ if DataType::TIME.as_string() == dt => DataType::TIME,
+            _ if DataType::TIMESTAMP.as_string() == dt => DataType::TIMESTAMP,
+            _ if DataType::TIMESTAMPTZ.as_string() == dt => DataType::TIMESTAMPTZ,
+            _ if DataType::INTERVAL.as_string() == dt => DataType::INTERVAL,
+            _ if DataType::BLOB.as_string() == dt || dt == "BYTEA" => DataType::BLOB,
+            _ if DataType::JSON.as_string() == dt || dt == "JSONB" => DataType::JSON,
+            _ if DataType::UUID.as_string() == dt => DataType::UUID,
+            _ if dt == "TIMESTAMP WITHOUT TIME ZONE" => DataType::TIMESTAMP,
+            _ if dt == "TIMESTAMP WITH TIME ZONE" => DataType::TIMESTAMPTZ,
 
             _ => return Err(DataTypesErr::InvalidType(datatype.trim().into())),
         };
 
-        return Ok(dt);
+        Ok(dt)
     }
 
     pub fn as_string(&self) -> String {
         format!("{:?}", self)
     }
 
-    pub fn is_valid(&self, raw: &str) -> Result<(), DataTypesErr> {
-        return match self {
-            DataType::INTEGER | DataType::INT if raw.parse::<i64>().is_err() => Err(
-                DataTypesErr::InvalidInt(format!("'{}' is not a valid {:?}", raw, self)),
-            ),
-            DataType::FLOAT | DataType::DEC if raw.parse::<f64>().is_err() => Err(
-                DataTypesErr::InvalidFloat(format!("'{}' is not a valid {:?}", raw, self)),
-            ),
+    /// Parse a raw value, as written in a query, into a value of this type.
+    pub fn parse_value(&self, raw: &str) -> Result<Value, DataTypesErr> {
+        match self {
+            DataType::TINYINT
+            | DataType::SMALLINT
+            | DataType::INTEGER
+            | DataType::INT
+            | DataType::BIGINT
+            | DataType::UNSIGNED(_) => match raw.parse::<i64>() {
+                Ok(v) => self.fit_int(v),
+                Err(_) => Err(DataTypesErr::InvalidInt(format!(
+                    "'{}' is not a valid {:?}",
+                    raw, self
+                ))),
+            },
+            DataType::DEC | DataType::DECIMAL(..) => match parse_decimal(raw) {
+                Some(v) => self.fit_decimal(v),
+                None => Err(DataTypesErr::InvalidDecimal(format!(
+                    "'{}' is not a valid {:?}",
+                    raw, self
+                ))),
+            },
+            DataType::FLOAT => match raw.parse::<f64>() {
+                Ok(v) if v.is_finite() => Ok(Value::Float(v)),
+                _ => Err(DataTypesErr::InvalidFloat(format!(
+                    "'{}' is not a valid {:?}",
+                    raw, self
+                ))),
+            },
             DataType::VARCHAR(max_len) if &raw.len() > max_len => Err(DataTypesErr::InvalidStr(
                 format!("Max length exceed of `{}`. Max len = {}", raw, max_len),
             )),
-            DataType::ENUM(values) if values.iter().position(|v| v == raw).is_none() => {
+            DataType::ENUM(values) if !values.iter().any(|v| v == raw) => {
                 Err(DataTypesErr::InvalidEnum(format!(
                     "`{}` is not valid enum. must be one of these {:?}",
                     raw, values
                 )))
             }
-            DataType::BOOLEAN | DataType::BOOL if raw.parse::<bool>().is_err() => Err(
-                DataTypesErr::InvalidBool(format!("`{}` is not a valid boolean", raw)),
-            ),
-            _ => Ok(()),
+            DataType::TEXT | DataType::VARCHAR(_) | DataType::ENUM(_) => {
+                Ok(Value::Text(raw.to_string()))
+            }
+            DataType::BOOLEAN | DataType::BOOL => {
+                raw.parse::<bool>().map(Value::Bool).map_err(|_| {
+                    DataTypesErr::InvalidBool(format!("`{}` is not a valid boolean", raw))
+                })
+            }
+            DataType::DATE => datetime::parse_date(raw)
+                .map(Value::Date)
+                .ok_or_else(|| self.invalid_datetime(raw)),
+            DataType::TIME => datetime::parse_time(raw)
+                .map(Value::Time)
+                .ok_or_else(|| self.invalid_datetime(raw)),
+            DataType::TIMESTAMP => datetime::parse_timestamp(raw)
+                .map(Value::Timestamp)
+                .ok_or_else(|| self.invalid_datetime(raw)),
+            DataType::TIMESTAMPTZ => datetime::parse_timestamptz(raw)
+                .map(Value::TimestampTz)
+                .ok_or_else(|| self.invalid_datetime(raw)),
+            DataType::INTERVAL => Interval::parse(raw)
+                .map(Value::Interval)
+                .ok_or_else(|| self.invalid_datetime(raw)),
+            // Like in Postgres, `\x` starts hex digits and other text is
+            // stored as is.
+            DataType::BLOB => match raw.strip_prefix("\\x") {
+                Some(digits) => hex::decode(digits)
+                    .map(Value::Blob)
+                    .map_err(|_| DataTypesErr::InvalidBlob(format!("'{}' is not valid hex", raw))),
+                None => Ok(Value::Blob(raw.as_bytes().to_vec())),
+            },
+            DataType::JSON => serde_json::from_str(raw)
+                .map(Value::Json)
+                .map_err(|_| DataTypesErr::InvalidJson(format!("'{}' is not valid JSON", raw))),
+            // Hex digits without hyphens, in braces or in capitals are
+            // accepted too, they are all read into the same 16 bytes.
+            DataType::UUID => uuid::Uuid::try_parse(raw)
+                .map(Value::Uuid)
+                .map_err(|_| DataTypesErr::InvalidUuid(format!("'{}' is not a valid UUID", raw))),
+        }
+    }
+
+    /// Check an integer is in the range of this type.
+    fn fit_int(&self, value: i64) -> Result<Value, DataTypesErr> {
+        match self.int_range() {
+            Some((min, max)) if value < min || value > max => Err(DataTypesErr::InvalidInt(
+                format!("'{}' is out of range for {:?}", value, self),
+            )),
+            _ => Ok(Value::Int(value)),
+        }
+    }
+
+    /// Round a decimal to the scale of a `DECIMAL(p, s)`, the digits left
+    /// before the decimal point must fit the precision.
+    fn fit_decimal(&self, value: Decimal) -> Result<Value, DataTypesErr> {
+        let DataType::DECIMAL(precision, scale) = self else {
+            return Ok(Value::Decimal(value));
         };
+
+        let mut rounded =
+            value.round_dp_with_strategy(*scale, RoundingStrategy::MidpointAwayFromZero);
+        rounded.rescale(*scale);
+        let max = Decimal::from_i128_with_scale(10i128.pow(precision - scale), 0);
+        match rounded.abs() < max {
+            true => Ok(Value::Decimal(rounded)),
+            false => Err(DataTypesErr::InvalidDecimal(format!(
+                "'{}' is out of range for {:?}",
+                value, self
+            ))),
+        }
+    }
+
+    fn invalid_datetime(&self, raw: &str) -> DataTypesErr {
+        DataTypesErr::InvalidDateTime(format!("'{}' is not a valid {:?}", raw, self))
     }
 
-    pub fn default(&self) -> String {
-        let res = match self {
-            DataType::INTEGER | DataType::INT => "0",
-            DataType::FLOAT | DataType::DEC => "0.0",
-            DataType::TEXT | DataType::VARCHAR(_) => "",
-            DataType::ENUM(val) => val[0].as_str(),
-            DataType::BOOLEAN | DataType::BOOL => "false",
+    /// Convert a value of any type into a value of this type, e.g. an integer
+    /// literal compared to a float column.
+    pub fn coerce(&self, value: &Value) -> Result<Value, DataTypesErr> {
+        match (self, value) {
+            (_, Value::Null) => Ok(Value::Null),
+            (_, Value::Int(_)) if self.is_integer() => Ok(value.clone()),
+            (DataType::FLOAT, Value::Float(_))
+            | (DataType::DEC | DataType::DECIMAL(..), Value::Decimal(_))
+            | (DataType::BOOLEAN | DataType::BOOL, Value::Bool(_))
+            | (DataType::DATE, Value::Date(_))
+            | (DataType::TIME, Value::Time(_))
+            | (DataType::TIMESTAMP, Value::Timestamp(_))
+            | (DataType::TIMESTAMPTZ, Value::TimestampTz(_))
+            | (DataType::INTERVAL, Value::Interval(_))
+            | (DataType::BLOB, Value::Blob(_))
+            | (DataType::JSON, Value::Json(_))
+            | (DataType::UUID, Value::Uuid(_)) => Ok(value.clone()),
+            (DataType::FLOAT, Value::Int(v)) => Ok(Value::Float(*v as f64)),
+            // Like floats, decimals without a fraction are valid integers.
+            (_, Value::Decimal(v)) if self.is_integer() && v.fract().is_zero() => {
+                self.parse_value(&v.trunc().to_string())
+            }
+            (DataType::FLOAT, Value::Decimal(v)) => match v.to_f64() {
+                Some(v) => Ok(Value::Float(v)),
+                None => Err(DataTypesErr::InvalidFloat(format!(
+                    "'{}' is not a valid {:?}",
+                    v, self
+                ))),
+            },
+            // Decimals are only rounded to their scale when stored, see `cast`.
+            (DataType::DEC | DataType::DECIMAL(..), Value::Int(v)) => {
+                Ok(Value::Decimal(Decimal::from(*v)))
+            }
+            (DataType::DEC | DataType::DECIMAL(..), Value::Float(v)) => {
+                match parse_decimal(&v.to_string()) {
+                    Some(v) => Ok(Value::Decimal(v)),
+                    None => Err(DataTypesErr::InvalidDecimal(format!(
+                        "'{}' is out of range for {:?}",
+                        v, self
+                    ))),
+                }
+            }
+            // Dates are at midnight and timestamps without a time zone are in UTC.
+            (DataType::TIMESTAMP, Value::Date(v)) => {
+                Ok(Value::Timestamp(v.and_time(NaiveTime::MIN)))
+            }
+            (DataType::TIMESTAMP, Value::TimestampTz(v)) => Ok(Value::Timestamp(v.naive_utc())),
+            (DataType::TIMESTAMPTZ, Value::Date(v)) => {
+                Ok(Value::TimestampTz(v.and_time(NaiveTime::MIN).and_utc()))
+            }
+            (DataType::TIMESTAMPTZ, Value::Timestamp(v)) => Ok(Value::TimestampTz(v.and_utc())),
+            (DataType::DATE, Value::Timestamp(v)) => Ok(Value::Date(v.date())),
+            (DataType::DATE, Value::TimestampTz(v)) => Ok(Value::Date(v.date_naive())),
+            (DataType::TIME, Value::Timestamp(v)) => Ok(Value::Time(v.time())),
+            (DataType::TIME, Value::TimestampTz(v)) => Ok(Value::Time(v.time())),
+            // The max length is only enforced when the value is stored.
+            (DataType::TEXT | DataType::VARCHAR(_), value) => Ok(Value::Text(value.to_string())),
+            (_, value) => self.parse_value(&value.to_string()),
+        }
+    }
+
+    /// Convert a value to this type like `CAST` does. Unlike
+    /// [`DataType::coerce`] the result is valid to store: floats and decimals
+    /// are rounded to integers or to the scale of a decimal, integers must be
+    /// in the range of the type and text must fit the type.
+    pub fn cast(&self, value: &Value) -> Result<Value, DataTypesErr> {
+        match (self, value) {
+            (_, Value::Null) => Ok(Value::Null),
+            (_, Value::Float(v)) if self.is_integer() => {
+                let rounded = v.round();
+                // `i64::MAX` as a float is 2^63, which doesn't fit.
+                if rounded >= i64::MIN as f64 && rounded < i64::MAX as f64 {
+                    self.fit_int(rounded as i64)
+                } else {
+                    Err(DataTypesErr::InvalidInt(format!(
+                        "'{}' is out of range for {:?}",
+                        v, self
+                    )))
+                }
+            }
+            (_, Value::Decimal(v)) if self.is_integer() => {
+                match v
+                    .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+                    .to_i64()
+                {
+                    Some(v) => self.fit_int(v),
+                    None => Err(DataTypesErr::InvalidInt(format!(
+                        "'{}' is out of range for {:?}",
+                        v, self
+                    ))),
+                }
+            }
+            (DataType::TEXT | DataType::VARCHAR(_) | DataType::ENUM(_), value) => {
+                self.parse_value(&value.to_string())
+            }
+            // Spaces are bytes too.
+            (DataType::BLOB, Value::Text(raw)) => self.parse_value(raw),
+            (_, Value::Text(raw)) => self.parse_value(raw.trim()),
+            (_, Value::Int(v)) if self.is_integer() => self.fit_int(*v),
+            (DataType::DECIMAL(..), value) => match self.coerce(value)? {
+                Value::Decimal(v) => self.fit_decimal(v),
+                value => Ok(value),
+            },
+            (_, value) => self.coerce(value),
+        }
+    }
+
+    /// Read a value back from the table file. Strings are parsed with
+    /// [`DataType::parse_value`] so tables written before values were typed
+    /// are read transparently. JSON documents are read as they are, a
+    /// document that is only `null` reads as a null value.
+    ///
+    /// Integers aren't checked against the range of the column, `INT` used to
+    /// hold 64 bits and what is already stored must stay readable.
+    pub fn value_from_json(&self, json: &serde_json::Value) -> Result<Value, DataTypesErr> {
+        match json {
+            serde_json::Value::Null => Ok(Value::Null),
+            json if *self == DataType::JSON => Ok(Value::Json(json.clone())),
+            serde_json::Value::String(raw) if self.is_integer() => match raw.parse::<i64>() {
+                Ok(v) => Ok(Value::Int(v)),
+                Err(_) => self.parse_value(raw),
+            },
+            serde_json::Value::String(raw) => self.parse_value(raw),
+            serde_json::Value::Bool(v) => self.coerce(&Value::Bool(*v)),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(v) => self.coerce(&Value::Int(v)),
+                None => self.coerce(&Value::Float(n.as_f64().unwrap_or(f64::NAN))),
+            },
+            json => Err(DataTypesErr::InvalidValue(format!(
+                "`{}` is not a valid {:?}",
+                json, self
+            ))),
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            DataType::FLOAT | DataType::DEC | DataType::DECIMAL(..)
+        ) || self.is_integer()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.int_range().is_some()
+    }
+
+    /// The smallest and largest values of an integer type.
+    pub fn int_range(&self) -> Option<(i64, i64)> {
+        match self {
+            DataType::TINYINT => Some((i8::MIN.into(), i8::MAX.into())),
+            DataType::SMALLINT => Some((i16::MIN.into(), i16::MAX.into())),
+            DataType::INTEGER | DataType::INT => Some((i32::MIN.into(), i32::MAX.into())),
+            DataType::BIGINT => Some((i64::MIN, i64::MAX)),
+            DataType::UNSIGNED(bits) => Some((0, i64::MAX >> 63u32.saturating_sub(*bits))),
+            _ => None,
+        }
+    }
+
+    pub fn is_decimal(&self) -> bool {
+        matches!(self, DataType::DEC | DataType::DECIMAL(..))
+    }
+
+    /// Enum values are text too.
+    pub fn is_text(&self) -> bool {
+        matches!(
+            self,
+            DataType::TEXT | DataType::VARCHAR(_) | DataType::ENUM(_)
+        )
+    }
+
+    pub fn is_temporal(&self) -> bool {
+        matches!(
+            self,
+            DataType::DATE
+                | DataType::TIME
+                | DataType::TIMESTAMP
+                | DataType::TIMESTAMPTZ
+                | DataType::INTERVAL
+        )
+    }
+
+    /// A type values of both types can be coerced to, if any. Integers mixed
+    /// together take the type of the widest one, numbers mixed with floats are
+    /// floats, integers mixed with decimals are decimals, text of any kind is
+    /// plain text and dates mixed with timestamps are timestamps.
+    pub fn common_type(&self, other: &DataType) -> Option<DataType> {
+        let contains = |a: &DataType, b: &DataType| match (a.int_range(), b.int_range()) {
+            (Some(a), Some(b)) => a.0 <= b.0 && b.1 <= a.1,
+            _ => false,
+        };
+        let is_bool = |dtype: &DataType| matches!(dtype, DataType::BOOLEAN | DataType::BOOL);
+        let is_timestamp = |dtype: &DataType| {
+            matches!(
+                dtype,
+                DataType::DATE | DataType::TIMESTAMP | DataType::TIMESTAMPTZ
+            )
         };
 
-        res.to_string()
+        match (self, other) {
+            (a, b) if a == b => Some(a.clone()),
+            (a, b) if contains(a, b) => Some(a.clone()),
+            (a, b) if contains(b, a) => Some(b.clone()),
+            (a, b) if a.is_integer() && b.is_integer() => Some(DataType::BIGINT),
+            (DataType::FLOAT, b) | (b, DataType::FLOAT) if b.is_numeric() => Some(DataType::FLOAT),
+            (a, b) if a.is_numeric() && b.is_numeric() => Some(DataType::DEC),
+            (a, b) if a.is_text() && b.is_text() => Some(DataType::TEXT),
+            (a, b) if is_bool(a) && is_bool(b) => Some(DataType::BOOL),
+            (DataType::TIMESTAMPTZ, b) | (b, DataType::TIMESTAMPTZ) if is_timestamp(b) => {
+                Some(DataType::TIMESTAMPTZ)
+            }
+            (a, b) if is_timestamp(a) && is_timestamp(b) => Some(DataType::TIMESTAMP),
+            _ => None,
+        }
+    }
+
+    /// Compare two values as this type, numbers are compared numerically,
+    /// `false` is less than `true` and enums follow their declaration order.
+    ///
+    /// Returns `None` when any of the values is null.
+    pub fn compare(&self, a: &Value, b: &Value) -> Result<Option<Ordering>, DataTypesErr> {
+        let (a, b) = (self.coerce(a)?, self.coerce(b)?);
+
+        let ordering = match (self, &a, &b) {
+            (DataType::ENUM(values), Value::Text(a), Value::Text(b)) => {
+                let pos = |v: &str| values.iter().position(|value| value == v);
+                Some(pos(a).cmp(&pos(b)))
+            }
+            (_, a, b) => a.compare(b),
+        };
+
+        Ok(ordering)
+    }
+
+    pub fn default(&self) -> Value {
+        match self {
+            DataType::TINYINT
+            | DataType::SMALLINT
+            | DataType::INTEGER
+            | DataType::INT
+            | DataType::BIGINT
+            | DataType::UNSIGNED(_) => Value::Int(0),
+            DataType::FLOAT => Value::Float(0.0),
+            DataType::DEC => Value::Decimal(Decimal::ZERO),
+            DataType::DECIMAL(_, scale) => Value::Decimal(Decimal::new(0, *scale)),
+            DataType::TEXT | DataType::VARCHAR(_) => Value::Text(String::new()),
+            DataType::ENUM(val) => Value::Text(val[0].clone()),
+            DataType::BOOLEAN | DataType::BOOL => Value::Bool(false),
+            DataType::DATE => Value::Date(NaiveDate::default()),
+            DataType::TIME => Value::Time(NaiveTime::MIN),
+            DataType::TIMESTAMP => Value::Timestamp(NaiveDateTime::default()),
+            DataType::TIMESTAMPTZ => Value::TimestampTz(DateTime::default()),
+            DataType::INTERVAL => Value::Interval(Interval::default()),
+            DataType::BLOB => Value::Blob(Vec::new()),
+            DataType::JSON => Value::Json(serde_json::Value::Object(Default::default())),
+            DataType::UUID => Value::Uuid(uuid::Uuid::nil()),
+        }
     }
 }
 
+/// Parse an exact number like `12.50` or `1.5e3`, numbers with more digits
+/// than a decimal holds are rejected instead of rounded.
+fn parse_decimal(raw: &str) -> Option<Decimal> {
+    let raw = raw.trim();
+    Decimal::from_str_exact(raw)
+        .or_else(|_| Decimal::from_scientific(raw))
+        .ok()
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::types::DataTypesErr;
+    use std::cmp::Ordering;
+
+    use serde_json::json;
+
+    use crate::{types::DataTypesErr, value::Value};
 
     use super::DataType;
 
+    #[test]
+    fn cast_values() {
+        assert_eq!(DataType::INT.cast(&Value::Float(2.5)), Ok(Value::Int(3)));
+        assert_eq!(
+            DataType::INT.cast(&Value::Text(" 42 ".into())),
+            Ok(Value::Int(42))
+        );
+        assert_eq!(DataType::FLOAT.cast(&Value::Int(2)), Ok(Value::Float(2.0)));
+        assert_eq!(
+            DataType::TEXT.cast(&Value::Float(1.5)),
+            Ok(Value::Text("1.5".into()))
+        );
+        assert_eq!(
+            DataType::BOOL.cast(&Value::Text("true".into())),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(DataType::INT.cast(&Value::Null), Ok(Value::Null));
+        assert!(matches!(
+            DataType::VARCHAR(2).cast(&Value::Text("abc".into())),
+            Err(DataTypesErr::InvalidStr(_))
+        ));
+        assert!(matches!(
+            DataType::INT.cast(&Value::Float(1e30)),
+            Err(DataTypesErr::InvalidInt(_))
+        ));
+    }
+
+    #[test]
+    fn sized_integers() {
+        let parse = |dt| DataType::parse(dt).unwrap();
+
+        assert_eq!(parse("tinyint"), DataType::TINYINT);
+        assert_eq!(parse("BIGINT"), DataType::BIGINT);
+        assert_eq!(parse("smallint  unsigned"), DataType::UNSIGNED(16));
+        assert_eq!(parse("INT UNSIGNED"), DataType::UNSIGNED(32));
+        assert_eq!(parse("UNSIGNED"), DataType::UNSIGNED(64));
+        assert!(DataType::parse("FLOAT UNSIGNED").is_err());
+
+        assert_eq!(
+            DataType::SMALLINT.parse_value("-32768"),
+            Ok(Value::Int(-32768))
+        );
+        assert_eq!(
+            DataType::TINYINT.parse_value("128"),
+            Err(DataTypesErr::InvalidInt(
+                "'128' is out of range for TINYINT".into()
+            ))
+        );
+        assert!(DataType::INT.parse_value("2147483648").is_err());
+        assert!(DataType::UNSIGNED(8).parse_value("-1").is_err());
+        assert_eq!(
+            DataType::UNSIGNED(8).parse_value("255"),
+            Ok(Value::Int(255))
+        );
+        assert_eq!(
+            DataType::UNSIGNED(64).parse_value("9223372036854775807"),
+            Ok(Value::Int(i64::MAX))
+        );
+
+        // Only stored values are checked, comparisons can be out of range.
+        assert_eq!(
+            DataType::TINYINT.coerce(&Value::Int(1000)),
+            Ok(Value::Int(1000))
+        );
+        assert!(DataType::TINYINT.cast(&Value::Int(1000)).is_err());
+        assert!(DataType::SMALLINT.cast(&Value::Float(40000.0)).is_err());
+        assert_eq!(DataType::TINYINT.default(), Value::Int(0));
+
+        assert_eq!(
+            DataType::TINYINT.common_type(&DataType::INT),
+            Some(DataType::INT)
+        );
+        assert_eq!(
+            DataType::UNSIGNED(32).common_type(&DataType::INT),
+            Some(DataType::BIGINT)
+        );
+        assert_eq!(
+            DataType::UNSIGNED(8).common_type(&DataType::SMALLINT),
+            Some(DataType::SMALLINT)
+        );
+    }
+
+    #[test]
+    fn exact_decimals() {
+        let decimal = |raw: &str| Value::Decimal(raw.parse().unwrap());
+        let money = DataType::parse("numeric( 6 , 2 )").unwrap();
+
+        assert_eq!(money, DataType::DECIMAL(6, 2));
+        assert_eq!(DataType::parse("DECIMAL(5)"), Ok(DataType::DECIMAL(5, 0)));
+        assert_eq!(DataType::parse("decimal"), Ok(DataType::DEC));
+        assert!(matches!(
+            DataType::parse("DECIMAL(29, 2)"),
+            Err(DataTypesErr::InvalidDecimal(_))
+        ));
+        assert!(matches!(
+            DataType::parse("DECIMAL(2, 3)"),
+            Err(DataTypesErr::InvalidDecimal(_))
+        ));
+
+        assert_eq!(money.parse_value("19.995"), Ok(decimal("20.00")));
+        assert_eq!(
+            money.parse_value("-0.005").map(|v| v.to_string()),
+            Ok("-0.01".into())
+        );
+        assert_eq!(
+            money.parse_value("1.5e2").map(|v| v.to_string()),
+            Ok("150.00".into())
+        );
+        assert_eq!(
+            money.parse_value("9999.995"),
+            Err(DataTypesErr::InvalidDecimal(
+                "'9999.995' is out of range for DECIMAL(6, 2)".into()
+            ))
+        );
+        assert_eq!(
+            DataType::DEC.parse_value("0.1000000000000000000000000001"),
+            Ok(decimal("0.1000000000000000000000000001"))
+        );
+
+        // Comparisons don't round, only stored values do.
+        assert_eq!(money.coerce(&Value::Float(0.125)), Ok(decimal("0.125")));
+        assert_eq!(money.cast(&Value::Float(0.125)), Ok(decimal("0.13")));
+        assert_eq!(DataType::INT.cast(&decimal("2.5")), Ok(Value::Int(3)));
+        assert_eq!(
+            DataType::DEC.value_from_json(&json!("12.50")),
+            Ok(decimal("12.50"))
+        );
+        assert_eq!(
+            DataType::DECIMAL(4, 2).common_type(&DataType::INT),
+            Some(DataType::DEC)
+        );
+        assert_eq!(
+            DataType::DEC.common_type(&DataType::FLOAT),
+            Some(DataType::FLOAT)
+        );
+    }
+
+    #[test]
+    fn parse_date_and_time_types() {
+        let parse = |dt| DataType::parse(dt).unwrap();
+
+        assert_eq!(parse("date"), DataType::DATE);
+        assert_eq!(parse("Timestamp"), DataType::TIMESTAMP);
+        assert_eq!(parse("timestamp  with\ttime zone"), DataType::TIMESTAMPTZ);
+        assert_eq!(parse("TIMESTAMP WITHOUT TIME ZONE"), DataType::TIMESTAMP);
+        assert_eq!(parse("interval"), DataType::INTERVAL);
+
+        assert_eq!(
+            DataType::TIMESTAMPTZ
+                .parse_value("2024-01-01T00:30:00+01:00")
+                .map(|v| v.to_string()),
+            Ok("2023-12-31 23:30:00+00:00".into())
+        );
+        assert_eq!(
+            DataType::TIMESTAMP.coerce(&DataType::DATE.parse_value("2024-01-01").unwrap()),
+            DataType::TIMESTAMP.parse_value("2024-01-01 00:00")
+        );
+        assert_eq!(
+            DataType::TIME.parse_value("25:00"),
+            Err(DataTypesErr::InvalidDateTime(
+                "'25:00' is not a valid TIME".into()
+            ))
+        );
+        assert_eq!(
+            DataType::DATE.common_type(&DataType::TIMESTAMPTZ),
+            Some(DataType::TIMESTAMPTZ)
+        );
+        assert_eq!(DataType::DATE.common_type(&DataType::TIME), None);
+    }
+
+    #[test]
+    fn binary_blobs() {
+        assert_eq!(DataType::parse("bytea"), Ok(DataType::BLOB));
+        assert_eq!(
+            DataType::BLOB.parse_value("\\x00ff"),
+            Ok(Value::Blob(vec![0, 0xff]))
+        );
+        assert_eq!(
+            DataType::BLOB.cast(&Value::Text(" ab".into())),
+            Ok(Value::Blob(b" ab".to_vec()))
+        );
+        assert!(DataType::BLOB.parse_value("\\x0").is_err());
+        assert_eq!(
+            DataType::TEXT.cast(&Value::Blob(vec![0x0a, 0x1b])),
+            Ok(Value::Text("\\x0a1b".into()))
+        );
+
+        // Bytes that aren't valid UTF-8 are written as hex.
+        let blob = Value::Blob(vec![0xff, 0xfe, 0x80]);
+        let json = serde_json::to_value(&blob).unwrap();
+        assert_eq!(json, json!("\\xfffe80"));
+        assert_eq!(DataType::BLOB.value_from_json(&json), Ok(blob));
+    }
+
+    #[test]
+    fn json_documents() {
+        assert_eq!(DataType::parse("jsonb"), Ok(DataType::JSON));
+        assert_eq!(
+            DataType::JSON.parse_value(r#"{"a": [1, "x"]}"#),
+            Ok(Value::Json(json!({"a": [1, "x"]})))
+        );
+        assert!(matches!(
+            DataType::JSON.parse_value("{'a': 1}"),
+            Err(DataTypesErr::InvalidJson(_))
+        ));
+        assert_eq!(
+            DataType::JSON.coerce(&Value::Int(1)),
+            Ok(Value::Json(json!(1)))
+        );
+        assert_eq!(
+            DataType::TEXT.cast(&Value::Json(json!({"a": 1}))),
+            Ok(Value::Text(r#"{"a":1}"#.into()))
+        );
+
+        // Documents are written as they are, not as a string, even strings.
+        for doc in [json!({"a": [1, "x"]}), json!("x"), json!(1.5)] {
+            let value = Value::Json(doc.clone());
+            assert_eq!(serde_json::to_value(&value).unwrap(), doc);
+            assert_eq!(DataType::JSON.value_from_json(&doc), Ok(value));
+        }
+    }
+
+    #[test]
+    fn uuids() {
+        let id = Value::Uuid(uuid::Uuid::from_u128(0x67e5504410b1426f9247bb680e5fe0c8));
+
+        for raw in [
+            "67e55044-10b1-426f-9247-bb680e5fe0c8",
+            "67E55044-10B1-426F-9247-BB680E5FE0C8",
+            "67e5504410b1426f9247bb680e5fe0c8",
+            "{67e55044-10b1-426f-9247-bb680e5fe0c8}",
+        ] {
+            assert_eq!(DataType::UUID.parse_value(raw), Ok(id.clone()));
+        }
+        assert!(matches!(
+            DataType::UUID.parse_value("67e55044-10b1-426f-9247"),
+            Err(DataTypesErr::InvalidUuid(_))
+        ));
+        assert_eq!(
+            DataType::TEXT.cast(&id),
+            Ok(Value::Text("67e55044-10b1-426f-9247-bb680e5fe0c8".into()))
+        );
+
+        let json = serde_json::to_value(&id).unwrap();
+        assert_eq!(json, json!("67e5504410b1426f9247bb680e5fe0c8"));
+        assert_eq!(DataType::UUID.value_from_json(&json), Ok(id.clone()));
+        // Tables written before UUIDs were stored as hex digits.
+        let hyphenated = json!("67e55044-10b1-426f-9247-bb680e5fe0c8");
+        assert_eq!(DataType::UUID.value_from_json(&hyphenated), Ok(id));
+    }
+
     #[test]
     fn should_convert_datatypes_as_str() {
         assert_eq!(DataType::BOOL.as_string(), "BOOL");
@@ -212,7 +902,7 @@ mod tests {
 
         datatypes
             .into_iter()
-            .for_each(|(dtype, value)| assert!(dtype.is_valid(value).is_ok()))
+            .for_each(|(dtype, value)| assert!(dtype.parse_value(value).is_ok()))
     }
 
     #[test]
@@ -245,6 +935,90 @@ mod tests {
 
         datatypes
             .into_iter()
-            .for_each(|(dtype, value, err_msg)| assert_eq!(dtype.is_valid(value), Err(err_msg)))
+            .for_each(|(dtype, value, err_msg)| assert_eq!(dtype.parse_value(value), Err(err_msg)))
+    }
+
+    #[test]
+    fn parse_typed_values() {
+        assert_eq!(DataType::INT.parse_value("42"), Ok(Value::Int(42)));
+        assert_eq!(DataType::FLOAT.parse_value("1.5"), Ok(Value::Float(1.5)));
+        assert_eq!(DataType::BOOL.parse_value("true"), Ok(Value::Bool(true)));
+        assert_eq!(
+            DataType::TEXT.parse_value("42"),
+            Ok(Value::Text("42".into()))
+        );
+    }
+
+    #[test]
+    fn read_values_from_json() {
+        assert_eq!(
+            DataType::INT.value_from_json(&json!(42)),
+            Ok(Value::Int(42))
+        );
+        assert_eq!(
+            DataType::FLOAT.value_from_json(&json!(2)),
+            Ok(Value::Float(2.0))
+        );
+        assert_eq!(
+            DataType::BOOL.value_from_json(&json!(null)),
+            Ok(Value::Null)
+        );
+        // Tables written before values were typed stored everything as strings.
+        assert_eq!(
+            DataType::INT.value_from_json(&json!("42")),
+            Ok(Value::Int(42))
+        );
+        assert_eq!(
+            DataType::BOOL.value_from_json(&json!("false")),
+            Ok(Value::Bool(false))
+        );
+    }
+
+    #[test]
+    fn compare_numbers_numerically() {
+        assert_eq!(
+            DataType::INT.compare(&Value::Int(10), &Value::Int(9)),
+            Ok(Some(Ordering::Greater))
+        );
+        assert_eq!(
+            DataType::FLOAT.compare(&Value::Float(2.5), &Value::Int(10)),
+            Ok(Some(Ordering::Less))
+        );
+        assert_eq!(
+            DataType::FLOAT.compare(&Value::Float(1.0), &Value::Int(1)),
+            Ok(Some(Ordering::Equal))
+        );
+        assert_eq!(
+            DataType::TEXT.compare(&Value::Int(10), &Value::Int(9)),
+            Ok(Some(Ordering::Less))
+        );
+    }
+
+    #[test]
+    fn compare_enums_by_declaration_order() {
+        let dtype = DataType::ENUM(vec!["LOW".into(), "MEDIUM".into(), "HIGH".into()]);
+        let (low, medium, high) = (
+            Value::Text("LOW".into()),
+            Value::Text("MEDIUM".into()),
+            Value::Text("HIGH".into()),
+        );
+        assert_eq!(dtype.compare(&high, &low), Ok(Some(Ordering::Greater)));
+        assert_eq!(dtype.compare(&low, &medium), Ok(Some(Ordering::Less)));
+    }
+
+    #[test]
+    fn compare_booleans() {
+        assert_eq!(
+            DataType::BOOL.compare(&Value::Bool(true), &Value::Bool(false)),
+            Ok(Some(Ordering::Greater))
+        );
+    }
+
+    #[test]
+    fn compare_with_uncoercible_value() {
+        assert_eq!(
+            DataType::INT.compare(&Value::Int(10), &Value::Text("ten".into())),
+            Err(DataTypesErr::InvalidInt("'ten' is not a valid INT".into()))
+        );
     }
 }
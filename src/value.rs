@@ -0,0 +1,150 @@
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use serde::{Serialize, Serializer};
+
+use crate::{datetime::Interval, json};
+
+/// A single typed value of a row, see [`DataType::parse_value`](crate::types::DataType::parse_value).
+///
+/// Values are written to the table file as plain JSON (`42`, `"Doe"`, `true`),
+/// decimals are written as text to stay exact, dates, times and intervals
+/// are written as ISO-8601 text and blobs as `\x` followed by hex digits so
+/// bytes that aren't valid UTF-8 survive. UUIDs are written as their 32 hex
+/// digits without hyphens and JSON documents are written as they are. The
+/// column type is needed to read them back, see
+/// [`DataType::value_from_json`](crate::types::DataType::value_from_json).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Int(i64),
+    Float(f64),
+    /// An exact number, see `DECIMAL(p, s)`.
+    Decimal(Decimal),
+    Text(String),
+    Bool(bool),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Timestamp(NaiveDateTime),
+    /// Always in UTC, offsets are applied when the value is parsed.
+    TimestampTz(DateTime<Utc>),
+    Interval(Interval),
+    Blob(Vec<u8>),
+    Json(serde_json::Value),
+    Uuid(uuid::Uuid),
+}
+
+impl Value {
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Value::Int(_) | Value::Float(_) | Value::Decimal(_))
+    }
+
+    /// Compare two values of the same kind, numbers of any kind can be mixed.
+    /// Returns `None` for nulls and values of different kinds.
+    pub fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+            (Value::Float(a), Value::Float(b)) => Some(a.total_cmp(b)),
+            (Value::Int(a), Value::Float(b)) => Some((*a as f64).total_cmp(b)),
+            (Value::Float(a), Value::Int(b)) => Some(a.total_cmp(&(*b as f64))),
+            (Value::Decimal(a), Value::Decimal(b)) => Some(a.cmp(b)),
+            (Value::Decimal(a), Value::Int(b)) => Some(a.cmp(&Decimal::from(*b))),
+            (Value::Int(a), Value::Decimal(b)) => Some(Decimal::from(*a).cmp(b)),
+            (Value::Decimal(a), Value::Float(b)) => Some(a.to_f64()?.total_cmp(b)),
+            (Value::Float(a), Value::Decimal(b)) => Some(a.total_cmp(&b.to_f64()?)),
+            (Value::Text(a), Value::Text(b)) => Some(a.cmp(b)),
+            (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+            (Value::Date(a), Value::Date(b)) => Some(a.cmp(b)),
+            (Value::Time(a), Value::Time(b)) => Some(a.cmp(b)),
+            (Value::Timestamp(a), Value::Timestamp(b)) => Some(a.cmp(b)),
+            (Value::TimestampTz(a), Value::TimestampTz(b)) => Some(a.cmp(b)),
+            // Like `1 day` and `24 hours`, intervals of the same length are equal.
+            (Value::Interval(a), Value::Interval(b)) => {
+                Some(a.total_micros().cmp(&b.total_micros()))
+            }
+            (Value::Blob(a), Value::Blob(b)) => Some(a.cmp(b)),
+            (Value::Json(a), Value::Json(b)) => Some(json::compare(a, b)),
+            (Value::Uuid(a), Value::Uuid(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+// Floats are always finite, see `DataType::parse_value`, so equality is total.
+impl Eq for Value {}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Value::Null => {}
+            Value::Int(v) => v.hash(state),
+            // `0.0` and `-0.0` are equal so they must hash the same.
+            Value::Float(v) => (if *v == 0.0 { 0.0 } else { *v }).to_bits().hash(state),
+            // Decimals hash the same regardless of their scale, like `1.0` and `1.00`.
+            Value::Decimal(v) => v.hash(state),
+            Value::Text(v) => v.hash(state),
+            Value::Bool(v) => v.hash(state),
+            Value::Date(v) => v.hash(state),
+            Value::Time(v) => v.hash(state),
+            Value::Timestamp(v) => v.hash(state),
+            Value::TimestampTz(v) => v.hash(state),
+            Value::Interval(v) => v.hash(state),
+            Value::Blob(v) => v.hash(state),
+            // Objects are sorted by key so equal documents have the same text.
+            Value::Json(v) => v.to_string().hash(state),
+            Value::Uuid(v) => v.hash(state),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "NULL"),
+            Value::Int(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{}", v),
+            Value::Decimal(v) => write!(f, "{}", v),
+            Value::Text(v) => write!(f, "{}", v),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Date(v) => write!(f, "{}", v),
+            Value::Time(v) => write!(f, "{}", v),
+            Value::Timestamp(v) => write!(f, "{}", v),
+            Value::TimestampTz(v) => write!(f, "{}+00:00", v.naive_utc()),
+            Value::Interval(v) => write!(f, "{}", v),
+            Value::Blob(v) => write!(f, "\\x{}", hex::encode(v)),
+            Value::Json(v) => write!(f, "{}", v),
+            Value::Uuid(v) => write!(f, "{}", v.hyphenated()),
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Null => serializer.serialize_none(),
+            Value::Int(v) => serializer.serialize_i64(*v),
+            Value::Float(v) => serializer.serialize_f64(*v),
+            Value::Text(v) => serializer.serialize_str(v),
+            Value::Bool(v) => serializer.serialize_bool(*v),
+            Value::Json(v) => v.serialize(serializer),
+            Value::Uuid(v) => serializer.serialize_str(v.simple().encode_lower(&mut [0; 32])),
+            Value::Decimal(_)
+            | Value::Date(_)
+            | Value::Time(_)
+            | Value::Timestamp(_)
+            | Value::TimestampTz(_)
+            | Value::Interval(_)
+            | Value::Blob(_) => serializer.collect_str(self),
+        }
+    }
+}
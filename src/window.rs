@@ -0,0 +1,306 @@
+//! Window functions, computed over the rows of a `SELECT` once they are
+//! filtered and grouped, right before they are projected.
+//!
+//! The executor replaces each window function of the projection with a
+//! column holding its value, see [`extract`], so the rest of the projection
+//! is evaluated like any other expression.
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    ops::Range,
+};
+
+use crate::{
+    ast::{Expr, FrameBound, WindowSpec},
+    eval::{self, EvalError, EvalResult, Scope},
+    executor::compare_keys,
+    table::Row,
+    value::Value,
+};
+
+/// A row the window functions are computed for, along with its group when
+/// the query is grouped.
+pub type Source<'r> = (Row, Option<&'r [Row]>);
+
+/// Replace the window functions of `expr` with references to the columns
+/// holding their values, each distinct function is added to `windows` once.
+pub fn extract(expr: &Expr, windows: &mut Vec<Expr>) -> Expr {
+    let mut extract = |expr: &Expr| Box::new(extract(expr, windows));
+
+    match expr {
+        Expr::Window { .. } => {
+            if !windows.contains(expr) {
+                windows.push(expr.clone());
+            }
+            Expr::Identifier(expr.to_string())
+        }
+        Expr::BinaryOp { left, op, right } => Expr::BinaryOp {
+            left: extract(left),
+            op: *op,
+            right: extract(right),
+        },
+        Expr::UnaryOp { op, expr } => Expr::UnaryOp {
+            op: *op,
+            expr: extract(expr),
+        },
+        Expr::IsNull { expr, negated } => Expr::IsNull {
+            expr: extract(expr),
+            negated: *negated,
+        },
+        Expr::InSubquery {
+            expr,
+            subquery,
+            negated,
+        } => Expr::InSubquery {
+            expr: extract(expr),
+            subquery: subquery.clone(),
+            negated: *negated,
+        },
+        Expr::Nested(expr) => Expr::Nested(extract(expr)),
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => Expr::InList {
+            expr: extract(expr),
+            list: list.iter().map(|item| *extract(item)).collect(),
+            negated: *negated,
+        },
+        Expr::Between {
+            expr,
+            low,
+            high,
+            negated,
+        } => Expr::Between {
+            expr: extract(expr),
+            low: extract(low),
+            high: extract(high),
+            negated: *negated,
+        },
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            negated,
+            case_insensitive,
+        } => Expr::Like {
+            expr: extract(expr),
+            pattern: extract(pattern),
+            escape: escape.as_deref().map(&mut extract),
+            negated: *negated,
+            case_insensitive: *case_insensitive,
+        },
+        Expr::Cast { expr, data_type } => Expr::Cast {
+            expr: extract(expr),
+            data_type: data_type.clone(),
+        },
+        Expr::Function {
+            name,
+            args,
+            distinct,
+        } if !expr.is_aggregate() => Expr::Function {
+            name: name.clone(),
+            args: args.iter().map(|arg| *extract(arg)).collect(),
+            distinct: *distinct,
+        },
+        Expr::Case {
+            operand,
+            branches,
+            else_result,
+        } => Expr::Case {
+            operand: operand.as_deref().map(&mut extract),
+            branches: branches
+                .iter()
+                .map(|(when, then)| (*extract(when), *extract(then)))
+                .collect(),
+            else_result: else_result.as_deref().map(&mut extract),
+        },
+        // Window functions nested in aggregates are left in place, they are
+        // rejected when the aggregate is validated.
+        _ => expr.clone(),
+    }
+}
+
+/// Check a window function along with its keys, aggregates are only allowed
+/// in the arguments and keys of a grouped query.
+pub fn validate(scope: &Scope, expr: &Expr, grouped: bool) -> EvalResult<()> {
+    let Expr::Window { name, args, window } = expr else {
+        return scope.validate(expr, grouped);
+    };
+
+    for key in window.exprs() {
+        scope.validate(key, grouped)?;
+    }
+
+    let arity = match name.to_uppercase().as_str() {
+        "ROW_NUMBER" | "RANK" | "DENSE_RANK" => (0..=0, "no arguments"),
+        "LAG" | "LEAD" => (1..=3, "between 1 and 3 arguments"),
+        "FIRST_VALUE" | "LAST_VALUE" => (1..=1, "a single argument"),
+        // Any aggregate runs over the frame of the row.
+        _ => {
+            let function = Expr::Function {
+                name: name.clone(),
+                args: args.clone(),
+                distinct: false,
+            };
+            return scope.validate(&function, true);
+        }
+    };
+    if !arity.0.contains(&args.len()) {
+        return Err(EvalError::InvalidArgument(format!(
+            "{} expects {}",
+            name, arity.1
+        )));
+    }
+
+    args.iter().try_for_each(|arg| scope.validate(arg, grouped))
+}
+
+/// Compute a window function for every source, the values are in the same
+/// order as the sources.
+pub fn evaluate(scope: &Scope, expr: &Expr, sources: &[Source]) -> EvalResult<Vec<Value>> {
+    let Expr::Window { name, args, window } = expr else {
+        unreachable!("only window functions are extracted");
+    };
+    let name = name.to_uppercase();
+    // `COUNT(*)` has no argument to evaluate, it counts the rows of the frame.
+    let args = match args.as_slice() {
+        [Expr::Wildcard] => &[],
+        args => args,
+    };
+
+    let mut partitions: Vec<Vec<usize>> = Vec::new();
+    let mut partition_pos: HashMap<Vec<Value>, usize> = HashMap::new();
+    for (i, (row, group)) in sources.iter().enumerate() {
+        let key = window
+            .partition_by
+            .iter()
+            .map(|expr| Ok(scope.eval(expr, row, *group)?.0))
+            .collect::<EvalResult<Vec<_>>>()?;
+        match partition_pos.entry(key) {
+            Entry::Occupied(pos) => partitions[*pos.get()].push(i),
+            Entry::Vacant(pos) => {
+                pos.insert(partitions.len());
+                partitions.push(vec![i]);
+            }
+        }
+    }
+
+    let mut values = vec![Value::Null; sources.len()];
+    for partition in partitions {
+        // The sort keys and arguments of each row, sorted by the keys.
+        let mut rows = partition
+            .into_iter()
+            .map(|i| {
+                let (row, group) = &sources[i];
+                let eval = |expr| scope.eval(expr, row, *group);
+                let keys = window
+                    .order_by
+                    .iter()
+                    .map(|key| eval(&key.expr))
+                    .collect::<EvalResult<Vec<_>>>()?;
+                let args = args.iter().map(eval).collect::<EvalResult<Vec<_>>>()?;
+                Ok((keys, i, args))
+            })
+            .collect::<EvalResult<Vec<_>>>()?;
+        rows.sort_by(|(a, ..), (b, ..)| compare_keys(a, b, &window.order_by));
+
+        // Rows sorted the same are peers, they share their rank.
+        let len = rows.len();
+        let is_peer =
+            |a: usize, b: usize| compare_keys(&rows[a].0, &rows[b].0, &window.order_by).is_eq();
+        let mut first_peer = vec![0; len];
+        let mut dense_rank = vec![1; len];
+        for pos in 1..len {
+            if is_peer(pos - 1, pos) {
+                first_peer[pos] = first_peer[pos - 1];
+                dense_rank[pos] = dense_rank[pos - 1];
+            } else {
+                first_peer[pos] = pos;
+                dense_rank[pos] = dense_rank[pos - 1] + 1;
+            }
+        }
+        let mut last_peer = (0..len).collect::<Vec<_>>();
+        for pos in (1..len).rev() {
+            if is_peer(pos - 1, pos) {
+                last_peer[pos - 1] = last_peer[pos];
+            }
+        }
+
+        let arg = |pos: usize| rows[pos].2[0].0.clone();
+        for pos in 0..len {
+            let value = match name.as_str() {
+                "ROW_NUMBER" => Value::Int(pos as i64 + 1),
+                "RANK" => Value::Int(first_peer[pos] as i64 + 1),
+                "DENSE_RANK" => Value::Int(dense_rank[pos]),
+                "LAG" | "LEAD" => {
+                    let args = &rows[pos].2;
+                    let offset = match args.get(1).map(|(offset, _)| offset) {
+                        None => 1,
+                        Some(Value::Int(offset)) if *offset >= 0 => *offset as usize,
+                        Some(offset) => {
+                            return Err(EvalError::InvalidArgument(format!(
+                                "{} expects a non negative offset, found {}",
+                                name, offset
+                            )))
+                        }
+                    };
+                    let target = match name.as_str() {
+                        "LAG" => pos.checked_sub(offset),
+                        _ => pos.checked_add(offset).filter(|target| *target < len),
+                    };
+                    match (target, args.get(2)) {
+                        (Some(target), _) => arg(target),
+                        (None, Some((default, _))) => default.clone(),
+                        (None, None) => Value::Null,
+                    }
+                }
+                name => {
+                    let frame = frame(window, pos, len, last_peer[pos]);
+                    match name {
+                        _ if frame.is_empty() && name != "COUNT" => Value::Null,
+                        "FIRST_VALUE" => arg(frame.start),
+                        "LAST_VALUE" => arg(frame.end - 1),
+                        "COUNT" if args.is_empty() => Value::Int(frame.len() as i64),
+                        _ => {
+                            let dtype = rows[pos].2[0].1;
+                            let values = frame.map(arg).filter(|value| !value.is_null());
+                            eval::aggregate(name, values.collect(), dtype)?.0
+                        }
+                    }
+                }
+            };
+            values[rows[pos].1] = value;
+        }
+    }
+
+    Ok(values)
+}
+
+/// Positions of the rows in the frame of the row at `pos`, `last_peer` is
+/// the last row sorted the same as it.
+fn frame(window: &WindowSpec, pos: usize, len: usize, last_peer: usize) -> Range<usize> {
+    let Some(frame) = &window.frame else {
+        return match window.order_by.is_empty() {
+            true => 0..len,
+            false => 0..last_peer + 1,
+        };
+    };
+
+    let start = match frame.start {
+        FrameBound::UnboundedPreceding => 0,
+        FrameBound::Preceding(n) => pos.saturating_sub(n),
+        FrameBound::CurrentRow => pos,
+        FrameBound::Following(n) => pos.saturating_add(n),
+        FrameBound::UnboundedFollowing => len,
+    };
+    let end = match frame.end {
+        FrameBound::UnboundedPreceding => 0,
+        FrameBound::Preceding(n) => (pos + 1).saturating_sub(n),
+        FrameBound::CurrentRow => pos + 1,
+        FrameBound::Following(n) => pos.saturating_add(n).saturating_add(1),
+        FrameBound::UnboundedFollowing => len,
+    };
+
+    start.min(len)..end.min(len)
+}